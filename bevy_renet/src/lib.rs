@@ -2,7 +2,7 @@ pub use renet;
 
 use bevy::prelude::*;
 
-use renet::{RenetClient, RenetServer, ServerEvent};
+use renet::{ClientEvent, RenetClient, RenetServer, ServerEvent};
 
 #[cfg(feature = "transport")]
 pub mod transport;
@@ -29,12 +29,17 @@ impl RenetServerPlugin {
 
 impl Plugin for RenetClientPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<Events<ClientEvent>>();
         app.add_systems(PreUpdate, Self::update_system.run_if(resource_exists::<RenetClient>()));
     }
 }
 
 impl RenetClientPlugin {
-    pub fn update_system(mut client: ResMut<RenetClient>, time: Res<Time>) {
+    pub fn update_system(mut client: ResMut<RenetClient>, time: Res<Time>, mut client_events: EventWriter<ClientEvent>) {
         client.update(time.delta());
+
+        while let Some(event) = client.get_event() {
+            client_events.send(event);
+        }
     }
 }
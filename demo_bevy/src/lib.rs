@@ -62,20 +62,20 @@ impl From<ClientChannel> for u8 {
 impl ClientChannel {
     pub fn channels_config() -> Vec<ChannelConfig> {
         vec![
-            ChannelConfig {
-                channel_id: Self::Input.into(),
-                max_memory_usage_bytes: 5 * 1024 * 1024,
-                send_type: SendType::ReliableOrdered {
+            ChannelConfig::new(
+                Self::Input.into(),
+                5 * 1024 * 1024,
+                SendType::ReliableOrdered {
                     resend_time: Duration::ZERO,
                 },
-            },
-            ChannelConfig {
-                channel_id: Self::Command.into(),
-                max_memory_usage_bytes: 5 * 1024 * 1024,
-                send_type: SendType::ReliableOrdered {
+            ),
+            ChannelConfig::new(
+                Self::Command.into(),
+                5 * 1024 * 1024,
+                SendType::ReliableOrdered {
                     resend_time: Duration::ZERO,
                 },
-            },
+            ),
         ]
     }
 }
@@ -92,18 +92,14 @@ impl From<ServerChannel> for u8 {
 impl ServerChannel {
     pub fn channels_config() -> Vec<ChannelConfig> {
         vec![
-            ChannelConfig {
-                channel_id: Self::NetworkedEntities.into(),
-                max_memory_usage_bytes: 10 * 1024 * 1024,
-                send_type: SendType::Unreliable,
-            },
-            ChannelConfig {
-                channel_id: Self::ServerMessages.into(),
-                max_memory_usage_bytes: 10 * 1024 * 1024,
-                send_type: SendType::ReliableOrdered {
+            ChannelConfig::new(Self::NetworkedEntities.into(), 10 * 1024 * 1024, SendType::Unreliable),
+            ChannelConfig::new(
+                Self::ServerMessages.into(),
+                10 * 1024 * 1024,
+                SendType::ReliableOrdered {
                     resend_time: Duration::from_millis(200),
                 },
-            },
+            ),
         ]
     }
 }
@@ -113,6 +109,7 @@ pub fn connection_config() -> ConnectionConfig {
         available_bytes_per_tick: 1024 * 1024,
         client_channels_config: ClientChannel::channels_config(),
         server_channels_config: ServerChannel::channels_config(),
+        ..Default::default()
     }
 }
 
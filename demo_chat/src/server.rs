@@ -27,13 +27,13 @@ impl ChatServer {
     pub fn new(host_username: String) -> Self {
         let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
         let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
-        let server_config = ServerConfig {
+        let server_config = ServerConfig::new(
             current_time,
-            max_clients: 64,
-            protocol_id: PROTOCOL_ID,
-            public_addresses: vec![socket.local_addr().unwrap()],
-            authentication: ServerAuthentication::Unsecure,
-        };
+            64,
+            PROTOCOL_ID,
+            vec![socket.local_addr().unwrap()],
+            ServerAuthentication::Unsecure,
+        );
 
         let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
 
@@ -68,13 +68,7 @@ fn server(public_addr: SocketAddr) {
     let mut server: RenetServer = RenetServer::new(connection_config);
 
     let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
-    let server_config = ServerConfig {
-        current_time,
-        max_clients: 64,
-        protocol_id: PROTOCOL_ID,
-        public_addresses: vec![public_addr],
-        authentication: ServerAuthentication::Unsecure,
-    };
+    let server_config = ServerConfig::new(current_time, 64, PROTOCOL_ID, vec![public_addr], ServerAuthentication::Unsecure);
     let socket: UdpSocket = UdpSocket::bind(public_addr).unwrap();
 
     let mut transport = NetcodeServerTransport::new(server_config, socket).unwrap();
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::channel::reliable::SendChannelReliable;
+
+/// Maximum size of the messages a block transfer is split into.
+pub(crate) const BLOCK_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Handle of a block transfer started with [RenetClient::send_block][crate::RenetClient::send_block],
+/// to follow its progress and pause or cancel it. Cheap to clone and can be sent to other threads.
+/// The state is refreshed in every update of the connection.
+#[derive(Debug, Clone)]
+pub struct BlockHandle {
+    state: Arc<Mutex<BlockState>>,
+}
+
+#[derive(Debug)]
+struct BlockState {
+    total_bytes: usize,
+    queued_bytes: usize,
+    acked_bytes: usize,
+    paused: bool,
+    cancelled: bool,
+    backpressured: bool,
+    started_at: Duration,
+    current_time: Duration,
+}
+
+impl BlockHandle {
+    fn state(&self) -> MutexGuard<'_, BlockState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Returns the size in bytes of the data being transferred.
+    pub fn total_bytes(&self) -> usize {
+        self.state().total_bytes
+    }
+
+    /// Returns the bytes queued in the channel, sent or waiting to be sent.
+    pub fn queued_bytes(&self) -> usize {
+        self.state().queued_bytes
+    }
+
+    /// Returns the bytes acknowledged by the peer.
+    pub fn acked_bytes(&self) -> usize {
+        self.state().acked_bytes
+    }
+
+    /// Returns the fraction of the data acknowledged by the peer, between 0.0 and 1.0.
+    pub fn progress(&self) -> f64 {
+        let state = self.state();
+        if state.total_bytes == 0 {
+            return 1.0;
+        }
+        state.acked_bytes as f64 / state.total_bytes as f64
+    }
+
+    /// Returns true once all the data was acknowledged by the peer.
+    pub fn is_complete(&self) -> bool {
+        let state = self.state();
+        state.acked_bytes == state.total_bytes
+    }
+
+    /// Returns the bytes acknowledged per second since the transfer started.
+    pub fn transfer_rate(&self) -> f64 {
+        let state = self.state();
+        let elapsed = (state.current_time - state.started_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        state.acked_bytes as f64 / elapsed
+    }
+
+    /// Returns the estimated time until all the data is acknowledged, projected with the [transfer rate][BlockHandle::transfer_rate].
+    /// Returns None if the transfer was cancelled or nothing was acknowledged yet to measure the rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.transfer_rate();
+        let state = self.state();
+        if state.acked_bytes == state.total_bytes {
+            return Some(Duration::ZERO);
+        }
+        if state.cancelled || !rate.is_normal() {
+            return None;
+        }
+        Some(Duration::from_secs_f64((state.total_bytes - state.acked_bytes) as f64 / rate))
+    }
+
+    /// Stops queuing the data in the channel, the chunks already queued are still delivered.
+    pub fn pause(&self) {
+        self.state().paused = true;
+    }
+
+    /// Resumes queuing the data in the channel in the next update.
+    pub fn resume(&self) {
+        self.state().paused = false;
+    }
+
+    /// Returns true if the transfer was paused with [BlockHandle::pause].
+    pub fn is_paused(&self) -> bool {
+        self.state().paused
+    }
+
+    /// Returns true if the last update couldn't queue the next chunk because the channel was full,
+    /// the transfer then waits for the peer to acknowledge the chunks in flight.
+    pub fn is_backpressured(&self) -> bool {
+        self.state().backpressured
+    }
+
+    /// Stops the transfer, the data not yet queued is discarded.
+    /// The chunks already queued in the reliable channel cannot be removed and are still delivered,
+    /// so the peer receives a prefix of the data.
+    pub fn cancel(&self) {
+        self.state().cancelled = true;
+    }
+
+    /// Returns true if the transfer was cancelled with [BlockHandle::cancel].
+    pub fn is_cancelled(&self) -> bool {
+        self.state().cancelled
+    }
+}
+
+/// The sending side of a block transfer, owned by the connection.
+#[derive(Debug)]
+pub(crate) struct BlockTransfer {
+    pub channel_id: u8,
+    data: Bytes,
+    offset: usize,
+    chunk_size: usize,
+    // Chunks queued and not yet acked, by message id
+    in_flight: BTreeMap<u64, usize>,
+    handle: BlockHandle,
+}
+
+impl BlockTransfer {
+    pub fn new(channel_id: u8, data: Bytes, chunk_size: usize, current_time: Duration) -> Self {
+        let handle = BlockHandle {
+            state: Arc::new(Mutex::new(BlockState {
+                total_bytes: data.len(),
+                queued_bytes: 0,
+                acked_bytes: 0,
+                paused: false,
+                cancelled: false,
+                backpressured: false,
+                started_at: current_time,
+                current_time,
+            })),
+        };
+
+        Self {
+            channel_id,
+            data,
+            offset: 0,
+            chunk_size: chunk_size.max(1),
+            in_flight: BTreeMap::new(),
+            handle,
+        }
+    }
+
+    pub fn handle(&self) -> BlockHandle {
+        self.handle.clone()
+    }
+
+    /// Queues the next chunks in the channel while it has memory available,
+    /// returns the chunks queued with their message ids.
+    pub fn queue_chunks(&mut self, channel: &mut SendChannelReliable, current_time: Duration) -> Vec<(u64, Bytes)> {
+        let mut state = self.handle.state();
+        state.current_time = current_time;
+        state.backpressured = false;
+        if state.paused || state.cancelled {
+            return vec![];
+        }
+
+        let mut queued = vec![];
+        while self.offset < self.data.len() {
+            let len = self.chunk_size.min(self.data.len() - self.offset);
+            if !channel.can_send_message(len) {
+                state.backpressured = true;
+                break;
+            }
+
+            let chunk = self.data.slice(self.offset..self.offset + len);
+            let Ok(message_id) = channel.send_message(chunk.clone()) else {
+                break;
+            };
+            self.offset += len;
+            state.queued_bytes += len;
+            self.in_flight.insert(message_id, len);
+            queued.push((message_id, chunk));
+        }
+
+        queued
+    }
+
+    /// Returns true if the message was a chunk of this transfer.
+    pub fn chunk_acked(&mut self, message_id: u64) -> bool {
+        match self.in_flight.remove(&message_id) {
+            Some(len) => {
+                self.handle.state().acked_bytes += len;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if nothing more will be queued or acked for the transfer.
+    pub fn is_finished(&self) -> bool {
+        self.in_flight.is_empty() && (self.offset == self.data.len() || self.handle.is_cancelled())
+    }
+}
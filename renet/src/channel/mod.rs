@@ -7,10 +7,21 @@ use std::time::Duration;
 pub(crate) use slice_constructor::SliceConstructor;
 
 /// Delivery garantee of a channel
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SendType {
     // Messages can be lost or received out of order.
     Unreliable,
+    /// Messages can be lost or received out of order, but are never received more than once.
+    /// Each message is sent `copies` times in different packets, increasing the chance it arrives
+    /// without the cost of retransmission of reliable channels. Also uses more bandwidth.
+    /// Must send at least one copy.
+    UnreliableDeduplicated {
+        copies: u8,
+    },
+    /// Messages can be lost, but are never received out of order or more than once.
+    /// Messages older than the last one received are discarded, useful for state updates
+    /// where only the most recent one matters.
+    UnreliableSequenced,
     /// Messages are guaranteed to be received and in the same order they were sent.
     ReliableOrdered {
         resend_time: Duration,
@@ -21,9 +32,21 @@ pub enum SendType {
     },
 }
 
+/// Maximum age of the messages received in an unreliable channel, older messages are discarded
+/// and counted as stale, avoiding acting on outdated state after reordering or while waiting to be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageAge {
+    /// Number of message ids that a message can be behind the newest one received.
+    /// Only applied to [SendType::UnreliableDeduplicated] and [SendType::UnreliableSequenced],
+    /// since plain unreliable messages are sent without ids.
+    Messages(u64),
+    /// Time that a message can wait in the receive queue, or arrive after a newer message was received.
+    Time(Duration),
+}
+
 /// Configuration of a channel for a server or client
 /// Channels are unilateral and message based.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChannelConfig {
     /// Channel identifier, must be unique within its own list,
     /// but it can be repeated between the server and client lists.
@@ -32,10 +55,41 @@ pub struct ChannelConfig {
     /// Unreliable channels will drop new messages when this value is reached
     /// Reliable channels will cause a disconnect when this value is reached
     pub max_memory_usage_bytes: usize,
+    /// Maximum number of sliced messages sent at the same time in reliable channels,
+    /// the next ones are queued until a sliced message is completely acknowledged.
+    /// Limits the memory used for reassembly by the receiver, with 1 the sliced messages
+    /// are transferred one after the other instead of interleaved.
+    /// Ignored by unreliable channels, since their messages are sent in a single tick.
+    /// None: all sliced messages are sent at the same time.
+    pub max_concurrent_block_transfers: Option<usize>,
+    /// Maximum age of the messages received in unreliable channels, measured in message ids or in time,
+    /// older messages are discarded on arrival or from the receive queue and counted as stale.
+    /// Ignored by reliable channels.
+    /// None: messages are delivered regardless of their age.
+    pub max_message_age: Option<MessageAge>,
+    /// Number of messages the send queue of unreliable channels is allocated for when the connection starts,
+    /// avoiding reallocations while it grows in the first moments of the connection.
+    /// Ignored by reliable channels, since their messages are kept in an ordered map that cannot be pre-allocated.
+    /// None: the queue grows as messages are sent.
+    pub initial_capacity: Option<usize>,
     /// Delivery garantee of the channel
     pub send_type: SendType,
 }
 
+impl ChannelConfig {
+    /// Creates a channel configuration without the optional limits, set the other fields to enable them.
+    pub fn new(channel_id: u8, max_memory_usage_bytes: usize, send_type: SendType) -> Self {
+        Self {
+            channel_id,
+            max_memory_usage_bytes,
+            max_concurrent_block_transfers: None,
+            max_message_age: None,
+            initial_capacity: None,
+            send_type,
+        }
+    }
+}
+
 /// Utility enumerator when using the default channels configuration.
 /// The default configuration has 3 channels: unreliable, reliable ordered, and reliable unordered.
 pub enum DefaultChannel {
@@ -57,25 +111,21 @@ impl From<DefaultChannel> for u8 {
 impl DefaultChannel {
     pub fn config() -> Vec<ChannelConfig> {
         vec![
-            ChannelConfig {
-                channel_id: 0,
-                max_memory_usage_bytes: 5 * 1024 * 1024,
-                send_type: SendType::Unreliable,
-            },
-            ChannelConfig {
-                channel_id: 1,
-                max_memory_usage_bytes: 5 * 1024 * 1024,
-                send_type: SendType::ReliableUnordered {
+            ChannelConfig::new(0, 5 * 1024 * 1024, SendType::Unreliable),
+            ChannelConfig::new(
+                1,
+                5 * 1024 * 1024,
+                SendType::ReliableUnordered {
                     resend_time: Duration::from_millis(300),
                 },
-            },
-            ChannelConfig {
-                channel_id: 2,
-                max_memory_usage_bytes: 5 * 1024 * 1024,
-                send_type: SendType::ReliableOrdered {
+            ),
+            ChannelConfig::new(
+                2,
+                5 * 1024 * 1024,
+                SendType::ReliableOrdered {
                     resend_time: Duration::from_millis(300),
                 },
-            },
+            ),
         ]
     }
 }
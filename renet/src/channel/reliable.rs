@@ -11,6 +11,12 @@ use crate::{
     packet::{Packet, Slice, SLICE_SIZE},
 };
 
+#[cfg(feature = "async")]
+use crate::{
+    error::DisconnectReason,
+    message_ack::{message_ack_channel, AckError, AckNotifier, MessageAcked},
+};
+
 #[derive(Debug)]
 enum UnackedMessage {
     Small {
@@ -35,6 +41,9 @@ pub struct SendChannelReliable {
     resend_time: Duration,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
+    max_concurrent_block_transfers: Option<usize>,
+    #[cfg(feature = "async")]
+    ack_notifiers: HashMap<u64, AckNotifier>,
 }
 
 #[derive(Debug)]
@@ -57,8 +66,16 @@ pub struct ReceiveChannelReliable {
 }
 
 impl UnackedMessage {
+    /// Sliced messages are considered sent if any of its slices was sent.
+    fn was_sent(&self) -> bool {
+        match self {
+            UnackedMessage::Small { last_sent, .. } => last_sent.is_some(),
+            UnackedMessage::Sliced { last_sent, .. } => last_sent.iter().any(Option::is_some),
+        }
+    }
+
     fn new_sliced(payload: Bytes) -> Self {
-        let num_slices = (payload.len() + SLICE_SIZE - 1) / SLICE_SIZE;
+        let num_slices = payload.len().div_ceil(SLICE_SIZE);
 
         Self::Sliced {
             message: payload,
@@ -80,6 +97,32 @@ impl SendChannelReliable {
             resend_time,
             max_memory_usage_bytes,
             memory_usage_bytes: 0,
+            max_concurrent_block_transfers: None,
+            #[cfg(feature = "async")]
+            ack_notifiers: HashMap::new(),
+        }
+    }
+
+    /// Limits the number of sliced messages sent at the same time, the next ones wait until one is acked.
+    pub fn with_max_concurrent_block_transfers(mut self, max_concurrent_block_transfers: Option<usize>) -> Self {
+        if let Some(max) = max_concurrent_block_transfers {
+            assert!(max > 0, "channel {} must allow at least one block transfer", self.channel_id);
+        }
+        self.max_concurrent_block_transfers = max_concurrent_block_transfers;
+        self
+    }
+
+    /// Returns the number of sliced messages that are being sent.
+    pub fn active_block_transfers(&self) -> usize {
+        let sliced_messages = self
+            .unacked_messages
+            .values()
+            .filter(|message| matches!(message, UnackedMessage::Sliced { .. }))
+            .count();
+
+        match self.max_concurrent_block_transfers {
+            Some(max) => sliced_messages.min(max),
+            None => sliced_messages,
         }
     }
 
@@ -87,6 +130,53 @@ impl SendChannelReliable {
         self.max_memory_usage_bytes - self.memory_usage_bytes
     }
 
+    /// Returns the number of messages that were never sent.
+    pub fn unsent_messages(&self) -> usize {
+        self.unacked_messages.values().filter(|message| !message.was_sent()).count()
+    }
+
+    /// Returns the number of messages that were sent, but not yet acked.
+    pub fn in_flight_messages(&self) -> usize {
+        self.unacked_messages.values().filter(|message| message.was_sent()).count()
+    }
+
+    /// Returns the bytes that were never sent from the message and the ones queued before it,
+    /// or None if the message was already acked.
+    pub fn unsent_bytes_until(&self, message_id: u64) -> Option<usize> {
+        if !self.unacked_messages.contains_key(&message_id) {
+            return None;
+        }
+
+        let unsent_bytes = self
+            .unacked_messages
+            .range(..=message_id)
+            .map(|(_, unacked_message)| match unacked_message {
+                UnackedMessage::Small { message, last_sent } => match last_sent {
+                    Some(_) => 0,
+                    None => message.len(),
+                },
+                UnackedMessage::Sliced { message, last_sent, .. } => last_sent
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, last_sent)| last_sent.is_none())
+                    .map(|(slice_index, _)| SLICE_SIZE.min(message.len() - slice_index * SLICE_SIZE))
+                    .sum(),
+            })
+            .sum();
+
+        Some(unsent_bytes)
+    }
+
+    /// Returns true if every message sent was acked.
+    pub fn is_drained(&self) -> bool {
+        self.unacked_messages.is_empty()
+    }
+
+    /// Sliced messages are sent over multiple ticks, so messages are only limited by the channel memory.
+    pub fn max_message_size(&self) -> usize {
+        self.max_memory_usage_bytes
+    }
+
     pub fn can_send_message(&self, size_bytes: usize) -> bool {
         size_bytes + self.memory_usage_bytes <= self.max_memory_usage_bytes
     }
@@ -100,6 +190,7 @@ impl SendChannelReliable {
 
         let mut small_messages: Vec<(u64, Bytes)> = vec![];
         let mut small_messages_bytes = 0;
+        let mut block_transfers = 0;
 
         'messages: for (&message_id, unacked_message) in self.unacked_messages.iter_mut() {
             match unacked_message {
@@ -143,6 +234,12 @@ impl SendChannelReliable {
                     next_slice_to_send,
                     ..
                 } => {
+                    // Messages are iterated by id, so the oldest sliced messages are sent first
+                    if self.max_concurrent_block_transfers.is_some_and(|max| block_transfers >= max) {
+                        continue;
+                    }
+                    block_transfers += 1;
+
                     let start_index = *next_slice_to_send;
                     for i in 0..*num_slices {
                         if *available_bytes < SLICE_SIZE as u64 {
@@ -201,7 +298,8 @@ impl SendChannelReliable {
         packets
     }
 
-    pub fn send_message(&mut self, message: Bytes) -> Result<(), ChannelError> {
+    /// Returns the id assigned to the message.
+    pub fn send_message(&mut self, message: Bytes) -> Result<u64, ChannelError> {
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             return Err(ChannelError::ReliableChannelMaxMemoryReached);
         }
@@ -213,25 +311,63 @@ impl SendChannelReliable {
             UnackedMessage::Small { message, last_sent: None }
         };
 
-        self.unacked_messages.insert(self.next_reliable_message_id, unacked_message);
+        let message_id = self.next_reliable_message_id;
+        self.unacked_messages.insert(message_id, unacked_message);
         self.next_reliable_message_id += 1;
 
-        Ok(())
+        Ok(message_id)
+    }
+
+    #[cfg(feature = "async")]
+    pub fn send_message_with_ack(&mut self, message: Bytes) -> Result<(u64, MessageAcked), ChannelError> {
+        let message_id = self.send_message(message)?;
+
+        let (notifier, acked) = message_ack_channel();
+        self.ack_notifiers.insert(message_id, notifier);
+
+        Ok((message_id, acked))
+    }
+
+    /// Resolves all pending acknowledgement futures with the disconnect reason.
+    #[cfg(feature = "async")]
+    pub fn notify_disconnect(&mut self, reason: DisconnectReason) {
+        for (_, notifier) in self.ack_notifiers.drain() {
+            notifier.notify(Err(AckError::Disconnected(reason)));
+        }
+    }
+
+    /// Resolves all pending acknowledgement futures as removed with the channel.
+    #[cfg(feature = "async")]
+    pub fn notify_channel_removed(&mut self) {
+        for (_, notifier) in self.ack_notifiers.drain() {
+            notifier.notify(Err(AckError::ChannelRemoved));
+        }
     }
 
-    pub fn process_message_ack(&mut self, message_id: u64) {
+    /// Returns true if the message was acked now.
+    pub fn process_message_ack(&mut self, message_id: u64) -> bool {
         if self.unacked_messages.contains_key(&message_id) {
             let unacked_message = self.unacked_messages.remove(&message_id).unwrap();
             let UnackedMessage::Small { message: payload, .. } = unacked_message else {
                 unreachable!("called ack on small message but found sliced");
             };
             self.memory_usage_bytes -= payload.len();
+
+            #[cfg(feature = "async")]
+            if let Some(notifier) = self.ack_notifiers.remove(&message_id) {
+                notifier.notify(Ok(()));
+            }
+
+            return true;
         }
+
+        false
     }
 
-    pub fn process_slice_message_ack(&mut self, message_id: u64, slice_index: usize) {
+    /// Returns true if the message was completely acked with this slice.
+    pub fn process_slice_message_ack(&mut self, message_id: u64, slice_index: usize) -> bool {
         let Some(unacked_message) = self.unacked_messages.get_mut(&message_id) else {
-            return;
+            return false;
         };
 
         let UnackedMessage::Sliced { message, num_slices, num_acked_slices, acked, .. } = unacked_message else {
@@ -239,7 +375,7 @@ impl SendChannelReliable {
         };
 
         if acked[slice_index] {
-            return;
+            return false;
         }
 
         acked[slice_index] = true;
@@ -248,7 +384,16 @@ impl SendChannelReliable {
         if *num_acked_slices == *num_slices {
             self.memory_usage_bytes -= message.len();
             self.unacked_messages.remove(&message_id);
+
+            #[cfg(feature = "async")]
+            if let Some(notifier) = self.ack_notifiers.remove(&message_id) {
+                notifier.notify(Ok(()));
+            }
+
+            return true;
         }
+
+        false
     }
 }
 
@@ -311,6 +456,23 @@ impl ReceiveChannelReliable {
         Ok(())
     }
 
+    /// Returns the bytes reserved for incomplete sliced messages.
+    pub fn reassembly_bytes(&self) -> usize {
+        self.slices.values().map(|slice| slice.num_slices * SLICE_SIZE).sum()
+    }
+
+    /// Returns true if processing the slice completes the reassembly of its message.
+    pub fn completes_message(&self, slice: &Slice) -> bool {
+        if self.messages.contains_key(&slice.message_id) || slice.message_id < self.oldest_pending_message_id {
+            return false;
+        }
+
+        match self.slices.get(&slice.message_id) {
+            Some(slice_constructor) => slice_constructor.completes_with(slice.slice_index),
+            None => slice.num_slices == 1,
+        }
+    }
+
     pub fn process_slice(&mut self, slice: Slice) -> Result<(), ChannelError> {
         if self.messages.contains_key(&slice.message_id) || slice.message_id < self.oldest_pending_message_id {
             // Message already assembled
@@ -340,21 +502,34 @@ impl ReceiveChannelReliable {
         Ok(())
     }
 
+    /// Returns true if there are no messages waiting to be received or reassembled.
+    pub fn is_drained(&self) -> bool {
+        self.messages.is_empty() && self.slices.is_empty()
+    }
+
+    /// Returns true if received messages are waiting for an older message that is still missing.
+    /// Only ordered channels can be blocked.
+    pub fn is_blocked(&self) -> bool {
+        match self.reliable_order {
+            ReliableOrder::Ordered => self
+                .messages
+                .first_key_value()
+                .is_some_and(|(message_id, _)| *message_id != self.oldest_pending_message_id),
+            ReliableOrder::Unordered { .. } => false,
+        }
+    }
+
     pub fn receive_message(&mut self) -> Option<Bytes> {
         match &mut self.reliable_order {
             ReliableOrder::Ordered => {
-                let Some(message) = self.messages.remove(&self.oldest_pending_message_id) else {
-                    return None;
-                };
+                let message = self.messages.remove(&self.oldest_pending_message_id)?;
 
                 self.oldest_pending_message_id += 1;
                 self.memory_usage_bytes -= message.len();
                 Some(message)
             }
             ReliableOrder::Unordered { received_messages, .. } => {
-                let Some((message_id, message)) = self.messages.pop_first() else {
-                    return None;
-                };
+                let (message_id, message) = self.messages.pop_first()?;
 
                 if self.oldest_pending_message_id == message_id {
                     // Remove all next items that could have been received out of order,
@@ -632,4 +807,44 @@ mod tests {
             assert!(len < 1300);
         }
     }
+
+    #[test]
+    fn max_concurrent_block_transfers() {
+        let mut available_bytes = u64::MAX;
+        let mut sequence: u64 = 0;
+        let current_time: Duration = Duration::ZERO;
+        let resend_time = Duration::from_millis(100);
+        let mut send = SendChannelReliable::new(0, resend_time, usize::MAX).with_max_concurrent_block_transfers(Some(1));
+
+        send.send_message(vec![0; SLICE_SIZE * 2].into()).unwrap();
+        send.send_message(vec![1; SLICE_SIZE * 2].into()).unwrap();
+        send.send_message(vec![2, 2].into()).unwrap();
+        assert_eq!(send.active_block_transfers(), 1);
+
+        // Only the slices of the first sliced message, small messages are not held back by the limit
+        let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
+        assert_eq!(packets.len(), 3);
+        let mut small_messages = vec![];
+        for packet in packets.iter() {
+            match packet {
+                Packet::ReliableSlice { slice, .. } => assert_eq!(slice.message_id, 0),
+                Packet::SmallReliable { messages, .. } => small_messages.extend(messages.iter().map(|(message_id, _)| *message_id)),
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(small_messages, vec![2]);
+
+        // Next sliced message starts after the first one is acked
+        send.process_slice_message_ack(0, 0);
+        send.process_slice_message_ack(0, 1);
+        let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
+        assert_eq!(packets.len(), 2);
+        for packet in packets.iter() {
+            let Packet::ReliableSlice { slice, .. } = packet else {
+                unreachable!();
+            };
+            assert_eq!(slice.message_id, 1);
+        }
+        assert_eq!(send.active_block_transfers(), 1);
+    }
 }
@@ -22,6 +22,11 @@ impl SliceConstructor {
         }
     }
 
+    /// Returns true if the slice is the last one missing to complete the message.
+    pub fn completes_with(&self, slice_index: usize) -> bool {
+        self.received.get(slice_index).is_some_and(|received| !received) && self.num_received_slices + 1 == self.num_slices
+    }
+
     pub fn process_slice(&mut self, slice_index: usize, bytes: &[u8]) -> Result<Option<Bytes>, ChannelError> {
         let is_last_slice = slice_index == self.num_slices - 1;
         if is_last_slice {
@@ -1,33 +1,48 @@
 use std::{
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     time::Duration,
 };
 
 use bytes::Bytes;
 
 use crate::{
-    channel::SliceConstructor,
+    channel::{MessageAge, SliceConstructor},
     error::ChannelError,
     packet::{Packet, Slice, SLICE_SIZE},
 };
 
+/// Number of message ids tracked by deduplicated channels,
+/// messages older than this window are considered already received.
+const DEDUPLICATION_WINDOW: usize = 1024;
+
 #[derive(Debug)]
 pub struct SendChannelUnreliable {
     channel_id: u8,
-    unreliable_messages: VecDeque<Bytes>,
-    sliced_message_id: u64,
+    unreliable_messages: VecDeque<(u64, Bytes)>,
+    next_message_id: u64,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
+    deduplicated: bool,
+    copies: u8,
 }
 
 #[derive(Debug)]
 pub struct ReceiveChannelUnreliable {
     channel_id: u8,
-    messages: VecDeque<Bytes>,
+    // Messages waiting to be received, with their id when sent with one and the time they were received
+    messages: VecDeque<(Option<u64>, Duration, Bytes)>,
     slices: BTreeMap<u64, SliceConstructor>,
     slices_last_received: BTreeMap<u64, Duration>,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
+    received_message_ids: Option<BTreeSet<u64>>,
+    sequenced: bool,
+    last_message_id: Option<u64>,
+    last_message_received_at: Duration,
+    max_message_age: Option<MessageAge>,
+    // Sliced messages already counted as stale, so their other slices are not counted again
+    stale_sliced_message_ids: BTreeSet<u64>,
+    stale_messages: u64,
 }
 
 impl SendChannelUnreliable {
@@ -35,10 +50,31 @@ impl SendChannelUnreliable {
         Self {
             channel_id,
             unreliable_messages: VecDeque::new(),
-            sliced_message_id: 0,
+            next_message_id: 0,
             max_memory_usage_bytes,
             memory_usage_bytes: 0,
+            deduplicated: false,
+            copies: 1,
+        }
+    }
+
+    /// Creates a channel where every message is sent with its id in `copies` different packets,
+    /// so the receiver can deliver it only once.
+    pub fn new_deduplicated(channel_id: u8, max_memory_usage_bytes: usize, copies: u8) -> Self {
+        assert!(copies > 0, "deduplicated channel {} must send at least one copy", channel_id);
+        Self {
+            deduplicated: true,
+            copies,
+            ..Self::new(channel_id, max_memory_usage_bytes)
+        }
+    }
+
+    /// Pre-allocates the send queue for the given number of messages.
+    pub fn with_initial_capacity(mut self, initial_capacity: Option<usize>) -> Self {
+        if let Some(initial_capacity) = initial_capacity {
+            self.unreliable_messages.reserve(initial_capacity);
         }
+        self
     }
 
     pub fn can_send_message(&self, size_bytes: usize) -> bool {
@@ -49,21 +85,60 @@ impl SendChannelUnreliable {
         self.max_memory_usage_bytes - self.memory_usage_bytes
     }
 
-    pub fn get_packets_to_send(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64) -> Vec<Packet> {
-        let mut packets: Vec<Packet> = vec![];
-        let mut small_messages: Vec<Bytes> = vec![];
-        let mut small_messages_bytes = 0;
+    /// Returns the number of messages waiting to be sent in the next tick.
+    pub fn unsent_messages(&self) -> usize {
+        self.unreliable_messages.len()
+    }
+
+    /// Returns true if there are no messages waiting to be sent.
+    pub fn is_drained(&self) -> bool {
+        self.unreliable_messages.is_empty()
+    }
 
-        while let Some(message) = self.unreliable_messages.pop_front() {
+    /// Messages, and all their copies, must be sent in a single tick, otherwise they are dropped.
+    pub fn max_message_size(&self, available_bytes_per_tick: u64) -> usize {
+        let max_bytes_per_tick = (available_bytes_per_tick / self.copies as u64).min(usize::MAX as u64) as usize;
+        self.max_memory_usage_bytes.min(max_bytes_per_tick)
+    }
+
+    pub fn get_packets_to_send(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64) -> Vec<Packet> {
+        let mut messages: Vec<(u64, Bytes)> = vec![];
+        while let Some((message_id, message)) = self.unreliable_messages.pop_front() {
             self.memory_usage_bytes -= message.len();
-            if *available_bytes < message.len() as u64 {
+            let message_bytes = (message.len() as u64).saturating_mul(self.copies as u64);
+            if *available_bytes < message_bytes {
                 // Drop message, no available bytes to send
                 continue;
             }
 
-            *available_bytes -= message.len() as u64;
+            *available_bytes -= message_bytes;
+            messages.push((message_id, message));
+        }
+
+        let mut packets: Vec<Packet> = vec![];
+        // Each copy is sent in different packets, so the loss of one packet doesn't drop every copy
+        for _ in 0..self.copies {
+            self.generate_packets(&messages, packet_sequence, &mut packets);
+        }
+
+        packets
+    }
+
+    fn generate_packets(&self, messages: &[(u64, Bytes)], packet_sequence: &mut u64, packets: &mut Vec<Packet>) {
+        let mut small_messages: Vec<(u64, Bytes)> = vec![];
+        let mut small_messages_bytes = 0;
+
+        for (message_id, message) in messages.iter() {
             if message.len() > SLICE_SIZE {
-                let num_slices = (message.len() + SLICE_SIZE - 1) / SLICE_SIZE;
+                // Small messages sent before are flushed first, so the packets keep the order of the messages,
+                // otherwise sequenced channels would discard them as older than the sliced message
+                if !small_messages.is_empty() {
+                    packets.push(self.small_messages_packet(*packet_sequence, std::mem::take(&mut small_messages)));
+                    *packet_sequence += 1;
+                    small_messages_bytes = 0;
+                }
+
+                let num_slices = message.len().div_ceil(SLICE_SIZE);
 
                 for slice_index in 0..num_slices {
                     let start = slice_index * SLICE_SIZE;
@@ -71,7 +146,7 @@ impl SendChannelUnreliable {
                     let payload = message.slice(start..end);
 
                     let slice = Slice {
-                        message_id: self.sliced_message_id,
+                        message_id: *message_id,
                         slice_index,
                         num_slices,
                         payload,
@@ -84,49 +159,62 @@ impl SendChannelUnreliable {
                     });
                     *packet_sequence += 1;
                 }
-
-                self.sliced_message_id += 1;
             } else {
-                let serialized_size = message.len() + octets::varint_len(message.len() as u64);
+                let mut serialized_size = message.len() + octets::varint_len(message.len() as u64);
+                if self.deduplicated {
+                    serialized_size += octets::varint_len(*message_id);
+                }
+
                 if small_messages_bytes + serialized_size > SLICE_SIZE {
-                    packets.push(Packet::SmallUnreliable {
-                        sequence: *packet_sequence,
-                        channel_id: self.channel_id,
-                        messages: std::mem::take(&mut small_messages),
-                    });
+                    packets.push(self.small_messages_packet(*packet_sequence, std::mem::take(&mut small_messages)));
                     *packet_sequence += 1;
                     small_messages_bytes = 0;
                 }
 
                 small_messages_bytes += serialized_size;
-                small_messages.push(message);
+                small_messages.push((*message_id, message.clone()));
             }
         }
 
         // Generate final packet for remaining small messages
         if !small_messages.is_empty() {
-            packets.push(Packet::SmallUnreliable {
-                sequence: *packet_sequence,
-                channel_id: self.channel_id,
-                messages: std::mem::take(&mut small_messages),
-            });
+            packets.push(self.small_messages_packet(*packet_sequence, small_messages));
             *packet_sequence += 1;
         }
+    }
 
-        packets
+    fn small_messages_packet(&self, sequence: u64, messages: Vec<(u64, Bytes)>) -> Packet {
+        if self.deduplicated {
+            Packet::SmallUnreliableSequenced {
+                sequence,
+                channel_id: self.channel_id,
+                messages,
+            }
+        } else {
+            Packet::SmallUnreliable {
+                sequence,
+                channel_id: self.channel_id,
+                messages: messages.into_iter().map(|(_, message)| message).collect(),
+            }
+        }
     }
 
-    pub fn send_message(&mut self, message: Bytes) {
+    /// Returns the id assigned to the message, or None if it was dropped.
+    pub fn send_message(&mut self, message: Bytes) -> Option<u64> {
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             log::warn!(
                 "dropped unreliable message sent because channel {} is memory limited",
                 self.channel_id
             );
-            return;
+            return None;
         }
 
+        let message_id = self.next_message_id;
+        self.next_message_id += 1;
         self.memory_usage_bytes += message.len();
-        self.unreliable_messages.push_back(message);
+        self.unreliable_messages.push_back((message_id, message));
+
+        Some(message_id)
     }
 }
 
@@ -139,10 +227,161 @@ impl ReceiveChannelUnreliable {
             messages: VecDeque::new(),
             memory_usage_bytes: 0,
             max_memory_usage_bytes,
+            received_message_ids: None,
+            sequenced: false,
+            last_message_id: None,
+            last_message_received_at: Duration::ZERO,
+            max_message_age: None,
+            stale_sliced_message_ids: BTreeSet::new(),
+            stale_messages: 0,
+        }
+    }
+
+    /// Creates a channel that delivers each message id at most once.
+    pub fn new_deduplicated(channel_id: u8, max_memory_usage_bytes: usize) -> Self {
+        Self {
+            received_message_ids: Some(BTreeSet::new()),
+            ..Self::new(channel_id, max_memory_usage_bytes)
+        }
+    }
+
+    /// Creates a channel that only delivers messages newer than the last one delivered.
+    pub fn new_sequenced(channel_id: u8, max_memory_usage_bytes: usize) -> Self {
+        Self {
+            sequenced: true,
+            ..Self::new(channel_id, max_memory_usage_bytes)
+        }
+    }
+
+    /// Discards messages older than `max_message_age`, by message id or by time.
+    pub fn with_max_message_age(mut self, max_message_age: Option<MessageAge>) -> Self {
+        self.max_message_age = max_message_age;
+        self
+    }
+
+    /// Returns the number of messages discarded because a newer message was already received,
+    /// or because they were older than the max message age.
+    pub fn stale_messages(&self) -> u64 {
+        self.stale_messages
+    }
+
+    pub fn reset_stale_messages(&mut self) {
+        self.stale_messages = 0;
+    }
+
+    /// Returns true if the message id is further behind the newest message id received than the max message age,
+    /// or if it arrives later than the max message age after a newer message.
+    fn is_too_old(&self, message_id: u64, current_time: Duration) -> bool {
+        let Some(last_message_id) = self.last_message_id else {
+            return false;
+        };
+
+        match self.max_message_age {
+            Some(MessageAge::Messages(max_age)) => message_id.saturating_add(max_age) < last_message_id,
+            // The message was sent before the newer one, so it is at least as old as the time since that one arrived
+            Some(MessageAge::Time(max_age)) => message_id < last_message_id && current_time - self.last_message_received_at > max_age,
+            None => false,
+        }
+    }
+
+    /// Returns true if the message should not be delivered because it was already received,
+    /// or because a newer one was received for sequenced channels.
+    fn should_discard(&mut self, message_id: u64, current_time: Duration) -> bool {
+        if self.is_stale(message_id, current_time) {
+            self.stale_messages += 1;
+            return true;
+        }
+
+        if !self.sequenced && self.is_duplicated(message_id) {
+            return true;
+        }
+
+        // Tracks the newest message id received to measure the age of the next ones
+        if self.last_message_id.is_none_or(|last_message_id| message_id > last_message_id) {
+            self.last_message_id = Some(message_id);
+            self.last_message_received_at = current_time;
+            self.discard_stale_slices(current_time);
+        }
+        false
+    }
+
+    /// Returns true if a newer message was already received for sequenced channels, or if it is older than the max message age.
+    fn is_stale(&self, message_id: u64, current_time: Duration) -> bool {
+        let older_than_last = self.last_message_id.is_some_and(|last_message_id| message_id <= last_message_id);
+        (self.sequenced && older_than_last) || self.is_too_old(message_id, current_time)
+    }
+
+    /// Discards the incomplete sliced messages that became stale after a newer message was received.
+    fn discard_stale_slices(&mut self, current_time: Duration) {
+        let stale_message_ids: Vec<u64> = self
+            .slices
+            .keys()
+            .copied()
+            .filter(|&message_id| self.is_stale(message_id, current_time))
+            .collect();
+
+        for message_id in stale_message_ids {
+            self.slices_last_received.remove(&message_id);
+            let slice = self.slices.remove(&message_id).expect("stale slice should exist");
+            self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
+            self.count_stale_slice(message_id);
+        }
+    }
+
+    /// Counts the sliced message as stale once, no matter how many of its slices arrive.
+    fn count_stale_slice(&mut self, message_id: u64) {
+        if !self.stale_sliced_message_ids.insert(message_id) {
+            return;
+        }
+
+        self.stale_messages += 1;
+        if self.stale_sliced_message_ids.len() > DEDUPLICATION_WINDOW {
+            self.stale_sliced_message_ids.pop_first();
+        }
+    }
+
+    /// Returns true if the message id was already received, otherwise marks it as received.
+    /// Always returns false for channels without deduplication.
+    fn is_duplicated(&mut self, message_id: u64) -> bool {
+        let Some(received_message_ids) = self.received_message_ids.as_mut() else {
+            return false;
+        };
+
+        if received_message_ids.contains(&message_id) {
+            return true;
+        }
+
+        // Too old to know if it was received
+        if received_message_ids.len() >= DEDUPLICATION_WINDOW && received_message_ids.first().is_some_and(|oldest| message_id < *oldest) {
+            return true;
+        }
+
+        received_message_ids.insert(message_id);
+        if received_message_ids.len() > DEDUPLICATION_WINDOW {
+            received_message_ids.pop_first();
+        }
+
+        false
+    }
+
+    pub fn process_sequenced_message(&mut self, message_id: u64, message: Bytes, current_time: Duration) {
+        if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
+            log::warn!(
+                "dropped unreliable message received because channel {} is memory limited",
+                self.channel_id
+            );
+            return;
         }
+
+        if self.should_discard(message_id, current_time) {
+            return;
+        }
+
+        self.memory_usage_bytes += message.len();
+        self.messages.push_back((Some(message_id), current_time, message));
     }
 
-    pub fn process_message(&mut self, message: Bytes) {
+    pub fn process_message(&mut self, message: Bytes, current_time: Duration) {
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             log::warn!(
                 "dropped unreliable message received because channel {} is memory limited",
@@ -152,10 +391,31 @@ impl ReceiveChannelUnreliable {
         }
 
         self.memory_usage_bytes += message.len();
-        self.messages.push_back(message);
+        self.messages.push_back((None, current_time, message));
+    }
+
+    /// Returns true if processing the slice completes the reassembly of its message.
+    pub fn completes_message(&self, slice: &Slice) -> bool {
+        match self.slices.get(&slice.message_id) {
+            Some(slice_constructor) => slice_constructor.completes_with(slice.slice_index),
+            None => slice.num_slices == 1,
+        }
     }
 
     pub fn process_slice(&mut self, slice: Slice, current_time: Duration) -> Result<(), ChannelError> {
+        if let Some(received_message_ids) = &self.received_message_ids {
+            if received_message_ids.contains(&slice.message_id) {
+                // Slice from a copy of a message already received
+                return Ok(());
+            }
+        }
+
+        if self.is_stale(slice.message_id, current_time) {
+            // Slice from a message older than the last one received or than the max message age, it would be discarded
+            self.count_stale_slice(slice.message_id);
+            return Ok(());
+        }
+
         if !self.slices.contains_key(&slice.message_id) {
             let message_len = slice.num_slices * SLICE_SIZE;
             if self.memory_usage_bytes + message_len > self.max_memory_usage_bytes {
@@ -178,8 +438,12 @@ impl ReceiveChannelUnreliable {
             self.slices.remove(&slice.message_id);
             self.slices_last_received.remove(&slice.message_id);
             self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
+            if self.should_discard(slice.message_id, current_time) {
+                return Ok(());
+            }
+
             self.memory_usage_bytes += message.len();
-            self.messages.push_back(message);
+            self.messages.push_back((Some(slice.message_id), current_time, message));
         } else {
             self.slices_last_received.insert(slice.message_id, current_time);
         }
@@ -187,6 +451,31 @@ impl ReceiveChannelUnreliable {
         Ok(())
     }
 
+    /// Returns true if there are no messages waiting to be received or reassembled.
+    pub fn is_drained(&self) -> bool {
+        self.messages.is_empty() && self.slices.is_empty()
+    }
+
+    /// Returns the bytes reserved for incomplete sliced messages.
+    pub fn reassembly_bytes(&self) -> usize {
+        self.slices.values().map(|slice| slice.num_slices * SLICE_SIZE).sum()
+    }
+
+    /// Returns the time of the last slice received by the incomplete message that has gone longest without a new slice.
+    pub fn least_recent_reassembly(&self) -> Option<Duration> {
+        self.slices_last_received.values().min().copied()
+    }
+
+    /// Discards the incomplete message that has gone longest without a new slice, returns its message id.
+    pub fn discard_least_recent_reassembly(&mut self) -> Option<u64> {
+        let (&message_id, _) = self.slices_last_received.iter().min_by_key(|(_, last_received)| **last_received)?;
+
+        self.slices_last_received.remove(&message_id);
+        let slice = self.slices.remove(&message_id).expect("discarded slice should exist");
+        self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
+        Some(message_id)
+    }
+
     pub fn discard_incomplete_old_slices(&mut self, current_time: Duration) {
         let mut lost_messages: Vec<u64> = Vec::new();
         for (&message_id, last_received) in self.slices_last_received.iter() {
@@ -207,8 +496,36 @@ impl ReceiveChannelUnreliable {
         }
     }
 
+    /// Discards the messages waiting to be received that became older than the max message age.
+    pub fn discard_stale_messages(&mut self, current_time: Duration) {
+        let Some(max_message_age) = self.max_message_age else {
+            return;
+        };
+
+        let last_message_id = self.last_message_id;
+        let mut discarded_bytes = 0;
+        let mut discarded_messages = 0;
+        self.messages.retain(|(message_id, received_at, message)| {
+            let stale = match max_message_age {
+                MessageAge::Messages(max_age) => match (message_id, last_message_id) {
+                    (Some(message_id), Some(last_message_id)) => message_id.saturating_add(max_age) < last_message_id,
+                    _ => false,
+                },
+                MessageAge::Time(max_age) => current_time - *received_at > max_age,
+            };
+            if stale {
+                discarded_bytes += message.len();
+                discarded_messages += 1;
+            }
+            !stale
+        });
+
+        self.memory_usage_bytes -= discarded_bytes;
+        self.stale_messages += discarded_messages;
+    }
+
     pub fn receive_message(&mut self) -> Option<Bytes> {
-        if let Some(message) = self.messages.pop_front() {
+        if let Some((_, _, message)) = self.messages.pop_front() {
             self.memory_usage_bytes -= message.len();
             return Some(message);
         };
@@ -243,7 +560,7 @@ mod tests {
                 unreachable!();
             };
             for message in messages {
-                recv.process_message(message);
+                recv.process_message(message, Duration::ZERO);
             }
         }
 
@@ -309,7 +626,7 @@ mod tests {
             // Second message was dropped
             assert_eq!(messages.len(), 1);
             for message in messages {
-                recv.process_message(message);
+                recv.process_message(message, Duration::ZERO);
             }
         }
 
@@ -372,4 +689,236 @@ mod tests {
             assert!(len < 1300);
         }
     }
+
+    #[test]
+    fn deduplicated_messages() {
+        let max_memory: usize = 100000;
+        let mut available_bytes = u64::MAX;
+        let mut sequence: u64 = 0;
+        let current_time = Duration::ZERO;
+        let mut recv = ReceiveChannelUnreliable::new_deduplicated(0, max_memory);
+        let mut send = SendChannelUnreliable::new_deduplicated(0, max_memory, 2);
+
+        let small_message = vec![1, 2, 3];
+        let sliced_message = vec![5; SLICE_SIZE * 3];
+
+        send.send_message(small_message.clone().into());
+        send.send_message(sliced_message.clone().into());
+
+        // Each copy is sent in its own packets: 1 small + 3 slices
+        let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes);
+        assert_eq!(packets.len(), 8);
+        for packet in packets {
+            match packet {
+                Packet::SmallUnreliableSequenced { messages, .. } => {
+                    for (message_id, message) in messages {
+                        recv.process_sequenced_message(message_id, message, Duration::ZERO);
+                    }
+                }
+                Packet::UnreliableSlice { slice, .. } => recv.process_slice(slice, current_time).unwrap(),
+                _ => unreachable!(),
+            }
+        }
+
+        // Messages are received in the order they were sent
+        assert_eq!(recv.receive_message().unwrap(), small_message);
+        assert_eq!(recv.receive_message().unwrap(), sliced_message);
+        assert!(recv.receive_message().is_none());
+    }
+
+    #[test]
+    fn deduplication_window() {
+        let mut recv = ReceiveChannelUnreliable::new_deduplicated(0, usize::MAX);
+        let message: Bytes = vec![0, 1, 2].into();
+
+        for message_id in 1..=DEDUPLICATION_WINDOW as u64 {
+            recv.process_sequenced_message(message_id, message.clone(), Duration::ZERO);
+        }
+        recv.process_sequenced_message(DEDUPLICATION_WINDOW as u64 + 1, message.clone(), Duration::ZERO);
+
+        // Oldest message is out of the window, so it's not delivered again
+        recv.process_sequenced_message(0, message.clone(), Duration::ZERO);
+        recv.process_sequenced_message(1, message, Duration::ZERO);
+
+        let mut count = 0;
+        while recv.receive_message().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, DEDUPLICATION_WINDOW + 1);
+    }
+
+    #[test]
+    fn sequenced_messages() {
+        let mut recv = ReceiveChannelUnreliable::new_sequenced(0, usize::MAX);
+
+        recv.process_sequenced_message(0, vec![0].into(), Duration::ZERO);
+        recv.process_sequenced_message(2, vec![2].into(), Duration::ZERO);
+        // Older than the last received
+        recv.process_sequenced_message(1, vec![1].into(), Duration::ZERO);
+        // Duplicated
+        recv.process_sequenced_message(2, vec![2].into(), Duration::ZERO);
+        recv.process_sequenced_message(3, vec![3].into(), Duration::ZERO);
+
+        assert_eq!(recv.receive_message().unwrap(), vec![0]);
+        assert_eq!(recv.receive_message().unwrap(), vec![2]);
+        assert_eq!(recv.receive_message().unwrap(), vec![3]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.stale_messages(), 2);
+    }
+
+    #[test]
+    fn sequenced_stale_slices() {
+        let current_time = Duration::ZERO;
+        let mut recv = ReceiveChannelUnreliable::new_sequenced(0, usize::MAX);
+        let slice = |message_id: u64| Slice {
+            message_id,
+            slice_index: 0,
+            num_slices: 1,
+            payload: vec![message_id as u8; SLICE_SIZE].into(),
+        };
+
+        recv.process_slice(slice(1), current_time).unwrap();
+        recv.process_slice(slice(0), current_time).unwrap();
+
+        assert_eq!(recv.receive_message().unwrap(), vec![1; SLICE_SIZE]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.reassembly_bytes(), 0);
+    }
+
+    #[test]
+    fn max_message_age() {
+        let mut recv = ReceiveChannelUnreliable::new_deduplicated(0, usize::MAX).with_max_message_age(Some(MessageAge::Messages(2)));
+
+        recv.process_sequenced_message(5, vec![5].into(), Duration::ZERO);
+        // Within the max age, delivered even if out of order
+        recv.process_sequenced_message(3, vec![3].into(), Duration::ZERO);
+        // Too old
+        recv.process_sequenced_message(2, vec![2].into(), Duration::ZERO);
+        recv.process_sequenced_message(6, vec![6].into(), Duration::ZERO);
+        recv.process_sequenced_message(3, vec![3].into(), Duration::ZERO);
+
+        assert_eq!(recv.receive_message().unwrap(), vec![5]);
+        assert_eq!(recv.receive_message().unwrap(), vec![3]);
+        assert_eq!(recv.receive_message().unwrap(), vec![6]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.stale_messages(), 2);
+    }
+
+    #[test]
+    fn sequenced_small_and_sliced_messages() {
+        let mut sequence = 0;
+        let mut available_bytes = u64::MAX;
+        let mut send = SendChannelUnreliable::new_deduplicated(0, usize::MAX, 1);
+        let mut recv = ReceiveChannelUnreliable::new_sequenced(0, usize::MAX);
+        let sliced_message = Bytes::from(vec![1; SLICE_SIZE * 2]);
+
+        // Sent in the same update
+        send.send_message(vec![0].into());
+        send.send_message(sliced_message.clone());
+        send.send_message(vec![2].into());
+
+        for packet in send.get_packets_to_send(&mut sequence, &mut available_bytes) {
+            match packet {
+                Packet::SmallUnreliableSequenced { messages, .. } => {
+                    for (message_id, message) in messages {
+                        recv.process_sequenced_message(message_id, message, Duration::ZERO);
+                    }
+                }
+                Packet::UnreliableSlice { slice, .. } => recv.process_slice(slice, Duration::ZERO).unwrap(),
+                _ => unreachable!(),
+            }
+        }
+
+        assert_eq!(recv.receive_message().unwrap(), vec![0]);
+        assert_eq!(recv.receive_message().unwrap(), sliced_message);
+        assert_eq!(recv.receive_message().unwrap(), vec![2]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.stale_messages(), 0);
+    }
+
+    #[test]
+    fn sequenced_max_message_age() {
+        let mut recv = ReceiveChannelUnreliable::new_sequenced(0, usize::MAX).with_max_message_age(Some(MessageAge::Messages(2)));
+
+        recv.process_sequenced_message(0, vec![0].into(), Duration::ZERO);
+        recv.process_sequenced_message(2, vec![2].into(), Duration::ZERO);
+        recv.process_sequenced_message(3, vec![3].into(), Duration::ZERO);
+        // Waiting in the queue while newer messages arrive
+        recv.discard_stale_messages(Duration::ZERO);
+
+        assert_eq!(recv.receive_message().unwrap(), vec![2]);
+        assert_eq!(recv.receive_message().unwrap(), vec![3]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.stale_messages(), 1);
+    }
+
+    #[test]
+    fn max_message_age_time() {
+        let max_age = Duration::from_millis(100);
+        let mut recv = ReceiveChannelUnreliable::new_deduplicated(0, usize::MAX).with_max_message_age(Some(MessageAge::Time(max_age)));
+
+        recv.process_sequenced_message(5, vec![5].into(), Duration::ZERO);
+        // Arrives out of order within the max age
+        recv.process_sequenced_message(4, vec![4].into(), Duration::from_millis(50));
+        // Arrives after more than the max age since a newer message
+        recv.process_sequenced_message(3, vec![3].into(), Duration::from_millis(150));
+
+        assert_eq!(recv.receive_message().unwrap(), vec![5]);
+        recv.discard_stale_messages(Duration::from_millis(160));
+        // Waited in the queue longer than the max age
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.stale_messages(), 2);
+        assert_eq!(recv.memory_usage_bytes, 0);
+
+        // Plain unreliable channels only apply the time in the queue
+        let mut recv = ReceiveChannelUnreliable::new(0, usize::MAX).with_max_message_age(Some(MessageAge::Time(max_age)));
+        recv.process_message(vec![0].into(), Duration::ZERO);
+        recv.process_message(vec![1].into(), Duration::from_millis(100));
+        recv.discard_stale_messages(Duration::from_millis(150));
+        assert_eq!(recv.receive_message().unwrap(), vec![1]);
+        assert_eq!(recv.stale_messages(), 1);
+    }
+
+    #[test]
+    fn stale_slices_counted_once() {
+        let current_time = Duration::ZERO;
+        let mut recv = ReceiveChannelUnreliable::new_sequenced(0, usize::MAX);
+        let slice = |message_id: u64, slice_index: usize| Slice {
+            message_id,
+            slice_index,
+            num_slices: 3,
+            payload: vec![message_id as u8; SLICE_SIZE].into(),
+        };
+
+        // Incomplete message discarded when a newer one arrives
+        recv.process_slice(slice(1, 0), current_time).unwrap();
+        recv.process_sequenced_message(2, vec![2].into(), current_time);
+        assert_eq!(recv.reassembly_bytes(), 0);
+        assert_eq!(recv.stale_messages(), 1);
+
+        // Slices arriving after the message became stale are not counted again
+        recv.process_slice(slice(1, 1), current_time).unwrap();
+        recv.process_slice(slice(1, 2), current_time).unwrap();
+        // Every slice of an older message only counts once
+        recv.process_slice(slice(0, 0), current_time).unwrap();
+        recv.process_slice(slice(0, 1), current_time).unwrap();
+
+        assert_eq!(recv.receive_message().unwrap(), vec![2]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(recv.stale_messages(), 2);
+    }
+
+    #[test]
+    fn initial_capacity() {
+        let send = SendChannelUnreliable::new(0, usize::MAX);
+        assert_eq!(send.unreliable_messages.capacity(), 0);
+
+        let mut send = SendChannelUnreliable::new(0, usize::MAX).with_initial_capacity(Some(64));
+        let capacity = send.unreliable_messages.capacity();
+        assert!(capacity >= 64);
+        for _ in 0..64 {
+            send.send_message(Bytes::from(vec![0]));
+        }
+        assert_eq!(send.unreliable_messages.capacity(), capacity);
+    }
 }
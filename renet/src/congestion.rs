@@ -0,0 +1,83 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Decides how many bytes a connection can send in each update, see [ConnectionConfig::congestion_controller].
+///
+/// The connection notifies the controller of the packets sent, acked and lost, and before generating
+/// the packets of an update asks it for the bytes available, the messages that don't fit remain queued.
+///
+/// [ConnectionConfig::congestion_controller]: crate::ConnectionConfig::congestion_controller
+pub trait CongestionController: fmt::Debug + Send + Sync {
+    /// Called once per update, after the time of the connection is advanced.
+    /// The packet loss is measured over the stats window of the connection.
+    fn update(&mut self, _current_time: Duration, _packet_loss: f64) {}
+
+    /// Called for each packet sent, with its size in bytes.
+    fn on_packet_sent(&mut self, _current_time: Duration, _bytes: u64) {}
+
+    /// Called for each packet acked by the peer, with its round-trip time
+    /// and the smoothed round-trip time of the connection, both in seconds.
+    fn on_packet_acked(&mut self, _current_time: Duration, _rtt_sample: f64, _rtt: f64) {}
+
+    /// Called for each packet not acked by the peer within 3 seconds after being sent.
+    fn on_packet_lost(&mut self, _current_time: Duration) {}
+
+    /// Returns the number of bytes the connection is allowed to send in the next update.
+    fn available_bytes(&self) -> u64;
+
+    /// Returns the estimated capacity of the link in the send direction, in bytes per second,
+    /// or None if the controller doesn't estimate it. Unlike the bytes sent per second,
+    /// it's how much the link could carry, not how much is being sent.
+    fn estimated_capacity(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns a copy of the controller, used to give each connection in the server its own controller.
+    fn box_clone(&self) -> Box<dyn CongestionController>;
+}
+
+impl Clone for Box<dyn CongestionController> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// The default congestion controller, allows a fixed number of bytes in each update,
+/// see [ConnectionConfig::available_bytes_per_tick]. It doesn't react to the round-trip time or the packet loss.
+///
+/// [ConnectionConfig::available_bytes_per_tick]: crate::ConnectionConfig::available_bytes_per_tick
+#[derive(Debug, Clone)]
+pub struct FixedBytesPerTick {
+    available_bytes_per_tick: u64,
+}
+
+impl FixedBytesPerTick {
+    pub fn new(available_bytes_per_tick: u64) -> Self {
+        Self { available_bytes_per_tick }
+    }
+}
+
+impl CongestionController for FixedBytesPerTick {
+    fn available_bytes(&self) -> u64 {
+        self.available_bytes_per_tick
+    }
+
+    fn box_clone(&self) -> Box<dyn CongestionController> {
+        Box::new(self.clone())
+    }
+}
+
+/// Doesn't limit the bytes sent in each update, every queued message is sent in the next update.
+/// For controlled links where the application decides the send rate by when it queues messages.
+#[derive(Debug, Clone, Default)]
+pub struct NoCongestionControl;
+
+impl CongestionController for NoCongestionControl {
+    fn available_bytes(&self) -> u64 {
+        u64::MAX
+    }
+
+    fn box_clone(&self) -> Box<dyn CongestionController> {
+        Box::new(self.clone())
+    }
+}
@@ -4,13 +4,29 @@ const RESOLUTION: Duration = Duration::from_millis(300);
 const WINDOW: Duration = Duration::from_millis(6000);
 const SIZE: usize = (WINDOW.as_millis() / RESOLUTION.as_millis()) as usize;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ConnectionStats {
     packets_sent: [u64; SIZE],
     packets_acked: [u64; SIZE],
     bytes_sent: [u64; SIZE],
     bytes_received: [u64; SIZE],
+    messages_sent: [u64; SIZE],
     current_index: usize,
+    // Totals since the connection started, not limited to the window
+    total_packets_sent: u64,
+    total_bytes_sent: u64,
+    total_bytes_received: u64,
+    total_messages_sent: u64,
+    // Exponential moving average of the bandwidth, updated each time a resolution completes
+    bandwidth_smoothing_factor: f64,
+    smoothed_bytes_sent_per_second: Option<f64>,
+    smoothed_bytes_received_per_second: Option<f64>,
+}
+
+impl Default for ConnectionStats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ConnectionStats {
@@ -20,8 +36,30 @@ impl ConnectionStats {
             packets_acked: [0; SIZE],
             bytes_sent: [0; SIZE],
             bytes_received: [0; SIZE],
+            messages_sent: [0; SIZE],
             current_index: 0,
+            total_packets_sent: 0,
+            total_bytes_sent: 0,
+            total_bytes_received: 0,
+            total_messages_sent: 0,
+            bandwidth_smoothing_factor: 1.0,
+            smoothed_bytes_sent_per_second: None,
+            smoothed_bytes_received_per_second: None,
+        }
+    }
+
+    pub fn bandwidth_smoothing_factor(&self) -> f64 {
+        self.bandwidth_smoothing_factor
+    }
+
+    /// Changes the weight of each new sample in the bandwidth average, keeping the average measured so far.
+    /// Factors above 1.0 are clamped to it, factors not greater than 0.0 are ignored.
+    pub fn set_bandwidth_smoothing_factor(&mut self, factor: f64) {
+        if factor.is_nan() || factor <= 0.0 {
+            log::warn!("ignored bandwidth smoothing factor {factor}, it must be greater than 0.0");
+            return;
         }
+        self.bandwidth_smoothing_factor = factor.min(1.0);
     }
 
     fn index(time: Duration) -> usize {
@@ -36,16 +74,53 @@ impl ConnectionStats {
             self.bytes_sent[i] = 0;
             self.bytes_received[i] = 0;
             self.packets_acked[i] = 0;
+            self.messages_sent[i] = 0;
+
+            let factor = self.bandwidth_smoothing_factor;
+            let smooth = |smoothed: Option<f64>, sample: f64| match smoothed {
+                Some(smoothed) => Some(smoothed + factor * (sample - smoothed)),
+                None => Some(sample),
+            };
+            self.smoothed_bytes_sent_per_second =
+                smooth(self.smoothed_bytes_sent_per_second, self.window_bytes_sent_per_second(current_time));
+            self.smoothed_bytes_received_per_second = smooth(
+                self.smoothed_bytes_received_per_second,
+                self.window_bytes_received_per_second(current_time),
+            );
         }
     }
 
     pub fn sent_packets(&mut self, num_packets: u64, bytes: u64) {
         self.packets_sent[self.current_index] += num_packets;
         self.bytes_sent[self.current_index] += bytes;
+        self.total_packets_sent += num_packets;
+        self.total_bytes_sent += bytes;
+    }
+
+    pub fn sent_message(&mut self) {
+        self.messages_sent[self.current_index] += 1;
+        self.total_messages_sent += 1;
     }
 
     pub fn received_packet(&mut self, bytes: u64) {
         self.bytes_received[self.current_index] += bytes;
+        self.total_bytes_received += bytes;
+    }
+
+    pub fn total_packets_sent(&self) -> u64 {
+        self.total_packets_sent
+    }
+
+    pub fn total_bytes_sent(&self) -> u64 {
+        self.total_bytes_sent
+    }
+
+    pub fn total_bytes_received(&self) -> u64 {
+        self.total_bytes_received
+    }
+
+    pub fn total_messages_sent(&self) -> u64 {
+        self.total_messages_sent
     }
 
     pub fn acked_packet(&mut self, sent_at: Duration, current_time: Duration) {
@@ -58,7 +133,15 @@ impl ConnectionStats {
         self.packets_acked[Self::index(sent_at)] += 1;
     }
 
+    /// Returns the bytes sent per second over the window, smoothed with the bandwidth smoothing factor.
     pub fn bytes_sent_per_second(&self, current_time: Duration) -> f64 {
+        match self.smoothed_bytes_sent_per_second {
+            Some(smoothed) if self.bandwidth_smoothing_factor < 1.0 => smoothed,
+            _ => self.window_bytes_sent_per_second(current_time),
+        }
+    }
+
+    fn window_bytes_sent_per_second(&self, current_time: Duration) -> f64 {
         let mut total_bytes: u64 = self.bytes_sent.iter().sum();
 
         if current_time < WINDOW {
@@ -71,7 +154,39 @@ impl ConnectionStats {
         total_bytes as f64 / (WINDOW - RESOLUTION).as_secs_f64()
     }
 
+    pub fn packets_sent_per_second(&self, current_time: Duration) -> f64 {
+        let mut total_packets: u64 = self.packets_sent.iter().sum();
+
+        if current_time < WINDOW {
+            return total_packets as f64 / current_time.as_secs_f64();
+        }
+
+        // Ignore the current incomplete resolution
+        total_packets -= self.packets_sent[self.current_index];
+        total_packets as f64 / (WINDOW - RESOLUTION).as_secs_f64()
+    }
+
+    pub fn messages_sent_per_second(&self, current_time: Duration) -> f64 {
+        let mut total_messages: u64 = self.messages_sent.iter().sum();
+
+        if current_time < WINDOW {
+            return total_messages as f64 / current_time.as_secs_f64();
+        }
+
+        // Ignore the current incomplete resolution
+        total_messages -= self.messages_sent[self.current_index];
+        total_messages as f64 / (WINDOW - RESOLUTION).as_secs_f64()
+    }
+
+    /// Returns the bytes received per second over the window, smoothed with the bandwidth smoothing factor.
     pub fn bytes_received_per_second(&self, current_time: Duration) -> f64 {
+        match self.smoothed_bytes_received_per_second {
+            Some(smoothed) if self.bandwidth_smoothing_factor < 1.0 => smoothed,
+            _ => self.window_bytes_received_per_second(current_time),
+        }
+    }
+
+    fn window_bytes_received_per_second(&self, current_time: Duration) -> f64 {
         let mut total_bytes: u64 = self.bytes_received.iter().sum();
 
         if current_time < WINDOW {
@@ -83,17 +198,20 @@ impl ConnectionStats {
         total_bytes as f64 / (WINDOW - RESOLUTION).as_secs_f64()
     }
 
+    /// Returns the number of sent packets used to calculate the packet loss.
+    pub fn packet_loss_samples(&self) -> u64 {
+        let mut sum: u64 = self.packets_sent.iter().sum();
+
+        // Ignore the current and last 2 resolutions,
+        // because the message or its ack could be in flight
+        sum -= self.packets_sent[self.current_index];
+        sum -= self.packets_sent[(self.current_index + SIZE - 1) % SIZE];
+        sum -= self.packets_sent[(self.current_index + SIZE - 2) % SIZE];
+        sum
+    }
+
     pub fn packet_loss(&self) -> f64 {
-        let total_packets_sent = {
-            let mut sum: u64 = self.packets_sent.iter().sum();
-
-            // Ignore the current and last 2 resolutions,
-            // because the message or its ack could be in flight
-            sum -= self.packets_sent[self.current_index];
-            sum -= self.packets_sent[(self.current_index + SIZE - 1) % SIZE];
-            sum -= self.packets_sent[(self.current_index + SIZE - 2) % SIZE];
-            sum as f64
-        };
+        let total_packets_sent = self.packet_loss_samples() as f64;
 
         let total_packets_acked = {
             let mut sum: u64 = self.packets_acked.iter().sum();
@@ -141,6 +259,66 @@ mod tests {
         assert_eq!(window.bytes_sent_per_second(current_time), 1000.);
     }
 
+    #[test]
+    fn bandwidth_smoothing() {
+        let mut current_time = Duration::ZERO;
+        let mut window = ConnectionStats::new();
+
+        for _ in 0..60 {
+            window.update(current_time);
+            window.sent_packets(10, 100);
+            current_time += Duration::from_millis(100);
+        }
+        assert_eq!(window.bytes_sent_per_second(current_time), 1000.);
+
+        // Changing the factor keeps the average measured so far
+        window.set_bandwidth_smoothing_factor(0.1);
+        assert_eq!(window.bandwidth_smoothing_factor(), 0.1);
+        assert!((window.bytes_sent_per_second(current_time) - 1000.).abs() < 1.);
+
+        // The average follows a burst slower than the window
+        for _ in 0..3 {
+            window.update(current_time);
+            window.sent_packets(10, 1000);
+            current_time += Duration::from_millis(100);
+        }
+        window.update(current_time);
+        let smoothed = window.bytes_sent_per_second(current_time);
+        assert!(smoothed > 1000.);
+        assert!(smoothed < window.window_bytes_sent_per_second(current_time));
+    }
+
+    #[test]
+    fn invalid_bandwidth_smoothing_factor() {
+        let mut stats = ConnectionStats::new();
+        stats.set_bandwidth_smoothing_factor(0.5);
+        stats.set_bandwidth_smoothing_factor(0.0);
+        stats.set_bandwidth_smoothing_factor(f64::NAN);
+        assert_eq!(stats.bandwidth_smoothing_factor(), 0.5);
+
+        stats.set_bandwidth_smoothing_factor(2.0);
+        assert_eq!(stats.bandwidth_smoothing_factor(), 1.0);
+    }
+
+    #[test]
+    fn messages_per_sec() {
+        let mut current_time = Duration::ZERO;
+        let mut window = ConnectionStats::default();
+
+        for _ in 0..60 {
+            window.update(current_time);
+            // Three messages aggregated in a single packet
+            for _ in 0..3 {
+                window.sent_message();
+            }
+            window.sent_packets(1, 100);
+            current_time += Duration::from_millis(100);
+        }
+
+        assert_eq!(window.messages_sent_per_second(current_time), 30.);
+        assert_eq!(window.packets_sent_per_second(current_time), 10.);
+    }
+
     #[test]
     fn packet_loss() {
         let mut current_time = Duration::ZERO;
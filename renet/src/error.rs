@@ -1,4 +1,5 @@
 use std::fmt;
+use std::time::Duration;
 
 use crate::packet::SerializationError;
 
@@ -30,6 +31,9 @@ pub enum ChannelError {
     ReliableChannelMaxMemoryReached,
     /// Received an invalid slice message in the channel.
     InvalidSliceMessage,
+    /// Reliable sliced messages exceeded the maximum memory for reassembly of all channels,
+    /// see [ConnectionConfig::max_total_reassembly_bytes][crate::ConnectionConfig::max_total_reassembly_bytes].
+    ReassemblyMaxMemoryReached,
 }
 
 impl fmt::Display for ChannelError {
@@ -39,6 +43,7 @@ impl fmt::Display for ChannelError {
         match *self {
             ReliableChannelMaxMemoryReached => write!(fmt, "reliable channel memory usage was exausted"),
             InvalidSliceMessage => write!(fmt, "received an invalid slice packet"),
+            ReassemblyMaxMemoryReached => write!(fmt, "memory usage for reassembly of sliced messages was exausted"),
         }
     }
 }
@@ -62,6 +67,50 @@ impl fmt::Display for DisconnectReason {
 
 impl std::error::Error for ChannelError {}
 
+/// Possibles errors when reconfiguring the channels of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReconfigureError {
+    /// Channels that would be changed still have messages to send, acknowledge or receive.
+    ChannelsNotDrained { channel_ids: Vec<u8> },
+    /// Client with given id was not found.
+    ClientNotFound,
+}
+
+impl std::error::Error for ReconfigureError {}
+
+impl fmt::Display for ReconfigureError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use ReconfigureError::*;
+
+        match self {
+            ChannelsNotDrained { channel_ids } => write!(fmt, "channels {channel_ids:?} still have pending messages"),
+            ClientNotFound => write!(fmt, "client with given id was not found"),
+        }
+    }
+}
+
+/// Possibles errors when sending a message with [RenetClient::try_send_message][crate::RenetClient::try_send_message].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    /// The peer doesn't have the channel, see [ConnectionConfig::exchange_channel_ids][crate::ConnectionConfig::exchange_channel_ids].
+    UnknownRemoteChannel(u8),
+    /// Client with given id was not found.
+    ClientNotFound,
+}
+
+impl std::error::Error for SendError {}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use SendError::*;
+
+        match *self {
+            UnknownRemoteChannel(id) => write!(fmt, "the peer has no channel {id} to receive the message"),
+            ClientNotFound => write!(fmt, "client with given id was not found"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ClientNotFound;
 
@@ -72,3 +121,21 @@ impl fmt::Display for ClientNotFound {
         write!(fmt, "client with given id was not found")
     }
 }
+
+/// The time given to [RenetClient::update_at][crate::RenetClient::update_at] or
+/// [RenetServer::update_at][crate::RenetServer::update_at] is earlier than the time of a previous update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockWentBackwards {
+    /// Latest time given in a previous update.
+    pub previous: Duration,
+    /// Time given in this update.
+    pub now: Duration,
+}
+
+impl std::error::Error for ClockWentBackwards {}
+
+impl fmt::Display for ClockWentBackwards {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "clock went backwards by {:?}", self.previous - self.now)
+    }
+}
@@ -1,16 +1,33 @@
+mod block;
 mod channel;
+mod congestion;
 mod connection_stats;
 mod error;
+#[cfg(feature = "async")]
+mod message_ack;
+#[cfg(feature = "prometheus")]
+mod metrics;
 mod packet;
 mod remote_connection;
 mod server;
+mod status;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
 #[cfg(feature = "transport")]
 pub mod transport;
 
-pub use channel::{ChannelConfig, DefaultChannel, SendType};
-pub use error::{ChannelError, ClientNotFound, DisconnectReason};
-pub use remote_connection::{ConnectionConfig, NetworkInfo, RenetClient};
+pub use block::BlockHandle;
+pub use channel::{ChannelConfig, DefaultChannel, MessageAge, SendType};
+pub use congestion::{CongestionController, FixedBytesPerTick, NoCongestionControl};
+pub use error::{ChannelError, ClientNotFound, ClockWentBackwards, DisconnectReason, ReconfigureError, SendError};
+#[cfg(feature = "async")]
+pub use message_ack::{AckError, MessageAcked};
+pub use remote_connection::{
+    ChannelReport, ClientEvent, ConnectionConfig, ConnectionEstablished, DiagnosticsSnapshot, DisconnectReport, DrainPolicy, NetworkInfo,
+    NetworkInfoDelta, PaddingPolicy, ReconfigurePolicy, RenetClient, UnknownChannelPolicy, MAX_ACK_DELAY,
+};
 pub use server::{RenetServer, ServerEvent};
+pub use status::StatusHandle;
 
 pub use bytes::Bytes;
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::error::DisconnectReason;
+
+/// Reasons a message sent with [RenetClient::send_message_with_ack][crate::RenetClient::send_message_with_ack]
+/// will never be acknowledged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckError {
+    /// The connection was terminated before the message was acknowledged.
+    Disconnected(DisconnectReason),
+    /// The channel of the message was changed or removed by
+    /// [RenetClient::reconfigure_channels][crate::RenetClient::reconfigure_channels] before the message was acknowledged.
+    ChannelRemoved,
+    /// The connection was dropped before the message was acknowledged.
+    ConnectionDropped,
+}
+
+impl std::error::Error for AckError {}
+
+impl fmt::Display for AckError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use AckError::*;
+
+        match *self {
+            Disconnected(reason) => write!(fmt, "message not acknowledged, {reason}"),
+            ChannelRemoved => write!(fmt, "message not acknowledged, its channel was removed"),
+            ConnectionDropped => write!(fmt, "message not acknowledged, the connection was dropped"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AckState {
+    result: Option<Result<(), AckError>>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by [RenetClient::send_message_with_ack][crate::RenetClient::send_message_with_ack].
+///
+/// Resolves with `Ok(())` when the message was acknowledged by the remote connection,
+/// or with an [AckError] if it never will be.
+#[derive(Debug)]
+pub struct MessageAcked {
+    state: Arc<Mutex<AckState>>,
+}
+
+/// Sending half of [MessageAcked], kept by the reliable channel until the message is acked.
+/// If dropped without being notified, the future resolves with [AckError::ConnectionDropped].
+#[derive(Debug)]
+pub(crate) struct AckNotifier {
+    state: Arc<Mutex<AckState>>,
+}
+
+pub(crate) fn message_ack_channel() -> (AckNotifier, MessageAcked) {
+    let state = Arc::new(Mutex::new(AckState::default()));
+    let notifier = AckNotifier { state: state.clone() };
+
+    (notifier, MessageAcked { state })
+}
+
+impl MessageAcked {
+    pub(crate) fn resolved(result: Result<(), AckError>) -> Self {
+        let (notifier, acked) = message_ack_channel();
+        notifier.notify(result);
+        acked
+    }
+}
+
+impl Future for MessageAcked {
+    type Output = Result<(), AckError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl AckNotifier {
+    pub fn notify(self, result: Result<(), AckError>) {
+        self.complete(result);
+    }
+
+    fn complete(&self, result: Result<(), AckError>) {
+        let mut state = self.state.lock().unwrap();
+        if state.result.is_some() {
+            return;
+        }
+
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for AckNotifier {
+    fn drop(&mut self) {
+        // Was not notified or has already been notified
+        self.complete(Err(AckError::ConnectionDropped));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{ChannelConfig, ConnectionConfig, DefaultChannel, ReconfigurePolicy, RenetClient, SendType};
+
+    fn poll(acked: &mut MessageAcked) -> Poll<Result<(), AckError>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        Pin::new(acked).poll(&mut cx)
+    }
+
+    #[test]
+    fn message_acked() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+
+        let mut acked = client.send_message_with_ack(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        assert_eq!(poll(&mut acked), Poll::Pending);
+
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert_eq!(poll(&mut acked), Poll::Pending);
+
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        assert_eq!(poll(&mut acked), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn message_not_acked_on_disconnect() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+
+        let mut acked = client.send_message_with_ack(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        client.disconnect_due_to_transport();
+        client.update(Duration::ZERO);
+        assert_eq!(
+            poll(&mut acked),
+            Poll::Ready(Err(AckError::Disconnected(DisconnectReason::Transport)))
+        );
+
+        let mut acked = client.send_message_with_ack(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        assert_eq!(
+            poll(&mut acked),
+            Poll::Ready(Err(AckError::Disconnected(DisconnectReason::Transport)))
+        );
+    }
+
+    #[test]
+    fn message_not_acked_on_drop() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+
+        let mut acked = client.send_message_with_ack(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        drop(client);
+        assert_eq!(poll(&mut acked), Poll::Ready(Err(AckError::ConnectionDropped)));
+    }
+
+    #[test]
+    fn message_not_acked_on_channel_removed() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+
+        let mut acked = client.send_message_with_ack(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        let mut config = ConnectionConfig::default();
+        config.client_channels_config[2] = ChannelConfig::new(
+            2,
+            1024,
+            SendType::ReliableOrdered {
+                resend_time: Duration::from_millis(100),
+            },
+        );
+        client.reconfigure_channels(&config, ReconfigurePolicy::Discard).unwrap();
+        assert_eq!(poll(&mut acked), Poll::Ready(Err(AckError::ChannelRemoved)));
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt::Write;
+
+use crate::remote_connection::{DiagnosticsSnapshot, NetworkInfo};
+
+/// Renders the diagnostics counters and the network info gauges in the Prometheus text exposition format.
+/// Every sample has the given labels, as (name, value) pairs.
+pub(crate) fn metrics_text(labels: &[(&str, u64)], diagnostics: &DiagnosticsSnapshot, network_info: &NetworkInfo) -> String {
+    let labels = match labels.is_empty() {
+        true => String::new(),
+        false => {
+            let labels: Vec<String> = labels.iter().map(|(name, value)| format!("{name}=\"{value}\"")).collect();
+            format!("{{{}}}", labels.join(","))
+        }
+    };
+
+    let counters = [
+        (
+            "renet_unknown_channel_messages_total",
+            "Messages received for channels not in the config.",
+            diagnostics.unknown_channel_messages,
+        ),
+        (
+            "renet_reassembly_evictions_total",
+            "Incomplete sliced messages dropped to make room for new ones.",
+            diagnostics.reassembly_evictions,
+        ),
+        (
+            "renet_stale_messages_total",
+            "Unreliable messages discarded for being older than the last one received.",
+            diagnostics.stale_messages,
+        ),
+        (
+            "renet_dropped_events_total",
+            "Events dropped because the event queue was full.",
+            diagnostics.dropped_events,
+        ),
+        (
+            "renet_dropped_deferred_packets_total",
+            "Packets dropped because too many were deferred by the packet rate limit.",
+            diagnostics.dropped_deferred_packets,
+        ),
+        (
+            "renet_unknown_remote_channel_messages_total",
+            "Messages dropped because the peer doesn't have their channel.",
+            diagnostics.unknown_remote_channel_messages,
+        ),
+        (
+            "renet_replay_drops_total",
+            "Packets dropped by the replay protection of the transport layer.",
+            diagnostics.replay_drops,
+        ),
+        (
+            "renet_would_block_sends_total",
+            "Packets not sent because the socket buffer was full.",
+            diagnostics.would_block_sends,
+        ),
+        (
+            "renet_discarded_packets_total",
+            "Packets received from an address other than the server.",
+            diagnostics.discarded_packets,
+        ),
+    ];
+    let gauges = [
+        ("renet_rtt_seconds", "Round-trip time.", network_info.rtt),
+        (
+            "renet_packet_loss_ratio",
+            "Ratio of the packets sent that were lost.",
+            network_info.packet_loss,
+        ),
+        (
+            "renet_bytes_sent_per_second",
+            "Bytes sent per second.",
+            network_info.bytes_sent_per_second,
+        ),
+        (
+            "renet_bytes_received_per_second",
+            "Bytes received per second.",
+            network_info.bytes_received_per_second,
+        ),
+        (
+            "renet_packets_sent_per_second",
+            "Packets sent per second.",
+            network_info.packets_sent_per_second,
+        ),
+        (
+            "renet_messages_sent_per_second",
+            "Messages queued per second.",
+            network_info.messages_sent_per_second,
+        ),
+    ];
+
+    let mut text = String::new();
+    for (name, help, value) in counters {
+        let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} counter\n{name}{labels} {value}");
+    }
+    for (name, help, value) in gauges {
+        let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} gauge\n{name}{labels} {value}");
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn exposition_format() {
+        let diagnostics = DiagnosticsSnapshot {
+            unknown_channel_messages: 3,
+            ..Default::default()
+        };
+        let network_info = NetworkInfo {
+            rtt: 0.05,
+            packet_loss: 0.0,
+            packet_loss_available: true,
+            bytes_sent_per_second: 0.0,
+            bytes_received_per_second: 0.0,
+            packets_sent_per_second: 0.0,
+            messages_sent_per_second: 0.0,
+            session_id: None,
+            current_time: Duration::ZERO,
+            total_bytes_sent: 0,
+            total_bytes_received: 0,
+            total_packets_sent: 0,
+            total_messages_sent: 0,
+        };
+
+        let text = metrics_text(&[("session_id", 7), ("client_id", 2)], &diagnostics, &network_info);
+        assert!(text.contains("# TYPE renet_unknown_channel_messages_total counter\n"));
+        assert!(text.contains("renet_unknown_channel_messages_total{session_id=\"7\",client_id=\"2\"} 3\n"));
+        assert!(text.contains("renet_rtt_seconds{session_id=\"7\",client_id=\"2\"} 0.05\n"));
+
+        let text = metrics_text(&[], &diagnostics, &network_info);
+        assert!(text.contains("renet_replay_drops_total 0\n"));
+    }
+}
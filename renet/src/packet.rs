@@ -28,6 +28,12 @@ pub enum Packet {
         channel_id: u8,
         messages: Vec<Bytes>,
     },
+    // Small messages in a unreliable channel with message ids are aggregated and sent in this packet
+    SmallUnreliableSequenced {
+        sequence: u64,
+        channel_id: u8,
+        messages: Vec<(u64, Bytes)>,
+    },
     // A big unreliable message is sliced in multiples slice packets
     UnreliableSlice {
         sequence: u64,
@@ -46,6 +52,27 @@ pub enum Packet {
         sequence: u64,
         ack_ranges: Vec<Range<u64>>,
     },
+    // Requests the peer to echo the token, used to measure its processing time
+    ProcessingTimeRequest {
+        sequence: u64,
+        token: u64,
+    },
+    // Echoes the token of a request with the microseconds between receiving it and sending this packet
+    ProcessingTimeResponse {
+        sequence: u64,
+        token: u64,
+        processing_time_us: u64,
+    },
+    // The ids of the channels the sender can receive messages on
+    ChannelIds {
+        sequence: u64,
+        channel_ids: Vec<u8>,
+    },
+    // Human-readable version advertised by the server
+    ServerVersion {
+        sequence: u64,
+        version: Vec<u8>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -86,9 +113,26 @@ impl Packet {
         match self {
             Packet::SmallReliable { sequence, .. }
             | Packet::SmallUnreliable { sequence, .. }
+            | Packet::SmallUnreliableSequenced { sequence, .. }
             | Packet::UnreliableSlice { sequence, .. }
             | Packet::ReliableSlice { sequence, .. }
-            | Packet::Ack { sequence, .. } => *sequence,
+            | Packet::Ack { sequence, .. }
+            | Packet::ProcessingTimeRequest { sequence, .. }
+            | Packet::ProcessingTimeResponse { sequence, .. }
+            | Packet::ChannelIds { sequence, .. }
+            | Packet::ServerVersion { sequence, .. } => *sequence,
+        }
+    }
+
+    /// Returns the channel of the packets with messages.
+    pub fn channel_id(&self) -> Option<u8> {
+        match self {
+            Packet::SmallReliable { channel_id, .. }
+            | Packet::SmallUnreliable { channel_id, .. }
+            | Packet::SmallUnreliableSequenced { channel_id, .. }
+            | Packet::UnreliableSlice { channel_id, .. }
+            | Packet::ReliableSlice { channel_id, .. } => Some(*channel_id),
+            _ => None,
         }
     }
 
@@ -125,6 +169,21 @@ impl Packet {
                     b.put_bytes(message)?;
                 }
             }
+            Packet::SmallUnreliableSequenced {
+                sequence,
+                channel_id,
+                messages,
+            } => {
+                b.put_u8(5)?;
+                b.put_varint(*sequence)?;
+                b.put_u8(*channel_id)?;
+                b.put_u16(messages.len() as u16)?;
+                for (message_id, message) in messages {
+                    b.put_varint(*message_id)?;
+                    b.put_varint(message.len() as u64)?;
+                    b.put_bytes(message)?;
+                }
+            }
             Packet::ReliableSlice {
                 sequence,
                 channel_id,
@@ -200,6 +259,33 @@ impl Packet {
                     previous_range_start = range.start;
                 }
             }
+            Packet::ProcessingTimeRequest { sequence, token } => {
+                b.put_u8(6)?;
+                b.put_varint(*sequence)?;
+                b.put_varint(*token)?;
+            }
+            Packet::ProcessingTimeResponse {
+                sequence,
+                token,
+                processing_time_us,
+            } => {
+                b.put_u8(7)?;
+                b.put_varint(*sequence)?;
+                b.put_varint(*token)?;
+                b.put_varint(*processing_time_us)?;
+            }
+            Packet::ChannelIds { sequence, channel_ids } => {
+                b.put_u8(8)?;
+                b.put_varint(*sequence)?;
+                b.put_u16(channel_ids.len() as u16)?;
+                b.put_bytes(channel_ids)?;
+            }
+            Packet::ServerVersion { sequence, version } => {
+                b.put_u8(9)?;
+                b.put_varint(*sequence)?;
+                b.put_u8(version.len() as u8)?;
+                b.put_bytes(version)?;
+            }
         }
 
         Ok(before - b.cap())
@@ -346,6 +432,60 @@ impl Packet {
 
                 Ok(Packet::Ack { sequence, ack_ranges })
             }
+            5 => {
+                // SmallUnreliableSequenced
+                let sequence = b.get_varint()?;
+                let channel_id = b.get_u8()?;
+                let messages_len = b.get_u16()?;
+                let mut messages: Vec<(u64, Bytes)> = Vec::with_capacity(64);
+                for _ in 0..messages_len {
+                    let message_id = b.get_varint()?;
+                    let payload = b.get_bytes_with_varint_length()?;
+
+                    messages.push((message_id, payload.to_vec().into()));
+                }
+
+                Ok(Packet::SmallUnreliableSequenced {
+                    sequence,
+                    channel_id,
+                    messages,
+                })
+            }
+            6 => {
+                // ProcessingTimeRequest
+                let sequence = b.get_varint()?;
+                let token = b.get_varint()?;
+
+                Ok(Packet::ProcessingTimeRequest { sequence, token })
+            }
+            7 => {
+                // ProcessingTimeResponse
+                let sequence = b.get_varint()?;
+                let token = b.get_varint()?;
+                let processing_time_us = b.get_varint()?;
+
+                Ok(Packet::ProcessingTimeResponse {
+                    sequence,
+                    token,
+                    processing_time_us,
+                })
+            }
+            8 => {
+                // ChannelIds
+                let sequence = b.get_varint()?;
+                let len = b.get_u16()? as usize;
+                let channel_ids = b.get_bytes(len)?.to_vec();
+
+                Ok(Packet::ChannelIds { sequence, channel_ids })
+            }
+            9 => {
+                // ServerVersion
+                let sequence = b.get_varint()?;
+                let len = b.get_u8()? as usize;
+                let version = b.get_bytes(len)?.to_vec();
+
+                Ok(Packet::ServerVersion { sequence, version })
+            }
             _ => Err(SerializationError::InvalidPacketType),
         }
     }
@@ -389,6 +529,23 @@ mod tests {
         assert_eq!(packet, recv_packet);
     }
 
+    #[test]
+    fn serialize_small_unreliable_sequenced_packet() {
+        let mut buffer = [0u8; 1300];
+        let packet = Packet::SmallUnreliableSequenced {
+            sequence: 0,
+            channel_id: 0,
+            messages: vec![(0, vec![0, 0, 0].into()), (1, vec![1, 1, 1].into()), (2, vec![2, 2, 2].into())],
+        };
+
+        let mut b = octets::OctetsMut::with_slice(&mut buffer);
+        packet.to_bytes(&mut b).unwrap();
+
+        let mut b = octets::Octets::with_slice(&buffer);
+        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        assert_eq!(packet, recv_packet);
+    }
+
     #[test]
     fn serialize_reliable_slice_packet() {
         let mut buffer = [0u8; 1300];
@@ -451,4 +608,58 @@ mod tests {
         let recv_packet = Packet::from_bytes(&mut b).unwrap();
         assert_eq!(packet, recv_packet);
     }
+
+    #[test]
+    fn serialize_processing_time_packets() {
+        let mut buffer = [0u8; 1300];
+        let packets = [
+            Packet::ProcessingTimeRequest { sequence: 3, token: 7 },
+            Packet::ProcessingTimeResponse {
+                sequence: 4,
+                token: 7,
+                processing_time_us: 16_000,
+            },
+        ];
+
+        for packet in packets {
+            let mut b = octets::OctetsMut::with_slice(&mut buffer);
+            packet.to_bytes(&mut b).unwrap();
+
+            let mut b = octets::Octets::with_slice(&buffer);
+            let recv_packet = Packet::from_bytes(&mut b).unwrap();
+            assert_eq!(packet, recv_packet);
+        }
+    }
+
+    #[test]
+    fn serialize_channel_ids_packet() {
+        let mut buffer = [0u8; 1300];
+        let packet = Packet::ChannelIds {
+            sequence: 5,
+            channel_ids: vec![0, 1, 2, 7],
+        };
+
+        let mut b = octets::OctetsMut::with_slice(&mut buffer);
+        packet.to_bytes(&mut b).unwrap();
+
+        let mut b = octets::Octets::with_slice(&buffer);
+        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        assert_eq!(packet, recv_packet);
+    }
+
+    #[test]
+    fn serialize_server_version_packet() {
+        let mut buffer = [0u8; 1300];
+        let packet = Packet::ServerVersion {
+            sequence: 5,
+            version: b"1.2.3-abcdef".to_vec(),
+        };
+
+        let mut b = octets::OctetsMut::with_slice(&mut buffer);
+        packet.to_bytes(&mut b).unwrap();
+
+        let mut b = octets::Octets::with_slice(&buffer);
+        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        assert_eq!(packet, recv_packet);
+    }
 }
@@ -1,16 +1,28 @@
+use crate::block::{BlockHandle, BlockTransfer, BLOCK_CHUNK_SIZE};
 use crate::channel::reliable::{ReceiveChannelReliable, SendChannelReliable};
 use crate::channel::unreliable::{ReceiveChannelUnreliable, SendChannelUnreliable};
 use crate::channel::{ChannelConfig, DefaultChannel, SendType};
+use crate::congestion::{CongestionController, FixedBytesPerTick};
 use crate::connection_stats::ConnectionStats;
-use crate::error::DisconnectReason;
-use crate::packet::{Packet, Payload};
+use crate::error::{ChannelError, ClockWentBackwards, DisconnectReason, ReconfigureError, SendError};
+use crate::packet::{Packet, Payload, Slice};
+use crate::status::StatusHandle;
 use bytes::Bytes;
 use octets::OctetsMut;
 
-use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "async")]
+use crate::message_ack::{AckError, MessageAcked};
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::net::SocketAddr;
 use std::ops::Range;
 use std::time::Duration;
 
+/// Maximum delay applied to acks, see [ConnectionConfig::ack_delay].
+/// Kept below the default resend time of reliable channels, so packets are not resent before the ack could be sent.
+pub const MAX_ACK_DELAY: Duration = Duration::from_millis(100);
+
 /// Configuration for a renet connection and its channels.
 #[derive(Debug, Clone)]
 pub struct ConnectionConfig {
@@ -27,6 +39,89 @@ pub struct ConnectionConfig {
     /// Each tick, the first channel can consume up to `available_bytes_per_tick`,
     /// used bytes are removed from it and passed to the next channel
     pub client_channels_config: Vec<ChannelConfig>,
+    /// Minimum number of packets sent in the stats window before the packet loss is measured.
+    /// Until then, the packet loss is reported as 0.0, avoiding noisy values right after connecting.
+    /// Default: 20
+    pub packet_loss_warm_up_packets: u64,
+    /// Maximum number of bytes held by incomplete sliced messages in all receive channels.
+    /// When exceeded, the unreliable sliced message that has gone longest without receiving a slice is dropped.
+    /// Reliable sliced messages cannot be dropped without breaking their delivery guarantee,
+    /// so if they alone exceed this value the connection is terminated.
+    /// Default: usize::MAX, only the memory limit of each channel is applied
+    pub max_total_reassembly_bytes: usize,
+    /// Maximum number of packets sent per second, for networks that rate limit packets instead of bytes.
+    /// Packets above the limit are deferred to the next updates, up to one second of packets,
+    /// older deferred packets are dropped after that and counted in [RenetClient::dropped_deferred_packets].
+    /// Deferred acks are sent with the ranges pending when they leave the queue.
+    /// Only the packets of the connection are limited, the handshake and keep-alive packets of the transport layer
+    /// are sent regardless, so the limit should leave room for them.
+    /// Default: None, the packet rate is not limited
+    pub max_packets_per_second: Option<u64>,
+    /// Interval between the requests sent by the client to measure the server processing time,
+    /// see [RenetClient::server_processing_time]. The server always replies to them.
+    /// Default: None, the client doesn't measure the server processing time
+    pub processing_time_request_interval: Option<Duration>,
+    /// Explicit order in which the send channels generate packets, overriding the order of the channels config.
+    /// Channels not listed come after the listed ones, ordered by their id.
+    /// Applied to the channels each side sends, so it can list both client and server channels.
+    /// Default: None, the channels are sent in the order of their config
+    pub channel_send_order: Option<Vec<u8>>,
+    /// Whether each side sends the ids of the channels it receives on, so messages sent
+    /// on channels the peer doesn't have are rejected locally instead of disconnecting it.
+    /// Useful during incremental rollouts where the channels of both sides cannot be changed at the same time,
+    /// see [RenetClient::try_send_message]. Both sides should enable it.
+    /// Default: false
+    pub exchange_channel_ids: bool,
+    /// Custom congestion controller deciding the bytes sent in each update, replacing `available_bytes_per_tick`.
+    /// Each connection gets its own copy of the controller.
+    /// Use [NoCongestionControl][crate::NoCongestionControl] to send every queued message in the next update.
+    /// Default: None, a [FixedBytesPerTick] with `available_bytes_per_tick` is used
+    pub congestion_controller: Option<Box<dyn CongestionController>>,
+    /// Human-readable version or build string sent by the server to its clients after connecting,
+    /// read by the client with [RenetClient::server_version]. It's only informative, use the protocol id
+    /// to prevent incompatible versions from connecting.
+    /// Cannot be longer than 255 bytes, [RenetServer::new][crate::RenetServer::new] panics otherwise.
+    /// Default: None, the server doesn't advertise a version
+    pub server_version: Option<String>,
+    /// Maximum number of sliced messages completed in each update, spreading the cost of reassembly
+    /// and of handling the messages over multiple updates when many complete at once, for example after recovering from loss.
+    /// Slices that would complete a message above the limit are deferred to the next updates, in the order they were received.
+    /// The deferred slices count toward [ConnectionConfig::max_total_reassembly_bytes].
+    /// Default: None, the reassemblies are not limited
+    pub max_reassemblies_per_update: Option<usize>,
+    /// How messages received in a channel that is not configured are handled.
+    /// Messages in channels removed by [RenetClient::reconfigure_channels] are always ignored.
+    /// Default: [UnknownChannelPolicy::Drop]
+    pub unknown_channel_policy: UnknownChannelPolicy,
+    /// Time that received packets wait to be acknowledged, so the acks of multiple packets are batched
+    /// in fewer ack packets when receiving at high rates, like TCP delayed acks. Capped at [MAX_ACK_DELAY].
+    /// A larger delay sends fewer acks, but the peer measures a larger round-trip time and waits longer
+    /// to learn its messages were delivered, so it may resend more reliable messages that were already received.
+    /// With a delay, acks are only sent after new packets are received, instead of in every update until acknowledged.
+    /// Default: None, acks are sent in the next update
+    pub ack_delay: Option<Duration>,
+    /// Pads the packets sent to fixed sizes, so an observer can't infer the messages sent from the packet lengths.
+    /// The padding is ignored by the receiver, but it's sent and counted as any other bytes,
+    /// with [PaddingPolicy::Fixed] every packet costs the full size, even acks of a few bytes.
+    /// Default: [PaddingPolicy::None]
+    pub padding: PaddingPolicy,
+    /// Order in which [RenetClient::receive_next_message] takes the messages of the receive channels.
+    /// Default: [DrainPolicy::Sequential]
+    pub drain_policy: DrainPolicy,
+    /// Weight of each new sample in the moving average of the bytes sent and received per second,
+    /// sampled from the stats window every 300ms. Lower values give a more stable measure that reacts slower to changes.
+    /// Must be greater than 0.0, otherwise it's ignored, and values above 1.0 are clamped to it.
+    /// Can be changed later with [RenetClient::set_bandwidth_smoothing_factor].
+    /// Default: 1.0, the bandwidth is measured over the stats window without smoothing
+    pub bandwidth_smoothing_factor: f64,
+    /// Maximum number of events waiting to be taken with [RenetClient::get_event], when full the oldest event is dropped
+    /// and counted in [RenetClient::dropped_events]. Set it to 0 when the events are not used.
+    /// Default: 256
+    pub max_queued_events: usize,
+    /// Relative change in the round-trip time, compared to the last reported value,
+    /// needed to emit a [ClientEvent::RttImproved] or [ClientEvent::RttDegraded].
+    /// Default: 0.2, a change of 20%
+    pub rtt_event_threshold: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +148,26 @@ enum PacketSentInfo {
     Ack {
         largest_acked_packet: u64,
     },
+    // The peer received our channel ids
+    ChannelIds,
+    // The client received the server version
+    ServerVersion,
+}
+
+// Slice that completes a message, deferred because of the max reassemblies per update
+#[derive(Debug)]
+struct DeferredReassembly {
+    channel_id: u8,
+    slice: Slice,
+    reliable: bool,
+}
+
+// Receive channel removed or changed by a reconfiguration, packets sent to it before the reconfiguration are ignored
+#[derive(Debug)]
+struct RetiredChannel {
+    // Packets with a lower sequence were sent before the reconfiguration
+    sequence_fence: u64,
+    retired_at: Duration,
 }
 
 #[derive(Debug)]
@@ -61,13 +176,323 @@ enum ChannelOrder {
     Unreliable(u8),
 }
 
+impl ChannelOrder {
+    fn channel_id(&self) -> u8 {
+        match self {
+            ChannelOrder::Reliable(channel_id) | ChannelOrder::Unreliable(channel_id) => *channel_id,
+        }
+    }
+}
+
+/// Events that happened in the connection, see [RenetClient::get_event].
+/// New events may be added in future versions, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Event))]
+pub enum ClientEvent {
+    /// The connection was established by the transport layer, emitted only once per connection.
+    ConnectionEstablished(ConnectionEstablished),
+    /// The round-trip time dropped below the last reported value by more than 20%.
+    RttImproved { new_rtt: f64 },
+    /// The round-trip time rose above the last reported value by more than 20%.
+    RttDegraded { new_rtt: f64 },
+    /// Messages were received in a channel that is not configured, emitted with [UnknownChannelPolicy::Event].
+    /// Slices of a message are counted individually.
+    UnknownChannelMessages { channel_id: u8, num_messages: usize },
+    /// A reliable ordered channel received the missing message that was blocking the delivery of the newer ones,
+    /// see [RenetClient::channel_is_blocked]. The duration is measured from when the channel became blocked.
+    ChannelUnblocked { channel_id: u8, blocked_for: Duration },
+}
+
+/// Information about an established connection, see [ClientEvent::ConnectionEstablished].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionEstablished {
+    /// Identifier of the client in the server.
+    pub client_id: u64,
+    /// Address of the server the client connected to.
+    pub server_addr: SocketAddr,
+}
+
+/// Diagnostic counters of a connection, see [RenetClient::diagnostics].
+/// The counters accumulate until reset with [RenetClient::reset_all_diagnostics].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiagnosticsSnapshot {
+    /// See [RenetClient::unknown_channel_messages].
+    pub unknown_channel_messages: u64,
+    /// See [RenetClient::reassembly_evictions].
+    pub reassembly_evictions: u64,
+    /// Sum of the [stale messages][RenetClient::stale_messages] of all receive channels.
+    pub stale_messages: u64,
+    /// See [RenetClient::dropped_events].
+    pub dropped_events: u64,
+    /// See [RenetClient::dropped_deferred_packets].
+    pub dropped_deferred_packets: u64,
+    /// See [RenetClient::unknown_remote_channel_messages].
+    pub unknown_remote_channel_messages: u64,
+    /// Packets dropped by the replay protection of the transport layer.
+    /// Always 0 in the snapshot of the connection, it's filled by the transport,
+    /// see [NetcodeClientTransport::diagnostics][crate::transport::NetcodeClientTransport::diagnostics].
+    pub replay_drops: u64,
+    /// Packets that the transport layer failed to send because the socket buffer was full.
+    /// Always 0 in the snapshot of the connection, it's filled by the transport.
+    pub would_block_sends: u64,
+    /// Packets discarded by the transport layer because they were received from an address other than the server,
+    /// see [NetcodeClientTransport::unexpected_source_packets][crate::transport::NetcodeClientTransport::unexpected_source_packets].
+    /// Always 0 in the snapshot of the connection, it's filled by the transport.
+    pub discarded_packets: u64,
+}
+
 /// Describes the stats of a connection.
+/// Keep a reading to compare it with a later one, see [NetworkInfo::delta].
+#[derive(Debug, Clone, PartialEq)]
 pub struct NetworkInfo {
     /// Round-trip Time
     pub rtt: f64,
+    /// Packet loss, 0.0 when there is not enough data to measure it yet.
+    pub packet_loss: f64,
+    /// Whether enough packets were sent to measure the packet loss,
+    /// see [ConnectionConfig::packet_loss_warm_up_packets].
+    pub packet_loss_available: bool,
+    pub bytes_sent_per_second: f64,
+    pub bytes_received_per_second: f64,
+    /// Packets sent per second, each one is sent as a single datagram by the transport layer.
+    pub packets_sent_per_second: f64,
+    /// Messages queued in the send channels per second, dividing it by the packets sent per second
+    /// shows how many messages are aggregated per packet, or how many packets a sliced message needs.
+    pub messages_sent_per_second: f64,
+    /// Application session id of the connection, see [RenetClient::set_session_id].
+    pub session_id: Option<u64>,
+    /// Time of the connection when the reading was taken, advanced by [RenetClient::update].
+    pub current_time: Duration,
+    /// Total bytes sent since the connection started.
+    pub total_bytes_sent: u64,
+    /// Total bytes received since the connection started.
+    pub total_bytes_received: u64,
+    /// Total packets sent since the connection started.
+    pub total_packets_sent: u64,
+    /// Total messages queued in the send channels since the connection started.
+    pub total_messages_sent: u64,
+}
+
+/// Difference between two [NetworkInfo] readings, see [NetworkInfo::delta].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkInfoDelta {
+    /// Time elapsed between the readings.
+    pub elapsed: Duration,
+    /// Change of the round-trip time, negative if it decreased.
+    pub rtt: f64,
+    /// Change of the packet loss, negative if it decreased.
     pub packet_loss: f64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub packets_sent: u64,
+    pub messages_sent: u64,
+    /// Average over the elapsed time, unlike [NetworkInfo::bytes_sent_per_second] that is measured over the stats window.
+    /// Zero if no time elapsed, as all the per second fields.
     pub bytes_sent_per_second: f64,
     pub bytes_received_per_second: f64,
+    pub packets_sent_per_second: f64,
+    pub messages_sent_per_second: f64,
+}
+
+impl NetworkInfo {
+    /// Returns the change of each metric since an earlier reading of the same connection,
+    /// with the average rates over the time elapsed between them.
+    ///
+    /// # Usage
+    /// ```
+    /// # use std::time::Duration;
+    /// # use renet::{ConnectionConfig, RenetClient};
+    /// # let mut client = RenetClient::new(ConnectionConfig::default());
+    /// let earlier = client.network_info();
+    /// client.update(Duration::from_secs(5));
+    /// let delta = client.network_info().delta(&earlier);
+    /// println!("Packet loss changed by {} in the last {:?}", delta.packet_loss, delta.elapsed);
+    /// ```
+    pub fn delta(&self, earlier: &NetworkInfo) -> NetworkInfoDelta {
+        let elapsed = self.current_time.saturating_sub(earlier.current_time);
+        let per_second = |count: u64| match elapsed.is_zero() {
+            true => 0.0,
+            false => count as f64 / elapsed.as_secs_f64(),
+        };
+        let bytes_sent = self.total_bytes_sent.saturating_sub(earlier.total_bytes_sent);
+        let bytes_received = self.total_bytes_received.saturating_sub(earlier.total_bytes_received);
+        let packets_sent = self.total_packets_sent.saturating_sub(earlier.total_packets_sent);
+        let messages_sent = self.total_messages_sent.saturating_sub(earlier.total_messages_sent);
+
+        NetworkInfoDelta {
+            elapsed,
+            rtt: self.rtt - earlier.rtt,
+            packet_loss: self.packet_loss - earlier.packet_loss,
+            bytes_sent,
+            bytes_received,
+            packets_sent,
+            messages_sent,
+            bytes_sent_per_second: per_second(bytes_sent),
+            bytes_received_per_second: per_second(bytes_received),
+            packets_sent_per_second: per_second(packets_sent),
+            messages_sent_per_second: per_second(messages_sent),
+        }
+    }
+}
+
+/// Messages that would be lost if the connection was terminated, see [RenetClient::disconnect_report].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisconnectReport {
+    /// Messages remaining in each send channel, in the order they are sent, see [ConnectionConfig::channel_send_order].
+    pub channels: Vec<ChannelReport>,
+}
+
+/// Messages remaining in a send channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelReport {
+    pub channel_id: u8,
+    /// Messages that were never sent.
+    pub unsent_messages: usize,
+    /// Messages that were sent, but were not acknowledged yet.
+    /// Always 0 for unreliable channels, since their messages are not acknowledged.
+    pub in_flight_messages: usize,
+}
+
+impl DisconnectReport {
+    /// Returns the total number of unsent messages in all channels.
+    pub fn unsent_messages(&self) -> usize {
+        self.channels.iter().map(|channel| channel.unsent_messages).sum()
+    }
+
+    /// Returns the total number of sent but unacknowledged messages in all channels.
+    pub fn in_flight_messages(&self) -> usize {
+        self.channels.iter().map(|channel| channel.in_flight_messages).sum()
+    }
+}
+
+/// How messages received in a channel that is not configured are handled, usually sent by a peer with a different version,
+/// see [ConnectionConfig::unknown_channel_policy].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownChannelPolicy {
+    /// The messages are dropped and counted, see [RenetClient::unknown_channel_messages].
+    #[default]
+    Drop,
+    /// The messages are dropped, counted and reported with a [ClientEvent::UnknownChannelMessages].
+    Event,
+    /// The connection is terminated with [DisconnectReason::ReceivedInvalidChannelId].
+    Disconnect,
+}
+
+/// Order in which [RenetClient::receive_next_message] takes the messages of the receive channels,
+/// see [ConnectionConfig::drain_policy].
+///
+/// The order only depends on the messages available in each channel, not on timing or hashing,
+/// so identical messages received are always drained in the same order, as needed by replays and tests.
+/// Channels are visited in the order of their receive channels config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DrainPolicy {
+    /// Each message is taken from the first channel in the config that has one,
+    /// so a channel is only drained when the channels before it are empty.
+    #[default]
+    Sequential,
+    /// One message is taken from each channel in turn, channels without messages are skipped.
+    RoundRobin,
+    /// Up to the weight of a channel messages are taken from it before moving to the next one,
+    /// channels without messages are skipped. Channels not listed have a weight of 1, a weight of 0 is treated as 1.
+    WeightedRoundRobin(Vec<(u8, u32)>),
+}
+
+impl DrainPolicy {
+    fn weight(&self, channel_id: u8) -> u32 {
+        match self {
+            DrainPolicy::Sequential => u32::MAX,
+            DrainPolicy::RoundRobin => 1,
+            DrainPolicy::WeightedRoundRobin(weights) => weights
+                .iter()
+                .find(|(id, _)| *id == channel_id)
+                .map_or(1, |(_, weight)| (*weight).max(1)),
+        }
+    }
+}
+
+/// Sizes the packets sent are padded to, see [ConnectionConfig::padding].
+///
+/// The sizes are of the packets given to the transport layer, the netcode transport adds a header of 18 to 25 bytes,
+/// depending on the packet sequence, and sends keep-alive packets without padding unless configured in the transport.
+/// Sizes cannot be greater than 1300 bytes, the maximum payload of the netcode transport.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PaddingPolicy {
+    /// Packets are sent with their own size.
+    #[default]
+    None,
+    /// Every packet is padded to the size, the most private and the most expensive.
+    /// Packets above the size are sent as they are.
+    Fixed(usize),
+    /// Packets are padded to the smallest size class that fits them, leaking only the class of each packet.
+    /// Packets above the largest class are sent as they are.
+    SizeClasses(Vec<usize>),
+}
+
+impl PaddingPolicy {
+    // Maximum payload of the netcode transport
+    const MAX_SIZE: usize = 1300;
+
+    fn validated(&self) -> Self {
+        let mut policy = self.clone();
+        match &mut policy {
+            PaddingPolicy::None => {}
+            PaddingPolicy::Fixed(size) => assert!(
+                *size <= Self::MAX_SIZE,
+                "The padding size cannot be greater than {}",
+                Self::MAX_SIZE
+            ),
+            PaddingPolicy::SizeClasses(sizes) => {
+                assert!(
+                    sizes.iter().all(|&size| size <= Self::MAX_SIZE),
+                    "The padding size classes cannot be greater than {}",
+                    Self::MAX_SIZE
+                );
+                sizes.sort_unstable();
+            }
+        }
+        policy
+    }
+
+    /// Returns the size a packet is padded to.
+    fn padded_len(&self, len: usize) -> usize {
+        match self {
+            PaddingPolicy::None => len,
+            PaddingPolicy::Fixed(size) => len.max(*size),
+            PaddingPolicy::SizeClasses(sizes) => sizes.iter().copied().find(|&size| size >= len).unwrap_or(len),
+        }
+    }
+}
+
+/// How messages in changed channels are handled by [RenetClient::reconfigure_channels].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconfigurePolicy {
+    /// Messages not yet delivered in the changed channels are discarded.
+    Discard,
+    /// Fails with [ReconfigureError::ChannelsNotDrained] if any changed channel still has
+    /// messages to send, be acknowledged, or be received.
+    RequireDrained,
+}
+
+type MessageQueuedFn = dyn FnMut(u8, u64, &[u8]) + Send + Sync;
+
+struct MessageQueuedHook(Box<MessageQueuedFn>);
+
+type MessageAckedFn = dyn FnMut(u8, u64, Duration) + Send + Sync;
+
+struct MessageAckedHook(Box<MessageAckedFn>);
+
+impl fmt::Debug for MessageAckedHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MessageAckedHook")
+    }
+}
+
+impl fmt::Debug for MessageQueuedHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MessageQueuedHook")
+    }
 }
 
 #[derive(Debug)]
@@ -77,15 +502,68 @@ pub struct RenetClient {
     current_time: Duration,
     sent_packets: BTreeMap<u64, PacketSent>,
     pending_acks: Vec<Range<u64>>,
+    ack_delay: Option<Duration>,
+    padding: PaddingPolicy,
+    unsent_ack_since: Option<Duration>,
     channel_send_order: Vec<ChannelOrder>,
     send_unreliable_channels: HashMap<u8, SendChannelUnreliable>,
     receive_unreliable_channels: HashMap<u8, ReceiveChannelUnreliable>,
     send_reliable_channels: HashMap<u8, SendChannelReliable>,
     receive_reliable_channels: HashMap<u8, ReceiveChannelReliable>,
+    pub(crate) send_channels_config: Vec<ChannelConfig>,
+    pub(crate) receive_channels_config: Vec<ChannelConfig>,
+    retired_receive_channels: HashMap<u8, RetiredChannel>,
+    max_received_sequence: Option<u64>,
+    drain_policy: DrainPolicy,
+    // Position of receive_next_message, the index in receive_channels_config and the messages taken from it
+    drain_index: usize,
+    drain_taken: u32,
+    unknown_channel_policy: UnknownChannelPolicy,
+    unknown_channel_messages: u64,
+    blocked_channels: HashMap<u8, Duration>,
+    established_client_id: Option<u64>,
+    status_handle: Option<StatusHandle>,
     stats: ConnectionStats,
-    available_bytes_per_tick: u64,
+    congestion_controller: Box<dyn CongestionController>,
+    packet_loss_warm_up_packets: u64,
+    max_total_reassembly_bytes: usize,
+    reassembly_evictions: u64,
+    max_reassemblies_per_update: Option<usize>,
+    reassemblies_this_update: usize,
+    deferred_reassemblies: VecDeque<DeferredReassembly>,
+    deferred_reassembly_bytes: usize,
+    acks_sent_this_update: u64,
+    acks_received_this_update: u64,
+    max_packets_per_second: Option<u64>,
+    packet_budget: f64,
+    deferred_packets: VecDeque<Packet>,
+    dropped_deferred_packets: u64,
+    on_message_queued: Option<MessageQueuedHook>,
+    on_message_acked: Option<MessageAckedHook>,
+    block_transfers: Vec<BlockTransfer>,
+    last_update_at: Option<Duration>,
+    clock_regressions: u64,
+    send_paused: bool,
+    processing_time_request_interval: Option<Duration>,
+    processing_time_token: u64,
+    last_processing_time_request: Option<Duration>,
+    processing_time_reply: Option<(u64, Duration)>,
+    server_processing_time: Option<Duration>,
+    session_id: Option<u64>,
+    exchange_channel_ids: bool,
+    channel_ids_acked: bool,
+    remote_channel_ids: Option<Vec<u8>>,
+    unknown_remote_channel_messages: u64,
+    advertised_version: Option<String>,
+    advertised_version_acked: bool,
+    server_version: Option<String>,
     pub(crate) disconnect_reason: Option<DisconnectReason>,
     rtt: f64,
+    rtt_reference: f64,
+    rtt_event_threshold: f64,
+    pub(crate) events: VecDeque<ClientEvent>,
+    max_queued_events: usize,
+    dropped_events: u64,
 }
 
 impl Default for ConnectionConfig {
@@ -95,48 +573,79 @@ impl Default for ConnectionConfig {
             available_bytes_per_tick: 60_000,
             server_channels_config: DefaultChannel::config(),
             client_channels_config: DefaultChannel::config(),
+            packet_loss_warm_up_packets: 20,
+            max_total_reassembly_bytes: usize::MAX,
+            max_packets_per_second: None,
+            processing_time_request_interval: None,
+            channel_send_order: None,
+            exchange_channel_ids: false,
+            congestion_controller: None,
+            server_version: None,
+            max_reassemblies_per_update: None,
+            unknown_channel_policy: UnknownChannelPolicy::Drop,
+            ack_delay: None,
+            padding: PaddingPolicy::None,
+            drain_policy: DrainPolicy::Sequential,
+            bandwidth_smoothing_factor: 1.0,
+            max_queued_events: 256,
+            rtt_event_threshold: 0.2,
         }
     }
 }
 
 impl RenetClient {
     pub fn new(config: ConnectionConfig) -> Self {
-        Self::from_channels(
-            config.available_bytes_per_tick,
-            config.client_channels_config,
-            config.server_channels_config,
-        )
+        let mut client = Self::from_channels(&config, &config.client_channels_config, &config.server_channels_config);
+        // Only the client requests the processing time
+        client.processing_time_request_interval = config.processing_time_request_interval;
+        client
     }
 
     // When creating a client from the server, the server_channels_config are used as send channels,
     // and the client_channels_config is used as recv channels.
     pub(crate) fn new_from_server(config: ConnectionConfig) -> Self {
-        Self::from_channels(
-            config.available_bytes_per_tick,
-            config.server_channels_config,
-            config.client_channels_config,
-        )
-    }
-
-    fn from_channels(
-        available_bytes_per_tick: u64,
-        send_channels_config: Vec<ChannelConfig>,
-        receive_channels_config: Vec<ChannelConfig>,
-    ) -> Self {
+        let mut client = Self::from_channels(&config, &config.server_channels_config, &config.client_channels_config);
+        // Only the server advertises its version, its length was validated by the server
+        client.advertised_version = config.server_version.clone();
+        client
+    }
+
+    fn from_channels(config: &ConnectionConfig, send_channels_config: &[ChannelConfig], receive_channels_config: &[ChannelConfig]) -> Self {
         let mut send_unreliable_channels = HashMap::new();
         let mut send_reliable_channels = HashMap::new();
         let mut channel_send_order: Vec<ChannelOrder> = Vec::with_capacity(send_channels_config.len());
         for channel_config in send_channels_config.iter() {
             match channel_config.send_type {
                 SendType::Unreliable => {
-                    let channel = SendChannelUnreliable::new(channel_config.channel_id, channel_config.max_memory_usage_bytes);
+                    let channel = SendChannelUnreliable::new(channel_config.channel_id, channel_config.max_memory_usage_bytes)
+                        .with_initial_capacity(channel_config.initial_capacity);
+                    let old = send_unreliable_channels.insert(channel_config.channel_id, channel);
+                    assert!(old.is_none(), "already exists send channel {}", channel_config.channel_id);
+
+                    channel_send_order.push(ChannelOrder::Unreliable(channel_config.channel_id));
+                }
+                SendType::UnreliableDeduplicated { copies } => {
+                    let channel =
+                        SendChannelUnreliable::new_deduplicated(channel_config.channel_id, channel_config.max_memory_usage_bytes, copies)
+                            .with_initial_capacity(channel_config.initial_capacity);
+                    let old = send_unreliable_channels.insert(channel_config.channel_id, channel);
+                    assert!(old.is_none(), "already exists send channel {}", channel_config.channel_id);
+
+                    channel_send_order.push(ChannelOrder::Unreliable(channel_config.channel_id));
+                }
+                SendType::UnreliableSequenced => {
+                    // Sent with the message ids, so the receiver can discard old messages
+                    let channel =
+                        SendChannelUnreliable::new_deduplicated(channel_config.channel_id, channel_config.max_memory_usage_bytes, 1)
+                            .with_initial_capacity(channel_config.initial_capacity);
                     let old = send_unreliable_channels.insert(channel_config.channel_id, channel);
                     assert!(old.is_none(), "already exists send channel {}", channel_config.channel_id);
 
                     channel_send_order.push(ChannelOrder::Unreliable(channel_config.channel_id));
                 }
                 SendType::ReliableOrdered { resend_time } | SendType::ReliableUnordered { resend_time } => {
-                    let channel = SendChannelReliable::new(channel_config.channel_id, resend_time, channel_config.max_memory_usage_bytes);
+                    let channel = SendChannelReliable::new(channel_config.channel_id, resend_time, channel_config.max_memory_usage_bytes)
+                        .with_max_concurrent_block_transfers(channel_config.max_concurrent_block_transfers);
                     let old = send_reliable_channels.insert(channel_config.channel_id, channel);
                     assert!(old.is_none(), "already exists send channel {}", channel_config.channel_id);
 
@@ -145,12 +654,34 @@ impl RenetClient {
             }
         }
 
+        if let Some(order) = &config.channel_send_order {
+            channel_send_order.sort_by_key(|channel_order| {
+                let channel_id = channel_order.channel_id();
+                let position = order.iter().position(|id| *id == channel_id).unwrap_or(usize::MAX);
+                (position, channel_id)
+            });
+        }
+
         let mut receive_unreliable_channels = HashMap::new();
         let mut receive_reliable_channels = HashMap::new();
         for channel_config in receive_channels_config.iter() {
             match channel_config.send_type {
                 SendType::Unreliable => {
-                    let channel = ReceiveChannelUnreliable::new(channel_config.channel_id, channel_config.max_memory_usage_bytes);
+                    let channel = ReceiveChannelUnreliable::new(channel_config.channel_id, channel_config.max_memory_usage_bytes)
+                        .with_max_message_age(channel_config.max_message_age);
+                    let old = receive_unreliable_channels.insert(channel_config.channel_id, channel);
+                    assert!(old.is_none(), "already exists receive channel {}", channel_config.channel_id);
+                }
+                SendType::UnreliableDeduplicated { .. } => {
+                    let channel =
+                        ReceiveChannelUnreliable::new_deduplicated(channel_config.channel_id, channel_config.max_memory_usage_bytes)
+                            .with_max_message_age(channel_config.max_message_age);
+                    let old = receive_unreliable_channels.insert(channel_config.channel_id, channel);
+                    assert!(old.is_none(), "already exists receive channel {}", channel_config.channel_id);
+                }
+                SendType::UnreliableSequenced => {
+                    let channel = ReceiveChannelUnreliable::new_sequenced(channel_config.channel_id, channel_config.max_memory_usage_bytes)
+                        .with_max_message_age(channel_config.max_message_age);
                     let old = receive_unreliable_channels.insert(channel_config.channel_id, channel);
                     assert!(old.is_none(), "already exists receive channel {}", channel_config.channel_id);
                 }
@@ -167,51 +698,524 @@ impl RenetClient {
             }
         }
 
+        let mut stats = ConnectionStats::new();
+        stats.set_bandwidth_smoothing_factor(config.bandwidth_smoothing_factor);
+
         Self {
             packet_sequence: 0,
             current_time: Duration::ZERO,
             sent_packets: BTreeMap::new(),
             pending_acks: Vec::new(),
+            ack_delay: config.ack_delay.map(|ack_delay| ack_delay.min(MAX_ACK_DELAY)),
+            padding: config.padding.validated(),
+            unsent_ack_since: None,
             channel_send_order,
             send_unreliable_channels,
             receive_unreliable_channels,
             send_reliable_channels,
             receive_reliable_channels,
-            stats: ConnectionStats::new(),
+            send_channels_config: send_channels_config.to_vec(),
+            receive_channels_config: receive_channels_config.to_vec(),
+            retired_receive_channels: HashMap::new(),
+            max_received_sequence: None,
+            drain_policy: config.drain_policy.clone(),
+            drain_index: 0,
+            drain_taken: 0,
+            unknown_channel_policy: config.unknown_channel_policy,
+            unknown_channel_messages: 0,
+            blocked_channels: HashMap::new(),
+            established_client_id: None,
+            status_handle: None,
+            stats,
             rtt: 0.0,
-            available_bytes_per_tick,
+            rtt_reference: 0.0,
+            rtt_event_threshold: config.rtt_event_threshold,
+            congestion_controller: match &config.congestion_controller {
+                Some(congestion_controller) => congestion_controller.clone(),
+                None => Box::new(FixedBytesPerTick::new(config.available_bytes_per_tick)),
+            },
+            packet_loss_warm_up_packets: config.packet_loss_warm_up_packets,
+            max_total_reassembly_bytes: config.max_total_reassembly_bytes,
+            reassembly_evictions: 0,
+            max_reassemblies_per_update: config.max_reassemblies_per_update,
+            reassemblies_this_update: 0,
+            deferred_reassemblies: VecDeque::new(),
+            deferred_reassembly_bytes: 0,
+            acks_sent_this_update: 0,
+            acks_received_this_update: 0,
+            max_packets_per_second: config.max_packets_per_second,
+            packet_budget: config.max_packets_per_second.unwrap_or(0) as f64,
+            deferred_packets: VecDeque::new(),
+            dropped_deferred_packets: 0,
+            on_message_queued: None,
+            on_message_acked: None,
+            block_transfers: Vec::new(),
+            last_update_at: None,
+            clock_regressions: 0,
+            send_paused: false,
+            processing_time_request_interval: None,
+            processing_time_token: 0,
+            last_processing_time_request: None,
+            processing_time_reply: None,
+            server_processing_time: None,
+            session_id: None,
+            exchange_channel_ids: config.exchange_channel_ids,
+            channel_ids_acked: false,
+            remote_channel_ids: None,
+            unknown_remote_channel_messages: 0,
+            advertised_version: None,
+            advertised_version_acked: false,
+            server_version: None,
             disconnect_reason: None,
+            events: VecDeque::new(),
+            max_queued_events: config.max_queued_events,
+            dropped_events: 0,
+        }
+    }
+
+    /// Changes the channels of the connection without reconnecting.
+    /// Only the channels configuration is used, channels with an unchanged configuration keep their messages,
+    /// while removed or changed channels are handled according to the policy.
+    ///
+    /// The library doesn't negotiate the change, both peers must reconfigure to the same channels,
+    /// for example after agreeing to it with a message over a reliable channel that is not changed.
+    /// Messages received in removed channels are ignored, instead of disconnecting.
+    /// Channels changed in place start with an empty receive state, and ignore the packets with a lower sequence
+    /// than the newest packet received before reconfiguring, since they were sent with the old configuration.
+    /// When this side reconfigures first, the peer should drain its changed send channels before reconfiguring,
+    /// since its packets sent in the meantime cannot be told apart.
+    /// Removed and changed channels stop being tracked after 3 seconds, when their old packets are considered lost.
+    ///
+    /// With [ReconfigurePolicy::RequireDrained], no channel is changed if it fails.
+    pub fn reconfigure_channels(&mut self, config: &ConnectionConfig, policy: ReconfigurePolicy) -> Result<(), ReconfigureError> {
+        self.reconfigure_channels_internal(config, &config.client_channels_config, &config.server_channels_config, policy)
+    }
+
+    // When reconfiguring a client from the server, the server_channels_config are used as send channels,
+    // and the client_channels_config is used as recv channels.
+    pub(crate) fn reconfigure_channels_from_server(
+        &mut self,
+        config: &ConnectionConfig,
+        policy: ReconfigurePolicy,
+    ) -> Result<(), ReconfigureError> {
+        self.reconfigure_channels_internal(config, &config.server_channels_config, &config.client_channels_config, policy)
+    }
+
+    fn reconfigure_channels_internal(
+        &mut self,
+        config: &ConnectionConfig,
+        send_channels_config: &[ChannelConfig],
+        receive_channels_config: &[ChannelConfig],
+        policy: ReconfigurePolicy,
+    ) -> Result<(), ReconfigureError> {
+        let changed_send_channels: Vec<u8> = self
+            .send_channels_config
+            .iter()
+            .filter(|channel_config| !send_channels_config.contains(channel_config))
+            .map(|channel_config| channel_config.channel_id)
+            .collect();
+        let changed_receive_channels: Vec<u8> = self
+            .receive_channels_config
+            .iter()
+            .filter(|channel_config| !receive_channels_config.contains(channel_config))
+            .map(|channel_config| channel_config.channel_id)
+            .collect();
+
+        if policy == ReconfigurePolicy::RequireDrained {
+            let mut channel_ids: Vec<u8> = changed_send_channels
+                .iter()
+                .filter(|channel_id| !self.is_send_channel_drained(**channel_id))
+                .chain(
+                    changed_receive_channels
+                        .iter()
+                        .filter(|channel_id| !self.is_receive_channel_drained(**channel_id)),
+                )
+                .copied()
+                .collect();
+
+            if !channel_ids.is_empty() {
+                channel_ids.sort_unstable();
+                channel_ids.dedup();
+                return Err(ReconfigureError::ChannelsNotDrained { channel_ids });
+            }
+        }
+
+        // Create the new channels, and move the unchanged ones with their messages
+        let mut reconfigured = Self::from_channels(config, send_channels_config, receive_channels_config);
+        for channel_config in send_channels_config.iter().filter(|c| self.send_channels_config.contains(c)) {
+            let channel_id = channel_config.channel_id;
+            if let Some(channel) = self.send_reliable_channels.remove(&channel_id) {
+                reconfigured.send_reliable_channels.insert(channel_id, channel);
+            } else if let Some(channel) = self.send_unreliable_channels.remove(&channel_id) {
+                reconfigured.send_unreliable_channels.insert(channel_id, channel);
+            }
+        }
+        for channel_config in receive_channels_config.iter().filter(|c| self.receive_channels_config.contains(c)) {
+            let channel_id = channel_config.channel_id;
+            if let Some(channel) = self.receive_reliable_channels.remove(&channel_id) {
+                reconfigured.receive_reliable_channels.insert(channel_id, channel);
+            } else if let Some(channel) = self.receive_unreliable_channels.remove(&channel_id) {
+                reconfigured.receive_unreliable_channels.insert(channel_id, channel);
+            }
+        }
+
+        // Acks for messages of changed channels must not reach the new channels
+        for sent_packet in self.sent_packets.values_mut() {
+            let channel_id = match &sent_packet.info {
+                PacketSentInfo::ReliableMessages { channel_id, .. } | PacketSentInfo::ReliableSliceMessage { channel_id, .. } => {
+                    *channel_id
+                }
+                _ => continue,
+            };
+            if changed_send_channels.contains(&channel_id) {
+                sent_packet.info = PacketSentInfo::None;
+            }
+        }
+
+        // The chunks of block transfers in changed channels were discarded with the channels
+        self.block_transfers.retain(|transfer| {
+            let changed = changed_send_channels.contains(&transfer.channel_id);
+            if changed {
+                transfer.handle().cancel();
+            }
+            !changed
+        });
+
+        if !changed_receive_channels.is_empty() {
+            // The peer must learn the new receive channels
+            self.channel_ids_acked = false;
+            for sent_packet in self.sent_packets.values_mut() {
+                if matches!(sent_packet.info, PacketSentInfo::ChannelIds) {
+                    sent_packet.info = PacketSentInfo::None;
+                }
+            }
+        }
+
+        // The remaining send channels were changed or removed, their messages will never be acked
+        #[cfg(feature = "async")]
+        for channel in self.send_reliable_channels.values_mut() {
+            channel.notify_channel_removed();
+        }
+
+        self.channel_send_order = reconfigured.channel_send_order;
+        self.send_unreliable_channels = reconfigured.send_unreliable_channels;
+        self.receive_unreliable_channels = reconfigured.receive_unreliable_channels;
+        self.send_reliable_channels = reconfigured.send_reliable_channels;
+        self.receive_reliable_channels = reconfigured.receive_reliable_channels;
+        self.send_channels_config = reconfigured.send_channels_config;
+        self.receive_channels_config = reconfigured.receive_channels_config;
+        for channel_id in changed_receive_channels.iter() {
+            self.blocked_channels.remove(channel_id);
+        }
+        // Deferred slices belong to the old channels
+        self.discard_deferred_reassemblies(|deferred| changed_receive_channels.contains(&deferred.channel_id));
+        let sequence_fence = self.max_received_sequence.map_or(0, |sequence| sequence + 1);
+        for channel_id in changed_receive_channels {
+            let retired = RetiredChannel {
+                sequence_fence,
+                retired_at: self.current_time,
+            };
+            self.retired_receive_channels.insert(channel_id, retired);
+        }
+
+        Ok(())
+    }
+
+    fn is_send_channel_drained(&self, channel_id: u8) -> bool {
+        if let Some(reliable_channel) = self.send_reliable_channels.get(&channel_id) {
+            reliable_channel.is_drained()
+        } else if let Some(unreliable_channel) = self.send_unreliable_channels.get(&channel_id) {
+            unreliable_channel.is_drained()
+        } else {
+            true
+        }
+    }
+
+    fn is_receive_channel_drained(&self, channel_id: u8) -> bool {
+        if let Some(reliable_channel) = self.receive_reliable_channels.get(&channel_id) {
+            reliable_channel.is_drained()
+        } else if let Some(unreliable_channel) = self.receive_unreliable_channels.get(&channel_id) {
+            unreliable_channel.is_drained()
+        } else {
+            true
         }
     }
 
+    /// Returns the time the server took to reply to the last processing time request, or None if no reply was received yet.
+    ///
+    /// Periodically the client sends a request with a token, the server echoes it back with
+    /// the time between receiving the request and sending the reply, measured with its own clock.
+    /// Since only durations are exchanged, the clocks don't need to be synchronized.
+    /// The server measures it in its update ticks, so its resolution is the server update interval.
+    ///
+    /// Subtracting it from the [rtt][RenetClient::rtt] gives an estimate of the network latency.
+    /// Only measured when [ConnectionConfig::processing_time_request_interval] is set.
+    pub fn server_processing_time(&self) -> Option<Duration> {
+        self.server_processing_time
+    }
+
+    /// Tags the connection with an opaque application session id, to correlate it with backend records.
+    /// It's included in the [NetworkInfo] and in the logs of the connection, but it's never sent.
+    /// To have the server log the same id, include it in the user data of the connect token,
+    /// and set it there with [RenetServer::set_session_id][crate::RenetServer::set_session_id].
+    pub fn set_session_id(&mut self, session_id: u64) {
+        self.session_id = Some(session_id);
+    }
+
+    /// Returns the application session id of the connection, see [RenetClient::set_session_id].
+    pub fn session_id(&self) -> Option<u64> {
+        self.session_id
+    }
+
     /// Returns the round-time trip for the connection.
     pub fn rtt(&self) -> f64 {
         self.rtt
     }
 
     /// Returns the packet loss for the connection.
+    /// Returns 0.0 until enough packets were sent to measure it, see [RenetClient::packet_loss_available].
     pub fn packet_loss(&self) -> f64 {
+        if !self.packet_loss_available() {
+            return 0.0;
+        }
+
         self.stats.packet_loss()
     }
 
+    /// Returns true if enough packets were sent to measure the packet loss,
+    /// see [ConnectionConfig::packet_loss_warm_up_packets].
+    pub fn packet_loss_available(&self) -> bool {
+        self.stats.packet_loss_samples() >= self.packet_loss_warm_up_packets
+    }
+
+    /// Returns the number of bytes the connection is allowed to send in the next update, as decided by the
+    /// [congestion controller][ConnectionConfig::congestion_controller]. The default controller doesn't shrink it under loss,
+    /// use it together with the [rtt][RenetClient::rtt] and [packet loss][RenetClient::packet_loss] to follow the sending decisions.
+    pub fn congestion_window(&self) -> usize {
+        self.congestion_controller.available_bytes().min(usize::MAX as u64) as usize
+    }
+
+    /// Returns the number of messages received in channels that are not configured,
+    /// see [ConnectionConfig::unknown_channel_policy].
+    pub fn unknown_channel_messages(&self) -> u64 {
+        self.unknown_channel_messages
+    }
+
+    /// Returns the number of incomplete sliced messages dropped because the total memory
+    /// for reassembly was exceeded, see [ConnectionConfig::max_total_reassembly_bytes].
+    pub fn reassembly_evictions(&self) -> u64 {
+        self.reassembly_evictions
+    }
+
+    /// Returns the number of packets dropped because too many were deferred by the packet rate limit,
+    /// see [ConnectionConfig::max_packets_per_second].
+    pub fn dropped_deferred_packets(&self) -> u64 {
+        self.dropped_deferred_packets
+    }
+
+    /// Returns the number of messages dropped by [RenetClient::send_message] because the peer doesn't have their channel,
+    /// see [ConnectionConfig::exchange_channel_ids].
+    pub fn unknown_remote_channel_messages(&self) -> u64 {
+        self.unknown_remote_channel_messages
+    }
+
+    /// Returns all the diagnostic counters of the connection.
+    pub fn diagnostics(&self) -> DiagnosticsSnapshot {
+        DiagnosticsSnapshot {
+            unknown_channel_messages: self.unknown_channel_messages,
+            reassembly_evictions: self.reassembly_evictions,
+            stale_messages: self.receive_unreliable_channels.values().map(|c| c.stale_messages()).sum(),
+            dropped_events: self.dropped_events,
+            dropped_deferred_packets: self.dropped_deferred_packets,
+            unknown_remote_channel_messages: self.unknown_remote_channel_messages,
+            replay_drops: 0,
+            would_block_sends: 0,
+            discarded_packets: 0,
+        }
+    }
+
+    /// Resets all the diagnostic counters of the connection to zero, starting a new measurement window.
+    pub fn reset_all_diagnostics(&mut self) {
+        self.unknown_channel_messages = 0;
+        self.reassembly_evictions = 0;
+        self.dropped_events = 0;
+        self.dropped_deferred_packets = 0;
+        self.unknown_remote_channel_messages = 0;
+        for unreliable_channel in self.receive_unreliable_channels.values_mut() {
+            unreliable_channel.reset_stale_messages();
+        }
+    }
+
+    /// Returns the diagnostic counters and the network info in the Prometheus text exposition format,
+    /// labeled with the session id when set. The transport layer also reports its counters,
+    /// see [NetcodeClientTransport::metrics_text][crate::transport::NetcodeClientTransport::metrics_text].
+    #[cfg(feature = "prometheus")]
+    pub fn metrics_text(&self) -> String {
+        let labels: Vec<(&str, u64)> = self.session_id.iter().map(|&session_id| ("session_id", session_id)).collect();
+        crate::metrics::metrics_text(&labels, &self.diagnostics(), &self.network_info())
+    }
+
+    /// Returns the number of ack packets sent since the last [update][RenetClient::update].
+    /// Acks are sent while there are received packets not yet known to be acknowledged by the peer,
+    /// so a connection that keeps receiving but stops sending acks will stall the reliable channels of the peer.
+    pub fn acks_sent_this_update(&self) -> u64 {
+        self.acks_sent_this_update
+    }
+
+    /// Returns the number of ack packets received since the last [update][RenetClient::update].
+    /// When it stays at 0 while sending reliable messages, they are resent until the acks arrive again.
+    pub fn acks_received_this_update(&self) -> u64 {
+        self.acks_received_this_update
+    }
+
     /// Returns the bytes sent per second in the connection.
     pub fn bytes_sent_per_sec(&self) -> f64 {
         self.stats.bytes_sent_per_second(self.current_time)
     }
 
+    /// Returns the packets sent per second in the connection.
+    pub fn packets_sent_per_sec(&self) -> f64 {
+        self.stats.packets_sent_per_second(self.current_time)
+    }
+
+    /// Returns the messages queued to be sent per second in the connection,
+    /// compare it with [RenetClient::packets_sent_per_sec] to measure the aggregation and slicing of messages.
+    pub fn messages_sent_per_sec(&self) -> f64 {
+        self.stats.messages_sent_per_second(self.current_time)
+    }
+
     /// Returns the bytes received per second in the connection.
     pub fn bytes_received_per_sec(&self) -> f64 {
         self.stats.bytes_received_per_second(self.current_time)
     }
 
+    /// Returns the weight of each new sample in the bandwidth average, see [ConnectionConfig::bandwidth_smoothing_factor].
+    pub fn bandwidth_smoothing_factor(&self) -> f64 {
+        self.stats.bandwidth_smoothing_factor()
+    }
+
+    /// Changes the weight of each new sample in the bandwidth average, see [ConnectionConfig::bandwidth_smoothing_factor].
+    /// The average measured so far is kept, so the next values move from it at the new rate.
+    /// Factors above 1.0 are clamped to it, factors not greater than 0.0 are ignored and keep the current one.
+    pub fn set_bandwidth_smoothing_factor(&mut self, factor: f64) {
+        self.stats.set_bandwidth_smoothing_factor(factor);
+    }
+
+    /// Returns the current throughput of the uplink, from this side to the peer, in kilobits per second.
+    pub fn uplink_kbps(&self) -> f64 {
+        self.bytes_sent_per_sec() * 8.0 / 1000.0
+    }
+
+    /// Returns the current throughput of the downlink, from the peer to this side, in kilobits per second.
+    pub fn downlink_kbps(&self) -> f64 {
+        self.bytes_received_per_sec() * 8.0 / 1000.0
+    }
+
+    /// Returns the capacity of the uplink in kilobits per second, as estimated by the congestion controller,
+    /// or None if the controller doesn't estimate it, see [CongestionController::estimated_capacity].
+    /// The downlink capacity is estimated by the controller of the peer.
+    pub fn uplink_capacity_kbps(&self) -> Option<f64> {
+        self.congestion_controller
+            .estimated_capacity()
+            .map(|bytes_per_second| bytes_per_second * 8.0 / 1000.0)
+    }
+
     /// Returns all network informations for the connection.
     pub fn network_info(&self) -> NetworkInfo {
         NetworkInfo {
             rtt: self.rtt,
-            packet_loss: self.stats.packet_loss(),
+            packet_loss: self.packet_loss(),
+            packet_loss_available: self.packet_loss_available(),
             bytes_sent_per_second: self.stats.bytes_sent_per_second(self.current_time),
             bytes_received_per_second: self.stats.bytes_received_per_second(self.current_time),
+            packets_sent_per_second: self.stats.packets_sent_per_second(self.current_time),
+            messages_sent_per_second: self.stats.messages_sent_per_second(self.current_time),
+            session_id: self.session_id,
+            current_time: self.current_time,
+            total_bytes_sent: self.stats.total_bytes_sent(),
+            total_bytes_received: self.stats.total_bytes_received(),
+            total_packets_sent: self.stats.total_packets_sent(),
+            total_messages_sent: self.stats.total_messages_sent(),
+        }
+    }
+
+    /// Returns the change of each metric since an earlier reading of [RenetClient::network_info],
+    /// see [NetworkInfo::delta].
+    pub fn network_info_since(&self, earlier: &NetworkInfo) -> NetworkInfoDelta {
+        self.network_info().delta(earlier)
+    }
+
+    /// Returns a summary of the messages that have not been delivered yet in each send channel.
+    /// Call it before (or right after) disconnecting to know which data is lost with the connection.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet::{ConnectionConfig, DefaultChannel, RenetClient};
+    /// # let mut client = RenetClient::new(ConnectionConfig::default());
+    /// client.send_message(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+    /// let report = client.disconnect_report();
+    /// client.disconnect();
+    /// println!("Disconnected with {} unacked messages", report.unsent_messages() + report.in_flight_messages());
+    /// ```
+    pub fn disconnect_report(&self) -> DisconnectReport {
+        let channels = self
+            .channel_send_order
+            .iter()
+            .map(|order| match order {
+                ChannelOrder::Reliable(channel_id) => {
+                    let channel = &self.send_reliable_channels[channel_id];
+                    ChannelReport {
+                        channel_id: *channel_id,
+                        unsent_messages: channel.unsent_messages(),
+                        in_flight_messages: channel.in_flight_messages(),
+                    }
+                }
+                ChannelOrder::Unreliable(channel_id) => ChannelReport {
+                    channel_id: *channel_id,
+                    unsent_messages: self.send_unreliable_channels[channel_id].unsent_messages(),
+                    in_flight_messages: 0,
+                },
+            })
+            .collect();
+
+        DisconnectReport { channels }
+    }
+
+    /// Returns a client event if available
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet::{RenetClient, ConnectionConfig, ClientEvent};
+    /// # let mut client = RenetClient::new(ConnectionConfig::default());
+    /// while let Some(event) = client.get_event() {
+    ///     match event {
+    ///         ClientEvent::ConnectionEstablished(connection) => println!("Connected with id {}", connection.client_id),
+    ///         ClientEvent::RttImproved { new_rtt } => println!("RTT improved to {new_rtt}"),
+    ///         ClientEvent::RttDegraded { new_rtt } => println!("RTT degraded to {new_rtt}"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn get_event(&mut self) -> Option<ClientEvent> {
+        self.events.pop_front()
+    }
+
+    /// Returns the number of events dropped because the queue was full, see [ConnectionConfig::max_queued_events].
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
+
+    fn push_event(&mut self, event: ClientEvent) {
+        if self.max_queued_events == 0 {
+            self.dropped_events += 1;
+            return;
         }
+
+        if self.events.len() >= self.max_queued_events {
+            self.events.pop_front();
+            self.dropped_events += 1;
+        }
+        self.events.push_back(event);
     }
 
     /// Returns if the client is disconnected.
@@ -238,6 +1242,31 @@ impl RenetClient {
         self.disconnect_reason = Some(DisconnectReason::DisconnectedByClient);
     }
 
+    /// Notifies that the connection was established, emitting a [ClientEvent::ConnectionEstablished].
+    /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
+    /// <strong>Note:</strong> This should only be called by the transport layer.
+    /// </p>
+    pub fn connection_established(&mut self, connection: ConnectionEstablished) {
+        self.established_client_id = Some(connection.client_id);
+        self.push_event(ClientEvent::ConnectionEstablished(connection));
+        self.store_status();
+    }
+
+    /// Returns a read-only handle to the status of the connection, refreshed in every [update][RenetClient::update].
+    /// The handle can be cloned and sent to other threads, see [StatusHandle].
+    pub fn status_handle(&mut self) -> StatusHandle {
+        let status_handle = self.status_handle.get_or_insert_with(StatusHandle::default).clone();
+        self.store_status();
+        status_handle
+    }
+
+    fn store_status(&self) {
+        if let Some(status_handle) = &self.status_handle {
+            let connected = self.established_client_id.is_some() && !self.is_disconnected();
+            status_handle.store(connected, self.is_disconnected(), self.established_client_id, &self.network_info());
+        }
+    }
+
     /// Disconnect the client because an error occurred in the transport layer.
     /// If the client is already disconnected, it does nothing.
     /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
@@ -263,6 +1292,64 @@ impl RenetClient {
         }
     }
 
+    /// Returns the maximum size in bytes of a message that can be sent over the given channel,
+    /// or None if the channel doesn't exist.
+    /// Reliable channels are limited by their memory, since sliced messages are sent over multiple updates.
+    /// Unreliable channels are also limited by the bytes available per tick, since messages that cannot be sent
+    /// in a single update are dropped.
+    pub fn max_message_size<I: Into<u8>>(&self, channel_id: I) -> Option<usize> {
+        let channel_id = channel_id.into();
+        if let Some(reliable_channel) = self.send_reliable_channels.get(&channel_id) {
+            Some(reliable_channel.max_message_size())
+        } else {
+            self.send_unreliable_channels
+                .get(&channel_id)
+                .map(|unreliable_channel| unreliable_channel.max_message_size(self.congestion_controller.available_bytes()))
+        }
+    }
+
+    /// Returns the number of sliced messages being sent in a reliable channel,
+    /// see [ChannelConfig::max_concurrent_block_transfers]. Returns 0 for other channels.
+    pub fn active_block_transfers<I: Into<u8>>(&self, channel_id: I) -> usize {
+        match self.send_reliable_channels.get(&channel_id.into()) {
+            Some(reliable_channel) => reliable_channel.active_block_transfers(),
+            None => 0,
+        }
+    }
+
+    /// Returns a best-effort estimate of the time until a message queued in a reliable channel is delivered.
+    /// The unsent bytes of the message, and the ones queued before it in the channel, are projected
+    /// with the current send rate, plus half of the round-trip time for the last packet to arrive.
+    /// Message ids are reported by [RenetClient::on_message_queued].
+    ///
+    /// Returns None if the message was already acked, the channel is not reliable,
+    /// or nothing was sent recently to measure the send rate.
+    pub fn estimated_delivery_time<I: Into<u8>>(&self, channel_id: I, message_id: u64) -> Option<Duration> {
+        let reliable_channel = self.send_reliable_channels.get(&channel_id.into())?;
+        let unsent_bytes = reliable_channel.unsent_bytes_until(message_id)?;
+        let mut estimate = self.rtt / 2.0;
+        if unsent_bytes > 0 {
+            let bytes_sent_per_sec = self.bytes_sent_per_sec();
+            // Also NaN before any time has passed
+            if !bytes_sent_per_sec.is_normal() {
+                return None;
+            }
+            estimate += unsent_bytes as f64 / bytes_sent_per_sec;
+        }
+
+        Some(Duration::from_secs_f64(estimate))
+    }
+
+    /// Returns the number of messages discarded in an unreliable channel because they were older
+    /// than [ChannelConfig::max_message_age], or in an unreliable sequenced channel because
+    /// a newer message was already received. Returns 0 for reliable channels.
+    pub fn stale_messages<I: Into<u8>>(&self, channel_id: I) -> u64 {
+        match self.receive_unreliable_channels.get(&channel_id.into()) {
+            Some(unreliable_channel) => unreliable_channel.stale_messages(),
+            None => 0,
+        }
+    }
+
     /// Checks if the channel can send a message with the given size in bytes.
     pub fn can_send_message<I: Into<u8>>(&self, channel_id: I, size_bytes: usize) -> bool {
         let channel_id = channel_id.into();
@@ -282,42 +1369,302 @@ impl RenetClient {
         }
 
         let channel_id = channel_id.into();
+        if self.is_unknown_remote_channel(channel_id) {
+            log::debug!("dropped message sent in channel {} because the peer doesn't have it", channel_id);
+            self.unknown_remote_channel_messages += 1;
+            return;
+        }
+
+        let message = message.into();
         if let Some(reliable_channel) = self.send_reliable_channels.get_mut(&channel_id) {
-            if let Err(error) = reliable_channel.send_message(message.into()) {
-                self.disconnect_reason = Some(DisconnectReason::SendChannelError { channel_id, error });
+            match reliable_channel.send_message(message.clone()) {
+                Ok(message_id) => self.message_queued(channel_id, message_id, &message),
+                Err(error) => self.disconnect_reason = Some(DisconnectReason::SendChannelError { channel_id, error }),
             }
         } else if let Some(unreliable_channel) = self.send_unreliable_channels.get_mut(&channel_id) {
-            unreliable_channel.send_message(message.into());
+            if let Some(message_id) = unreliable_channel.send_message(message.clone()) {
+                self.message_queued(channel_id, message_id, &message);
+            }
         } else {
             panic!("Called 'send_message' with invalid channel {channel_id}");
         }
     }
 
-    /// Receive a message from the server over a channel.
-    pub fn receive_message<I: Into<u8>>(&mut self, channel_id: I) -> Option<Bytes> {
-        if self.is_disconnected() {
-            return None;
-        }
-
+    /// Send a message over a channel, failing if the peer doesn't have the channel.
+    /// The channels of the peer are only known with [ConnectionConfig::exchange_channel_ids],
+    /// until they are received, or when disabled, it behaves like [RenetClient::send_message].
+    pub fn try_send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) -> Result<(), SendError> {
         let channel_id = channel_id.into();
-        if let Some(reliable_channel) = self.receive_reliable_channels.get_mut(&channel_id) {
-            reliable_channel.receive_message()
-        } else if let Some(unreliable_channel) = self.receive_unreliable_channels.get_mut(&channel_id) {
-            unreliable_channel.receive_message()
-        } else {
-            panic!("Called 'receive_message' with invalid channel {channel_id}");
+        if self.is_unknown_remote_channel(channel_id) {
+            return Err(SendError::UnknownRemoteChannel(channel_id));
         }
+
+        self.send_message(channel_id, message);
+        Ok(())
     }
 
-    /// Advances the client by the duration.
-    /// Should be called every tick
-    pub fn update(&mut self, duration: Duration) {
-        self.current_time += duration;
-        self.stats.update(self.current_time);
+    /// Returns the version advertised by the server, or None if it doesn't advertise one or it was not received yet,
+    /// see [ConnectionConfig::server_version].
+    pub fn server_version(&self) -> Option<&str> {
+        self.server_version.as_deref()
+    }
 
-        for unreliable_channel in self.receive_unreliable_channels.values_mut() {
-            unreliable_channel.discard_incomplete_old_slices(self.current_time);
-        }
+    /// Returns the ids of the channels the peer receives on, or None if they were not received yet,
+    /// see [ConnectionConfig::exchange_channel_ids].
+    pub fn remote_channel_ids(&self) -> Option<&[u8]> {
+        self.remote_channel_ids.as_deref()
+    }
+
+    fn is_unknown_remote_channel(&self, channel_id: u8) -> bool {
+        match &self.remote_channel_ids {
+            Some(remote_channel_ids) => !remote_channel_ids.contains(&channel_id),
+            None => false,
+        }
+    }
+
+    /// Stops sending messages in all channels, while acks are still sent and messages are still received.
+    /// The connection is kept alive, since the transport layer sends keep-alive packets when there is nothing to send.
+    /// Messages sent while paused are queued and sent after [RenetClient::resume_send].
+    ///
+    /// The queues keep growing while paused, limited by the memory of each channel:
+    /// unreliable channels drop new messages when full, but reliable channels disconnect.
+    /// Use [RenetClient::can_send_message] to avoid that.
+    pub fn pause_send(&mut self) {
+        self.send_paused = true;
+    }
+
+    /// Resumes sending messages, the queued messages are sent over the next updates
+    /// within the available bytes per tick.
+    pub fn resume_send(&mut self) {
+        self.send_paused = false;
+    }
+
+    /// Returns true if sending messages is paused, see [RenetClient::pause_send].
+    pub fn is_send_paused(&self) -> bool {
+        self.send_paused
+    }
+
+    /// Sets a hook called for every message queued to be sent, with the channel id,
+    /// the id assigned to the message in the channel and its bytes, before it's sliced or packed with other messages.
+    /// Messages dropped before being queued, for example when the channel memory is full, are not observed.
+    pub fn on_message_queued<F: FnMut(u8, u64, &[u8]) + Send + Sync + 'static>(&mut self, hook: F) {
+        self.on_message_queued = Some(MessageQueuedHook(Box::new(hook)));
+    }
+
+    /// Sets a hook called for every message acknowledged in a reliable channel, with the channel id,
+    /// the id assigned to the message in the channel and its round-trip time:
+    /// the time between sending the packet that was acknowledged and receiving its acknowledgement.
+    /// For sliced messages, it's called when the last slice is acknowledged.
+    pub fn on_message_acked<F: FnMut(u8, u64, Duration) + Send + Sync + 'static>(&mut self, hook: F) {
+        self.on_message_acked = Some(MessageAckedHook(Box::new(hook)));
+    }
+
+    fn message_queued(&mut self, channel_id: u8, message_id: u64, message: &[u8]) {
+        self.stats.sent_message();
+        if let Some(MessageQueuedHook(hook)) = self.on_message_queued.as_mut() {
+            hook(channel_id, message_id, message);
+        }
+    }
+
+    fn message_acked(&mut self, channel_id: u8, message_id: u64, round_trip: Duration) {
+        if let Some(MessageAckedHook(hook)) = self.on_message_acked.as_mut() {
+            hook(channel_id, message_id, round_trip);
+        }
+        if let Some(transfer) = self.block_transfers.iter_mut().find(|transfer| transfer.channel_id == channel_id) {
+            // Transfers in the same channel are queued one after the other, so only the first one can have chunks in flight
+            transfer.chunk_acked(message_id);
+        }
+    }
+
+    /// Sends a large payload over a reliable ordered channel, split in chunks of up to 64KB queued as the channel has memory
+    /// available, so the payload doesn't need to fit in the channel memory. Returns a handle to follow the progress
+    /// of the transfer, pause it or cancel it. The chunks are queued in the next updates.
+    ///
+    /// The peer receives the chunks as consecutive messages in the channel, concatenating them gives the payload.
+    /// Transfers in the same channel are sent one after the other, but other messages sent in the channel are
+    /// interleaved with the chunks, use a channel dedicated to the transfers to receive the payload contiguously.
+    ///
+    /// Panics if the channel is not reliable ordered.
+    pub fn send_block<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, data: B) -> BlockHandle {
+        let channel_id = channel_id.into();
+        let reliable_ordered = self
+            .send_channels_config
+            .iter()
+            .any(|config| config.channel_id == channel_id && matches!(config.send_type, SendType::ReliableOrdered { .. }));
+        if !reliable_ordered {
+            panic!("Called 'send_block' with invalid reliable ordered channel {channel_id}");
+        }
+
+        let chunk_size = BLOCK_CHUNK_SIZE.min(self.send_reliable_channels[&channel_id].max_message_size());
+        let transfer = BlockTransfer::new(channel_id, data.into(), chunk_size, self.current_time);
+        let handle = transfer.handle();
+        self.block_transfers.push(transfer);
+        handle
+    }
+
+    /// Returns the number of block transfers not finished yet, see [RenetClient::send_block].
+    pub fn active_block_sends(&self) -> usize {
+        self.block_transfers.len()
+    }
+
+    fn update_block_transfers(&mut self) {
+        let mut transfers = std::mem::take(&mut self.block_transfers);
+        transfers.retain(|transfer| !transfer.is_finished());
+
+        let mut busy_channels: Vec<u8> = Vec::new();
+        for transfer in transfers.iter_mut() {
+            // Only the first transfer of a channel queues chunks, so each payload is sent contiguously
+            if busy_channels.contains(&transfer.channel_id) {
+                continue;
+            }
+            busy_channels.push(transfer.channel_id);
+
+            let Some(channel) = self.send_reliable_channels.get_mut(&transfer.channel_id) else {
+                continue;
+            };
+            for (message_id, chunk) in transfer.queue_chunks(channel, self.current_time) {
+                self.message_queued(transfer.channel_id, message_id, &chunk);
+            }
+        }
+
+        transfers.retain(|transfer| !transfer.is_finished());
+        self.block_transfers = transfers;
+    }
+
+    /// Send a message to the server over a reliable channel.
+    /// Returns a future that resolves when the message is acknowledged,
+    /// or with an [AckError] if the message will never be acknowledged.
+    ///
+    /// Panics if the channel is not reliable.
+    #[cfg(feature = "async")]
+    pub fn send_message_with_ack<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) -> MessageAcked {
+        if let Some(reason) = self.disconnect_reason {
+            return MessageAcked::resolved(Err(AckError::Disconnected(reason)));
+        }
+
+        let channel_id = channel_id.into();
+        let Some(reliable_channel) = self.send_reliable_channels.get_mut(&channel_id) else {
+            panic!("Called 'send_message_with_ack' with invalid reliable channel {channel_id}");
+        };
+
+        let message = message.into();
+        match reliable_channel.send_message_with_ack(message.clone()) {
+            Ok((message_id, acked)) => {
+                self.message_queued(channel_id, message_id, &message);
+                acked
+            }
+            Err(error) => {
+                let reason = DisconnectReason::SendChannelError { channel_id, error };
+                self.disconnect_reason = Some(reason);
+                MessageAcked::resolved(Err(AckError::Disconnected(reason)))
+            }
+        }
+    }
+
+    /// Returns true if the reliable ordered channel has received messages that cannot be delivered
+    /// until an older missing message arrives (head-of-line blocking).
+    /// A [ClientEvent::ChannelUnblocked] is emitted when the missing message is received.
+    pub fn channel_is_blocked<I: Into<u8>>(&self, channel_id: I) -> bool {
+        self.blocked_channels.contains_key(&channel_id.into())
+    }
+
+    /// Receive a message from the server over a channel.
+    pub fn receive_message<I: Into<u8>>(&mut self, channel_id: I) -> Option<Bytes> {
+        if self.is_disconnected() {
+            return None;
+        }
+
+        let channel_id = channel_id.into();
+        if let Some(reliable_channel) = self.receive_reliable_channels.get_mut(&channel_id) {
+            reliable_channel.receive_message()
+        } else if let Some(unreliable_channel) = self.receive_unreliable_channels.get_mut(&channel_id) {
+            unreliable_channel.receive_message()
+        } else {
+            panic!("Called 'receive_message' with invalid channel {channel_id}");
+        }
+    }
+
+    /// Receive the next message from the server over any channel, with the id of its channel.
+    /// The channel is chosen by the [ConnectionConfig::drain_policy], so messages from several channels
+    /// are interleaved in the same order for the same messages received.
+    pub fn receive_next_message(&mut self) -> Option<(u8, Bytes)> {
+        let channels = self.receive_channels_config.len();
+        if self.is_disconnected() || channels == 0 {
+            return None;
+        }
+
+        if self.drain_policy == DrainPolicy::Sequential {
+            self.drain_index = 0;
+            self.drain_taken = 0;
+        }
+
+        // The current channel is visited again at the end, in case its weight was used up and the others are empty
+        for _ in 0..=channels {
+            let channel_id = self.receive_channels_config[self.drain_index % channels].channel_id;
+            if self.drain_taken < self.drain_policy.weight(channel_id) {
+                if let Some(message) = self.receive_message(channel_id) {
+                    self.drain_taken += 1;
+                    return Some((channel_id, message));
+                }
+            }
+            self.drain_index = (self.drain_index + 1) % channels;
+            self.drain_taken = 0;
+        }
+
+        None
+    }
+
+    /// Receive messages from the server over a channel, copying each one into the given buffers.
+    /// Returns the number of buffers filled, the buffers are cleared before being filled and
+    /// reuse their capacity, so no allocation is needed if they are large enough.
+    pub fn receive_into<I: Into<u8>>(&mut self, channel_id: I, bufs: &mut [&mut Vec<u8>]) -> usize {
+        let channel_id = channel_id.into();
+        let mut filled = 0;
+        for buf in bufs.iter_mut() {
+            let Some(message) = self.receive_message(channel_id) else {
+                break;
+            };
+
+            buf.clear();
+            buf.extend_from_slice(&message);
+            filled += 1;
+        }
+
+        filled
+    }
+
+    /// Advances the client by the duration.
+    /// Should be called every tick
+    pub fn update(&mut self, duration: Duration) {
+        self.current_time += duration;
+        self.stats.update(self.current_time);
+        self.acks_sent_this_update = 0;
+        self.acks_received_this_update = 0;
+        let packet_loss = self.packet_loss();
+        self.congestion_controller.update(self.current_time, packet_loss);
+
+        if let Some(max_packets_per_second) = self.max_packets_per_second {
+            let budget = self.packet_budget + duration.as_secs_f64() * max_packets_per_second as f64;
+            self.packet_budget = budget.min(max_packets_per_second as f64);
+        }
+
+        #[cfg(feature = "async")]
+        if let Some(reason) = self.disconnect_reason {
+            for reliable_channel in self.send_reliable_channels.values_mut() {
+                reliable_channel.notify_disconnect(reason);
+            }
+        }
+
+        for unreliable_channel in self.receive_unreliable_channels.values_mut() {
+            unreliable_channel.discard_incomplete_old_slices(self.current_time);
+            unreliable_channel.discard_stale_messages(self.current_time);
+        }
+
+        // Packets sent before a reconfiguration are lost by now
+        const RETIRE_AFTER: Duration = Duration::from_secs(3);
+        let current_time = self.current_time;
+        self.retired_receive_channels
+            .retain(|_, retired| current_time - retired.retired_at < RETIRE_AFTER);
 
         // Discard lost packets
         let mut lost_packets: Vec<u64> = Vec::new();
@@ -334,7 +1681,81 @@ impl RenetClient {
 
         for sequence in lost_packets.iter() {
             self.sent_packets.remove(sequence);
+            self.congestion_controller.on_packet_lost(self.current_time);
+        }
+
+        // Complete the deferred reassemblies first, the ones above the limit are deferred again in the same order
+        self.reassemblies_this_update = 0;
+        self.deferred_reassembly_bytes = 0;
+        for deferred in std::mem::take(&mut self.deferred_reassemblies) {
+            if self.is_disconnected() {
+                break;
+            }
+            match deferred.reliable {
+                true => self.process_reliable_slice(deferred.channel_id, deferred.slice),
+                false => self.process_unreliable_slice(deferred.channel_id, deferred.slice),
+            }
+        }
+
+        if !self.is_disconnected() {
+            self.update_block_transfers();
         }
+
+        self.store_status();
+    }
+
+    /// Advances the connection to the time of an absolute clock, like the time since the start of the application,
+    /// updating it with the time elapsed since the previous call. The first call only sets the reference time.
+    ///
+    /// If the time is earlier than a previous one, usually a bug in the clock given by the engine, the connection
+    /// is updated without elapsed time and the latest time is kept as reference, so the timers are not corrupted
+    /// and the time is measured again once the clock catches up. A warning is logged, the regression is counted
+    /// in [RenetClient::clock_regressions] and returned as an error, that can be ignored to keep running.
+    ///
+    /// Giving the same time again, as a clock that didn't tick between two frames, is not a regression:
+    /// the connection is updated without elapsed time and the timers wait for the next time that advances.
+    pub fn update_at(&mut self, now: Duration) -> Result<(), ClockWentBackwards> {
+        let previous = self.last_update_at.unwrap_or(now);
+        if now < previous {
+            log::warn!(
+                "Clock went backwards by {:?}, updating the connection without elapsed time",
+                previous - now
+            );
+            self.clock_regressions += 1;
+            self.update(Duration::ZERO);
+            return Err(ClockWentBackwards { previous, now });
+        }
+
+        self.last_update_at = Some(now);
+        self.update(now - previous);
+        Ok(())
+    }
+
+    /// Returns the number of times [RenetClient::update_at] was given a time earlier than a previous one.
+    pub fn clock_regressions(&self) -> u64 {
+        self.clock_regressions
+    }
+
+    /// Returns true if the connection has work for the next [update][RenetClient::update] and
+    /// [get_packets_to_send][RenetClient::get_packets_to_send]: messages queued or waiting to be acked,
+    /// acks to send or not yet acknowledged by the peer, deferred reassemblies, block transfers not finished
+    /// or control packets not yet acked. The ack ranges are resent until the peer acks a packet carrying them,
+    /// so a connection that received packets keeps reporting work while it stays active.
+    /// Work that only depends on time passing, as the periodic processing time requests, is not reported.
+    pub fn has_pending_work(&self) -> bool {
+        if self.is_disconnected() {
+            return false;
+        }
+
+        self.unsent_ack_since.is_some()
+            || !self.pending_acks.is_empty()
+            || !self.deferred_reassemblies.is_empty()
+            || self.processing_time_reply.is_some()
+            || (self.exchange_channel_ids && !self.channel_ids_acked)
+            || (self.advertised_version.is_some() && !self.advertised_version_acked)
+            || self.send_reliable_channels.values().any(|channel| !channel.is_drained())
+            || self.send_unreliable_channels.values().any(|channel| !channel.is_drained())
+            || !self.block_transfers.is_empty()
     }
 
     /// Process a packet received from the server.
@@ -356,12 +1777,30 @@ impl RenetClient {
             Ok(packet) => packet,
         };
 
-        self.add_pending_ack(packet.sequence());
+        let sequence = packet.sequence();
+        self.add_pending_ack(sequence);
+        self.unsent_ack_since.get_or_insert(self.current_time);
+        self.max_received_sequence = self.max_received_sequence.max(Some(sequence));
+
+        if let Some(channel_id) = packet.channel_id() {
+            if self
+                .retired_receive_channels
+                .get(&channel_id)
+                .is_some_and(|retired| sequence < retired.sequence_fence)
+            {
+                log::debug!(
+                    "Ignored packet sent before reconfiguring channel {} (session {:?})",
+                    channel_id,
+                    self.session_id
+                );
+                return;
+            }
+        }
 
         match packet {
             Packet::SmallReliable { channel_id, messages, .. } => {
                 let Some(channel) = self.receive_reliable_channels.get_mut(&channel_id) else {
-                    self.disconnect_reason = Some(DisconnectReason::ReceivedInvalidChannelId(channel_id));
+                    self.received_invalid_channel(channel_id, messages.len());
                     return;
                 };
 
@@ -371,38 +1810,49 @@ impl RenetClient {
                         return;
                     }
                 }
+
+                self.check_channel_blocked(channel_id);
             }
             Packet::SmallUnreliable { channel_id, messages, .. } => {
                 let Some(channel) = self.receive_unreliable_channels.get_mut(&channel_id) else {
-                    self.disconnect_reason = Some(DisconnectReason::ReceivedInvalidChannelId(channel_id));
+                    self.received_invalid_channel(channel_id, messages.len());
                     return;
                 };
 
                 for message in messages {
-                    channel.process_message(message);
+                    channel.process_message(message, self.current_time);
                 }
             }
-            Packet::ReliableSlice { channel_id, slice, .. } => {
-                let Some(channel) = self.receive_reliable_channels.get_mut(&channel_id) else {
-                    self.disconnect_reason = Some(DisconnectReason::ReceivedInvalidChannelId(channel_id));
+            Packet::SmallUnreliableSequenced { channel_id, messages, .. } => {
+                let Some(channel) = self.receive_unreliable_channels.get_mut(&channel_id) else {
+                    self.received_invalid_channel(channel_id, messages.len());
                     return;
                 };
 
-                if let Err(error) = channel.process_slice(slice) {
-                    self.disconnect_reason = Some(DisconnectReason::ReceiveChannelError { channel_id, error });
+                for (message_id, message) in messages {
+                    channel.process_sequenced_message(message_id, message, self.current_time);
                 }
             }
-            Packet::UnreliableSlice { channel_id, slice, .. } => {
-                let Some(channel) = self.receive_unreliable_channels.get_mut(&channel_id) else {
-                    self.disconnect_reason = Some(DisconnectReason::ReceivedInvalidChannelId(channel_id));
-                    return;
-                };
-
-                if let Err(error) = channel.process_slice(slice, self.current_time) {
-                    self.disconnect_reason = Some(DisconnectReason::ReceiveChannelError { channel_id, error });
+            Packet::ReliableSlice { channel_id, slice, .. } => self.process_reliable_slice(channel_id, slice),
+            Packet::UnreliableSlice { channel_id, slice, .. } => self.process_unreliable_slice(channel_id, slice),
+            Packet::ChannelIds { channel_ids, .. } => {
+                self.remote_channel_ids = Some(channel_ids);
+            }
+            Packet::ServerVersion { version, .. } => {
+                self.server_version = Some(String::from_utf8_lossy(&version).into_owned());
+            }
+            Packet::ProcessingTimeRequest { token, .. } => {
+                self.processing_time_reply = Some((token, self.current_time));
+            }
+            Packet::ProcessingTimeResponse {
+                token, processing_time_us, ..
+            } => {
+                if token == self.processing_time_token {
+                    self.server_processing_time = Some(Duration::from_micros(processing_time_us));
                 }
             }
             Packet::Ack { ack_ranges, .. } => {
+                self.acks_received_this_update += 1;
                 // Create list with just new acks
                 // This prevents DoS from huge ack ranges
                 let mut new_acks: Vec<u64> = Vec::new();
@@ -423,12 +1873,18 @@ impl RenetClient {
                     } else {
                         self.rtt = self.rtt * 0.875 + rtt * 0.125;
                     }
+                    self.check_rtt_change();
+                    self.congestion_controller.on_packet_acked(self.current_time, rtt, self.rtt);
 
                     match sent_packet.info {
                         PacketSentInfo::ReliableMessages { channel_id, message_ids } => {
                             let reliable_channel = self.send_reliable_channels.get_mut(&channel_id).unwrap();
-                            for message_id in message_ids {
-                                reliable_channel.process_message_ack(message_id);
+                            let acked_message_ids: Vec<u64> = message_ids
+                                .into_iter()
+                                .filter(|&message_id| reliable_channel.process_message_ack(message_id))
+                                .collect();
+                            for message_id in acked_message_ids {
+                                self.message_acked(channel_id, message_id, self.current_time - sent_packet.sent_at);
                             }
                         }
                         PacketSentInfo::ReliableSliceMessage {
@@ -437,11 +1893,19 @@ impl RenetClient {
                             slice_index,
                         } => {
                             let reliable_channel = self.send_reliable_channels.get_mut(&channel_id).unwrap();
-                            reliable_channel.process_slice_message_ack(message_id, slice_index);
+                            if reliable_channel.process_slice_message_ack(message_id, slice_index) {
+                                self.message_acked(channel_id, message_id, self.current_time - sent_packet.sent_at);
+                            }
                         }
                         PacketSentInfo::Ack { largest_acked_packet } => {
                             self.acked_largest(largest_acked_packet);
                         }
+                        PacketSentInfo::ChannelIds => {
+                            self.channel_ids_acked = true;
+                        }
+                        PacketSentInfo::ServerVersion => {
+                            self.advertised_version_acked = true;
+                        }
                         PacketSentInfo::None => {}
                     }
                 }
@@ -449,6 +1913,161 @@ impl RenetClient {
         }
     }
 
+    fn reassembly_limit_reached(&self) -> bool {
+        self.max_reassemblies_per_update
+            .is_some_and(|max_reassemblies| self.reassemblies_this_update >= max_reassemblies)
+    }
+
+    fn process_reliable_slice(&mut self, channel_id: u8, slice: Slice) {
+        let reassembly_limit_reached = self.reassembly_limit_reached();
+        let Some(channel) = self.receive_reliable_channels.get_mut(&channel_id) else {
+            self.received_invalid_channel(channel_id, 1);
+            return;
+        };
+
+        let completes_message = channel.completes_message(&slice);
+        if completes_message && reassembly_limit_reached {
+            self.defer_reassembly(DeferredReassembly {
+                channel_id,
+                slice,
+                reliable: true,
+            });
+            return;
+        }
+
+        if let Err(error) = channel.process_slice(slice) {
+            self.disconnect_reason = Some(DisconnectReason::ReceiveChannelError { channel_id, error });
+            return;
+        }
+
+        if completes_message {
+            self.reassemblies_this_update += 1;
+        }
+        self.check_channel_blocked(channel_id);
+        self.enforce_reassembly_limit(channel_id);
+    }
+
+    fn process_unreliable_slice(&mut self, channel_id: u8, slice: Slice) {
+        let reassembly_limit_reached = self.reassembly_limit_reached();
+        let Some(channel) = self.receive_unreliable_channels.get_mut(&channel_id) else {
+            self.received_invalid_channel(channel_id, 1);
+            return;
+        };
+
+        let completes_message = channel.completes_message(&slice);
+        if completes_message && reassembly_limit_reached {
+            self.defer_reassembly(DeferredReassembly {
+                channel_id,
+                slice,
+                reliable: false,
+            });
+            return;
+        }
+
+        if let Err(error) = channel.process_slice(slice, self.current_time) {
+            self.disconnect_reason = Some(DisconnectReason::ReceiveChannelError { channel_id, error });
+            return;
+        }
+
+        if completes_message {
+            self.reassemblies_this_update += 1;
+        }
+        self.enforce_reassembly_limit(channel_id);
+    }
+
+    fn received_invalid_channel(&mut self, channel_id: u8, num_messages: usize) {
+        // Messages sent before the peer reconfigured its channels can still arrive
+        if self.retired_receive_channels.contains_key(&channel_id) {
+            log::debug!(
+                "Ignored message received in reconfigured channel {} (session {:?})",
+                channel_id,
+                self.session_id
+            );
+            return;
+        }
+
+        match self.unknown_channel_policy {
+            UnknownChannelPolicy::Drop => {
+                self.unknown_channel_messages += num_messages as u64;
+                log::debug!(
+                    "Dropped {} messages received in unknown channel {} (session {:?})",
+                    num_messages,
+                    channel_id,
+                    self.session_id
+                );
+            }
+            UnknownChannelPolicy::Event => {
+                self.unknown_channel_messages += num_messages as u64;
+                self.push_event(ClientEvent::UnknownChannelMessages { channel_id, num_messages });
+            }
+            UnknownChannelPolicy::Disconnect => {
+                self.disconnect_reason = Some(DisconnectReason::ReceivedInvalidChannelId(channel_id));
+            }
+        }
+    }
+
+    fn defer_reassembly(&mut self, deferred: DeferredReassembly) {
+        let channel_id = deferred.channel_id;
+        self.deferred_reassembly_bytes += deferred.slice.payload.len();
+        self.deferred_reassemblies.push_back(deferred);
+        self.enforce_reassembly_limit(channel_id);
+    }
+
+    fn enforce_reassembly_limit(&mut self, channel_id: u8) {
+        // Measuring the reassembly bytes walks every channel, skipped for each slice when there is no limit
+        if self.max_total_reassembly_bytes == usize::MAX {
+            return;
+        }
+
+        let reliable_bytes: usize = self.receive_reliable_channels.values().map(|c| c.reassembly_bytes()).sum();
+        let mut unreliable_bytes: usize = self.receive_unreliable_channels.values().map(|c| c.reassembly_bytes()).sum();
+
+        while reliable_bytes + unreliable_bytes + self.deferred_reassembly_bytes > self.max_total_reassembly_bytes {
+            let least_recent = self
+                .receive_unreliable_channels
+                .iter_mut()
+                .filter_map(|(id, channel)| channel.least_recent_reassembly().map(|last_received| (last_received, *id, channel)))
+                .min_by_key(|(last_received, _, _)| *last_received);
+
+            if let Some((_, evicted_channel_id, channel)) = least_recent {
+                let before = channel.reassembly_bytes();
+                let message_id = channel.discard_least_recent_reassembly();
+                unreliable_bytes -= before - channel.reassembly_bytes();
+                // The slice completing the evicted message could be deferred
+                self.discard_deferred_reassemblies(|deferred| {
+                    !deferred.reliable && deferred.channel_id == evicted_channel_id && Some(deferred.slice.message_id) == message_id
+                });
+                self.reassembly_evictions += 1;
+                continue;
+            }
+
+            // Deferred unreliable messages of a single slice have no reassembly to evict, the newest one is dropped instead
+            let Some(index) = self.deferred_reassemblies.iter().rposition(|deferred| !deferred.reliable) else {
+                self.disconnect_reason = Some(DisconnectReason::ReceiveChannelError {
+                    channel_id,
+                    error: ChannelError::ReassemblyMaxMemoryReached,
+                });
+                return;
+            };
+
+            let deferred = self.deferred_reassemblies.remove(index).expect("deferred reassembly should exist");
+            self.deferred_reassembly_bytes -= deferred.slice.payload.len();
+            self.reassembly_evictions += 1;
+        }
+    }
+
+    fn discard_deferred_reassemblies(&mut self, discard: impl Fn(&DeferredReassembly) -> bool) {
+        let mut discarded_bytes = 0;
+        self.deferred_reassemblies.retain(|deferred| {
+            let discarded = discard(deferred);
+            if discarded {
+                discarded_bytes += deferred.slice.payload.len();
+            }
+            !discarded
+        });
+        self.deferred_reassembly_bytes -= discarded_bytes;
+    }
+
     /// Returns a list of packets to be sent to the server.
     /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
     /// <strong>Note:</strong> This should only be called by the transport layer.
@@ -459,8 +2078,10 @@ impl RenetClient {
             return vec![];
         }
 
-        let mut available_bytes = self.available_bytes_per_tick;
-        for order in self.channel_send_order.iter() {
+        let mut available_bytes = self.congestion_controller.available_bytes();
+        // While paused, the messages remain queued in the channels
+        let channel_send_order: &[ChannelOrder] = if self.send_paused { &[] } else { &self.channel_send_order };
+        for order in channel_send_order.iter() {
             match order {
                 ChannelOrder::Reliable(channel_id) => {
                     let channel = self.send_reliable_channels.get_mut(channel_id).unwrap();
@@ -473,7 +2094,55 @@ impl RenetClient {
             }
         }
 
-        if !self.pending_acks.is_empty() {
+        if let Some((token, received_at)) = self.processing_time_reply.take() {
+            packets.push(Packet::ProcessingTimeResponse {
+                sequence: self.packet_sequence,
+                token,
+                processing_time_us: (self.current_time - received_at).as_micros() as u64,
+            });
+            self.packet_sequence += 1;
+        }
+
+        let request_processing_time = match (self.processing_time_request_interval, self.last_processing_time_request) {
+            (Some(interval), Some(last_request)) => self.current_time - last_request >= interval,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if request_processing_time {
+            // Replies to older requests are ignored, so lost requests don't need to be tracked
+            self.processing_time_token += 1;
+            self.last_processing_time_request = Some(self.current_time);
+            packets.push(Packet::ProcessingTimeRequest {
+                sequence: self.packet_sequence,
+                token: self.processing_time_token,
+            });
+            self.packet_sequence += 1;
+        }
+
+        if self.exchange_channel_ids && !self.channel_ids_acked {
+            // Sent every tick until acknowledged
+            packets.push(Packet::ChannelIds {
+                sequence: self.packet_sequence,
+                channel_ids: self.receive_channels_config.iter().map(|c| c.channel_id).collect(),
+            });
+            self.packet_sequence += 1;
+        }
+
+        if let (Some(version), false) = (&self.advertised_version, self.advertised_version_acked) {
+            // Sent every tick until acknowledged
+            packets.push(Packet::ServerVersion {
+                sequence: self.packet_sequence,
+                version: version.as_bytes().to_vec(),
+            });
+            self.packet_sequence += 1;
+        }
+
+        let send_ack = match self.ack_delay {
+            None => true,
+            Some(ack_delay) => self.unsent_ack_since.is_some_and(|since| self.current_time - since >= ack_delay),
+        };
+        if send_ack && !self.pending_acks.is_empty() {
+            self.unsent_ack_since = None;
             let ack_packet = Packet::Ack {
                 sequence: self.packet_sequence,
                 ack_ranges: self.pending_acks.clone(),
@@ -482,6 +2151,35 @@ impl RenetClient {
             packets.push(ack_packet);
         }
 
+        if let Some(max_packets_per_second) = self.max_packets_per_second {
+            // A new ack packet carries all the pending ranges, the deferred ones are redundant
+            if packets.iter().any(|packet| matches!(packet, Packet::Ack { .. })) {
+                self.deferred_packets.retain(|packet| !matches!(packet, Packet::Ack { .. }));
+            }
+
+            // Packets above the rate limit are deferred to the next calls
+            self.deferred_packets.extend(std::mem::take(&mut packets));
+            let num_packets = (self.packet_budget as usize).min(self.deferred_packets.len());
+            self.packet_budget -= num_packets as f64;
+            packets.extend(self.deferred_packets.drain(..num_packets));
+
+            // Acks deferred from previous calls are sent with the ranges pending now
+            for packet in packets.iter_mut() {
+                if let Packet::Ack { ack_ranges, .. } = packet {
+                    ack_ranges.clone_from(&self.pending_acks);
+                }
+            }
+
+            while self.deferred_packets.len() as u64 > max_packets_per_second {
+                log::debug!(
+                    "Dropped deferred packet, deferred packets exceeded {max_packets_per_second} packets (session {:?})",
+                    self.session_id
+                );
+                self.deferred_packets.pop_front();
+                self.dropped_deferred_packets += 1;
+            }
+        }
+
         let sent_at = self.current_time;
         for packet in packets.iter() {
             match packet {
@@ -518,7 +2216,7 @@ impl RenetClient {
                         },
                     );
                 }
-                Packet::SmallUnreliable { sequence, .. } => {
+                Packet::SmallUnreliable { sequence, .. } | Packet::SmallUnreliableSequenced { sequence, .. } => {
                     self.sent_packets.insert(
                         *sequence,
                         PacketSent {
@@ -527,7 +2225,27 @@ impl RenetClient {
                         },
                     );
                 }
-                Packet::UnreliableSlice { sequence, .. } => {
+                Packet::ChannelIds { sequence, .. } => {
+                    self.sent_packets.insert(
+                        *sequence,
+                        PacketSent {
+                            sent_at,
+                            info: PacketSentInfo::ChannelIds,
+                        },
+                    );
+                }
+                Packet::ServerVersion { sequence, .. } => {
+                    self.sent_packets.insert(
+                        *sequence,
+                        PacketSent {
+                            sent_at,
+                            info: PacketSentInfo::ServerVersion,
+                        },
+                    );
+                }
+                Packet::UnreliableSlice { sequence, .. }
+                | Packet::ProcessingTimeRequest { sequence, .. }
+                | Packet::ProcessingTimeResponse { sequence, .. } => {
                     self.sent_packets.insert(
                         *sequence,
                         PacketSent {
@@ -537,6 +2255,7 @@ impl RenetClient {
                     );
                 }
                 Packet::Ack { sequence, ack_ranges } => {
+                    self.acks_sent_this_update += 1;
                     let last_range = ack_ranges.last().unwrap();
                     let largest_acked_packet = last_range.end - 1;
                     self.sent_packets.insert(
@@ -563,8 +2282,14 @@ impl RenetClient {
                 Ok(len) => len,
             };
 
-            bytes_sent += len as u64;
-            serialized_packets.push(buffer[..len].to_vec());
+            // The padding is zeroed bytes after the packet, ignored when deserializing
+            let mut serialized_packet = buffer[..len].to_vec();
+            serialized_packet.resize(self.padding.padded_len(len), 0);
+
+            bytes_sent += serialized_packet.len() as u64;
+            self.congestion_controller
+                .on_packet_sent(self.current_time, serialized_packet.len() as u64);
+            serialized_packets.push(serialized_packet);
         }
 
         self.stats.sent_packets(serialized_packets.len() as u64, bytes_sent);
@@ -572,6 +2297,36 @@ impl RenetClient {
         serialized_packets
     }
 
+    fn check_channel_blocked(&mut self, channel_id: u8) {
+        let Some(channel) = self.receive_reliable_channels.get(&channel_id) else {
+            return;
+        };
+
+        if channel.is_blocked() {
+            self.blocked_channels.entry(channel_id).or_insert(self.current_time);
+        } else if let Some(blocked_at) = self.blocked_channels.remove(&channel_id) {
+            self.push_event(ClientEvent::ChannelUnblocked {
+                channel_id,
+                blocked_for: self.current_time - blocked_at,
+            });
+        }
+    }
+
+    fn check_rtt_change(&mut self) {
+        if self.rtt_reference < f64::EPSILON {
+            self.rtt_reference = self.rtt;
+            return;
+        }
+
+        if self.rtt < self.rtt_reference * (1.0 - self.rtt_event_threshold) {
+            self.rtt_reference = self.rtt;
+            self.push_event(ClientEvent::RttImproved { new_rtt: self.rtt });
+        } else if self.rtt > self.rtt_reference * (1.0 + self.rtt_event_threshold) {
+            self.rtt_reference = self.rtt;
+            self.push_event(ClientEvent::RttDegraded { new_rtt: self.rtt });
+        }
+    }
+
     fn add_pending_ack(&mut self, sequence: u64) {
         if self.pending_acks.is_empty() {
             self.pending_acks.push(sequence..sequence + 1);
@@ -650,6 +2405,8 @@ impl RenetClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::packet::{Slice, SLICE_SIZE};
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn pending_acks() {
@@ -720,4 +2477,1125 @@ mod tests {
         connection.update(Duration::from_secs(4));
         assert_eq!(connection.sent_packets.len(), 0);
     }
+
+    #[test]
+    fn rtt_events() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+
+        let mut exchange = |client: &mut RenetClient, delay: Duration| {
+            client.send_message(DefaultChannel::Unreliable, vec![1, 2, 3]);
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+            client.update(delay);
+            for packet in server.get_packets_to_send() {
+                client.process_packet(&packet);
+            }
+        };
+
+        // First measurement is the reference
+        exchange(&mut client, Duration::from_millis(100));
+        assert!(client.get_event().is_none());
+
+        while client.rtt() > 0.07 {
+            exchange(&mut client, Duration::from_millis(10));
+        }
+        assert!(matches!(client.get_event(), Some(ClientEvent::RttImproved { .. })));
+
+        while client.rtt() < 0.2 {
+            exchange(&mut client, Duration::from_millis(500));
+        }
+        let mut degraded = false;
+        while let Some(event) = client.get_event() {
+            degraded = matches!(event, ClientEvent::RttDegraded { .. });
+        }
+        assert!(degraded);
+    }
+
+    #[test]
+    fn rtt_event_threshold() {
+        let config = ConnectionConfig {
+            rtt_event_threshold: 0.5,
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+
+        let mut exchange = |client: &mut RenetClient, delay: Duration| {
+            client.send_message(DefaultChannel::Unreliable, vec![1, 2, 3]);
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+            client.update(delay);
+            for packet in server.get_packets_to_send() {
+                client.process_packet(&packet);
+            }
+        };
+
+        exchange(&mut client, Duration::from_millis(100));
+        // A 30% improvement is below the threshold
+        while client.rtt() > 0.07 {
+            exchange(&mut client, Duration::from_millis(10));
+        }
+        assert!(client.get_event().is_none());
+
+        while client.rtt() > 0.045 {
+            exchange(&mut client, Duration::from_millis(10));
+        }
+        assert!(matches!(client.get_event(), Some(ClientEvent::RttImproved { .. })));
+    }
+
+    #[test]
+    fn max_message_size() {
+        let config = ConnectionConfig {
+            available_bytes_per_tick: 1000,
+            ..Default::default()
+        };
+        let client = RenetClient::new(config);
+
+        assert_eq!(client.max_message_size(DefaultChannel::Unreliable), Some(1000));
+        assert_eq!(client.max_message_size(DefaultChannel::ReliableOrdered), Some(5 * 1024 * 1024));
+        assert_eq!(client.max_message_size(10), None);
+    }
+
+    #[test]
+    fn channel_send_order() {
+        let config = ConnectionConfig {
+            available_bytes_per_tick: 1000,
+            channel_send_order: Some(vec![DefaultChannel::ReliableOrdered.into()]),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        // Only one message fits in a tick
+        client.send_message(DefaultChannel::Unreliable, vec![0; 600]);
+        client.send_message(DefaultChannel::ReliableUnordered, vec![1; 600]);
+        client.send_message(DefaultChannel::ReliableOrdered, vec![2; 600]);
+
+        let order: Vec<u8> = client.disconnect_report().channels.iter().map(|c| c.channel_id).collect();
+        assert_eq!(order, vec![2, 0, 1]);
+
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert_eq!(server.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![2; 600]);
+        assert!(server.receive_message(DefaultChannel::Unreliable).is_none());
+        assert!(server.receive_message(DefaultChannel::ReliableUnordered).is_none());
+    }
+
+    #[test]
+    fn estimated_delivery_time() {
+        let config = ConnectionConfig {
+            available_bytes_per_tick: 1500,
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        let channel_id: u8 = DefaultChannel::ReliableOrdered.into();
+        client.send_message(channel_id, vec![0; 1000]);
+        client.send_message(channel_id, vec![1; 1000]);
+
+        // The send rate is not known yet
+        assert_eq!(client.estimated_delivery_time(channel_id, 1), None);
+
+        // Only the first message fits in the tick
+        client.update(Duration::from_millis(100));
+        let packets = client.get_packets_to_send();
+        assert_eq!(client.estimated_delivery_time(channel_id, 0), Some(Duration::ZERO));
+        assert!(client.estimated_delivery_time(channel_id, 1).unwrap() > Duration::ZERO);
+        assert_eq!(client.estimated_delivery_time(channel_id, 2), None);
+        assert_eq!(client.estimated_delivery_time(DefaultChannel::Unreliable, 0), None);
+
+        for packet in packets {
+            server.process_packet(&packet);
+        }
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        client.update(Duration::from_millis(100));
+        assert_eq!(client.estimated_delivery_time(channel_id, 0), None);
+    }
+
+    #[test]
+    fn disconnect_report() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        client.send_message(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        client.get_packets_to_send();
+        client.send_message(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+        client.send_message(DefaultChannel::Unreliable, vec![1, 2, 3]);
+
+        let report = client.disconnect_report();
+        let reliable = report
+            .channels
+            .iter()
+            .find(|c| c.channel_id == DefaultChannel::ReliableOrdered.into())
+            .unwrap();
+        assert_eq!(reliable.unsent_messages, 1);
+        assert_eq!(reliable.in_flight_messages, 1);
+        assert_eq!(report.unsent_messages(), 2);
+        assert_eq!(report.in_flight_messages(), 1);
+    }
+
+    #[test]
+    fn packet_loss_warm_up() {
+        let config = ConnectionConfig {
+            packet_loss_warm_up_packets: 10,
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config);
+
+        // No packets are acked, but there is not enough data to report it
+        for _ in 0..5 {
+            client.send_message(DefaultChannel::Unreliable, vec![1, 2, 3]);
+            client.get_packets_to_send();
+            client.update(Duration::from_millis(300));
+        }
+        assert!(!client.packet_loss_available());
+        assert_eq!(client.packet_loss(), 0.0);
+
+        for _ in 0..15 {
+            client.send_message(DefaultChannel::Unreliable, vec![1, 2, 3]);
+            client.get_packets_to_send();
+            client.update(Duration::from_millis(300));
+        }
+        assert!(client.packet_loss_available());
+        assert_eq!(client.packet_loss(), 1.0);
+    }
+
+    #[test]
+    fn max_total_reassembly_bytes() {
+        let config = ConnectionConfig {
+            max_total_reassembly_bytes: SLICE_SIZE * 4,
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config);
+
+        let process_slice = |client: &mut RenetClient, channel_id: DefaultChannel, message_id: u64| {
+            let slice = Slice {
+                message_id,
+                slice_index: 0,
+                num_slices: 3,
+                payload: vec![0; SLICE_SIZE].into(),
+            };
+            let packet = match channel_id {
+                DefaultChannel::Unreliable => Packet::UnreliableSlice {
+                    sequence: message_id,
+                    channel_id: channel_id.into(),
+                    slice,
+                },
+                _ => Packet::ReliableSlice {
+                    sequence: message_id,
+                    channel_id: channel_id.into(),
+                    slice,
+                },
+            };
+            let mut buffer = [0u8; 1400];
+            let mut oct = OctetsMut::with_slice(&mut buffer);
+            let len = packet.to_bytes(&mut oct).unwrap();
+            client.process_packet(&buffer[..len]);
+            client.update(Duration::from_millis(10));
+        };
+
+        // Second unreliable transfer evicts the first one
+        process_slice(&mut client, DefaultChannel::Unreliable, 0);
+        process_slice(&mut client, DefaultChannel::Unreliable, 1);
+        assert_eq!(client.reassembly_evictions(), 1);
+        assert!(!client.is_disconnected());
+
+        // Reliable transfers evict unreliable ones, but are never evicted
+        process_slice(&mut client, DefaultChannel::ReliableOrdered, 2);
+        assert_eq!(client.reassembly_evictions(), 2);
+        assert!(!client.is_disconnected());
+        process_slice(&mut client, DefaultChannel::ReliableOrdered, 3);
+        assert_eq!(
+            client.disconnect_reason(),
+            Some(DisconnectReason::ReceiveChannelError {
+                channel_id: DefaultChannel::ReliableOrdered.into(),
+                error: ChannelError::ReassemblyMaxMemoryReached
+            })
+        );
+    }
+
+    #[test]
+    fn max_packets_per_second() {
+        let config = ConnectionConfig {
+            max_packets_per_second: Some(10),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config);
+
+        for _ in 0..15 {
+            client.send_message(DefaultChannel::Unreliable, vec![0; 1000]);
+        }
+
+        // Each message needs its own packet, only the budget is sent
+        assert_eq!(client.get_packets_to_send().len(), 10);
+        assert_eq!(client.get_packets_to_send().len(), 0);
+
+        // Budget is refilled over time
+        client.update(Duration::from_millis(300));
+        assert_eq!(client.get_packets_to_send().len(), 3);
+        client.update(Duration::from_secs(1));
+        assert_eq!(client.get_packets_to_send().len(), 2);
+        assert_eq!(client.dropped_deferred_packets(), 0);
+
+        // Deferred packets above one second of packets are dropped and counted
+        for _ in 0..25 {
+            client.send_message(DefaultChannel::Unreliable, vec![0; 1000]);
+        }
+        assert_eq!(client.get_packets_to_send().len(), 8);
+        assert_eq!(client.dropped_deferred_packets(), 7);
+        assert_eq!(client.diagnostics().dropped_deferred_packets, 7);
+    }
+
+    #[test]
+    fn max_packets_per_second_deferred_acks() {
+        let config = ConnectionConfig {
+            max_packets_per_second: Some(1),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config);
+        let mut server = RenetClient::new(ConnectionConfig::default());
+
+        server.send_message(DefaultChannel::Unreliable, vec![1]);
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        // Uses the budget, the ack is deferred
+        client.send_message(DefaultChannel::Unreliable, vec![0; 1000]);
+        assert_eq!(client.get_packets_to_send().len(), 1);
+
+        // Received while the ack is deferred
+        server.send_message(DefaultChannel::Unreliable, vec![2]);
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+
+        client.update(Duration::from_secs(1));
+        let packets = client.get_packets_to_send();
+        assert_eq!(packets.len(), 1);
+        let packet = Packet::from_bytes(&mut octets::Octets::with_slice(&packets[0])).unwrap();
+        let Packet::Ack { ack_ranges, .. } = packet else {
+            panic!("expected an ack packet");
+        };
+        // Only one ack packet left and it acks both server packets
+        assert_eq!(ack_ranges, vec![0..2]);
+        assert!(client.deferred_packets.is_empty());
+    }
+
+    #[test]
+    fn on_message_queued() {
+        let mut connection = RenetClient::new(ConnectionConfig::default());
+        let queued = Arc::new(Mutex::new(Vec::new()));
+        let hook_queued = queued.clone();
+        connection.on_message_queued(move |channel_id, message_id, message| {
+            hook_queued.lock().unwrap().push((channel_id, message_id, message.to_vec()));
+        });
+
+        let sliced_message = vec![7; SLICE_SIZE * 2];
+        connection.send_message(DefaultChannel::ReliableOrdered, vec![1, 2]);
+        connection.send_message(DefaultChannel::ReliableOrdered, sliced_message.clone());
+        connection.send_message(DefaultChannel::Unreliable, vec![3]);
+        connection.get_packets_to_send();
+
+        let reliable_channel = u8::from(DefaultChannel::ReliableOrdered);
+        let unreliable_channel = u8::from(DefaultChannel::Unreliable);
+        assert_eq!(
+            *queued.lock().unwrap(),
+            vec![
+                (reliable_channel, 0, vec![1, 2]),
+                (reliable_channel, 1, sliced_message),
+                (unreliable_channel, 0, vec![3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn server_processing_time() {
+        let config = ConnectionConfig {
+            processing_time_request_interval: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert_eq!(client.server_processing_time(), None);
+
+        // Server replies in its next update
+        server.update(Duration::from_millis(16));
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        assert_eq!(client.server_processing_time(), Some(Duration::from_millis(16)));
+
+        // Server doesn't send requests
+        server.update(Duration::from_secs(1));
+        for packet in server.get_packets_to_send() {
+            let packet = Packet::from_bytes(&mut octets::Octets::with_slice(&packet)).unwrap();
+            assert!(!matches!(packet, Packet::ProcessingTimeRequest { .. }));
+        }
+    }
+
+    #[test]
+    fn pause_send() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+
+        server.send_message(DefaultChannel::ReliableOrdered, vec![1]);
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+
+        client.pause_send();
+        client.send_message(DefaultChannel::ReliableOrdered, vec![2]);
+        client.send_message(DefaultChannel::Unreliable, vec![3]);
+
+        // Only the ack is sent
+        let packets = client.get_packets_to_send();
+        assert_eq!(packets.len(), 1);
+        let packet = Packet::from_bytes(&mut octets::Octets::with_slice(&packets[0])).unwrap();
+        assert!(matches!(packet, Packet::Ack { .. }));
+        assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![1]);
+
+        client.resume_send();
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert_eq!(server.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![2]);
+        assert_eq!(server.receive_message(DefaultChannel::Unreliable).unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn on_message_acked() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+        let acked = Arc::new(Mutex::new(Vec::new()));
+        let hook_acked = acked.clone();
+        client.on_message_acked(move |channel_id, message_id, round_trip| {
+            hook_acked.lock().unwrap().push((channel_id, message_id, round_trip));
+        });
+
+        client.send_message(DefaultChannel::ReliableOrdered, vec![1]);
+        client.send_message(DefaultChannel::ReliableOrdered, vec![2; SLICE_SIZE * 2]);
+        client.send_message(DefaultChannel::Unreliable, vec![3]);
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+
+        server.update(Duration::from_millis(50));
+        client.update(Duration::from_millis(50));
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+
+        let channel_id = u8::from(DefaultChannel::ReliableOrdered);
+        let round_trip = Duration::from_millis(50);
+        let mut acked = acked.lock().unwrap().clone();
+        acked.sort();
+        assert_eq!(acked, vec![(channel_id, 0, round_trip), (channel_id, 1, round_trip)]);
+    }
+
+    #[test]
+    fn receive_into() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+        for i in 0..3 {
+            server.send_message(DefaultChannel::ReliableOrdered, vec![i; 3]);
+        }
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+
+        let mut first = Vec::with_capacity(16);
+        let mut second = vec![9; 10];
+        let filled = client.receive_into(DefaultChannel::ReliableOrdered, &mut [&mut first, &mut second]);
+        assert_eq!(filled, 2);
+        assert_eq!(first, vec![0; 3]);
+        assert_eq!(second, vec![1; 3]);
+
+        let filled = client.receive_into(DefaultChannel::ReliableOrdered, &mut [&mut first, &mut second]);
+        assert_eq!(filled, 1);
+        assert_eq!(first, vec![2; 3]);
+    }
+
+    #[test]
+    fn exchange_channel_ids() {
+        let mut client_config = ConnectionConfig {
+            exchange_channel_ids: true,
+            ..Default::default()
+        };
+        let server_config = client_config.clone();
+        // Channel not yet rolled out to the server
+        client_config
+            .client_channels_config
+            .push(ChannelConfig::new(3, 1024, SendType::Unreliable));
+        let mut client = RenetClient::new(client_config);
+        let mut server = RenetClient::new_from_server(server_config);
+
+        // Unknown until the server channels are received
+        assert!(client.try_send_message(3, vec![1]).is_ok());
+        client.get_packets_to_send();
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+
+        assert_eq!(client.remote_channel_ids(), Some(&[0, 1, 2][..]));
+        assert_eq!(client.try_send_message(3, vec![1]), Err(SendError::UnknownRemoteChannel(3)));
+        assert!(client.try_send_message(DefaultChannel::Unreliable, vec![2]).is_ok());
+        client.send_message(3, vec![3]);
+        client.send_message(3, vec![4]);
+        assert_eq!(client.unknown_remote_channel_messages(), 2);
+        assert_eq!(client.diagnostics().unknown_remote_channel_messages, 2);
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert!(!server.is_disconnected());
+        assert_eq!(server.receive_message(DefaultChannel::Unreliable).unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn congestion_controller() {
+        #[derive(Debug, Clone)]
+        struct HalveOnAck(u64);
+
+        impl CongestionController for HalveOnAck {
+            fn on_packet_acked(&mut self, _current_time: Duration, _rtt_sample: f64, _rtt: f64) {
+                self.0 /= 2;
+            }
+
+            fn available_bytes(&self) -> u64 {
+                self.0
+            }
+
+            fn box_clone(&self) -> Box<dyn CongestionController> {
+                Box::new(self.clone())
+            }
+        }
+
+        let config = ConnectionConfig {
+            available_bytes_per_tick: 1000,
+            congestion_controller: Some(Box::new(crate::NoCongestionControl)),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        client.send_message(DefaultChannel::ReliableOrdered, vec![0; 600]);
+        client.send_message(DefaultChannel::ReliableOrdered, vec![1; 600]);
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        // Not limited by the available bytes per tick
+        assert_eq!(server.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![0; 600]);
+        assert_eq!(server.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![1; 600]);
+
+        let config = ConnectionConfig {
+            congestion_controller: Some(Box::new(HalveOnAck(2000))),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        assert_eq!(client.congestion_window(), 2000);
+        assert_eq!(server.congestion_window(), 2000);
+
+        client.send_message(DefaultChannel::ReliableOrdered, vec![0; 100]);
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        assert_eq!(client.congestion_window(), 1000);
+        // Each connection has its own controller
+        assert_eq!(server.congestion_window(), 2000);
+    }
+
+    #[test]
+    fn acks_this_update() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+        client.send_message(DefaultChannel::ReliableOrdered, vec![0; 100]);
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert_eq!(client.acks_sent_this_update(), 0);
+
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        assert_eq!(server.acks_sent_this_update(), 1);
+        assert_eq!(client.acks_received_this_update(), 1);
+
+        client.update(Duration::from_millis(16));
+        server.update(Duration::from_millis(16));
+        assert_eq!(client.acks_received_this_update(), 0);
+        assert_eq!(server.acks_sent_this_update(), 0);
+    }
+
+    #[test]
+    fn channel_unblocked() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+        let channel_id: u8 = DefaultChannel::ReliableOrdered.into();
+
+        client.send_message(channel_id, vec![0]);
+        let lost_packets = client.get_packets_to_send();
+        client.send_message(channel_id, vec![1]);
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert!(server.channel_is_blocked(channel_id));
+        assert!(server.receive_message(channel_id).is_none());
+
+        server.update(Duration::from_millis(100));
+        for packet in lost_packets {
+            server.process_packet(&packet);
+        }
+        assert!(!server.channel_is_blocked(channel_id));
+        assert_eq!(
+            server.get_event(),
+            Some(ClientEvent::ChannelUnblocked {
+                channel_id,
+                blocked_for: Duration::from_millis(100)
+            })
+        );
+        assert_eq!(server.receive_message(channel_id).unwrap(), vec![0]);
+        assert_eq!(server.receive_message(channel_id).unwrap(), vec![1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn server_version_too_long() {
+        let config = ConnectionConfig {
+            server_version: Some("a".repeat(256)),
+            ..Default::default()
+        };
+        crate::RenetServer::new(config);
+    }
+
+    #[test]
+    fn server_version() {
+        let config = ConnectionConfig {
+            server_version: Some("1.2.3".to_string()),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        assert_eq!(client.server_version(), None);
+
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        assert_eq!(client.server_version(), Some("1.2.3"));
+        // The client doesn't advertise it
+        assert_eq!(server.server_version(), None);
+
+        // Stops being sent once acknowledged
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        assert!(server.advertised_version_acked);
+    }
+
+    #[test]
+    fn max_reassemblies_per_update() {
+        let config = ConnectionConfig {
+            max_reassemblies_per_update: Some(1),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        let channel_id: u8 = DefaultChannel::ReliableOrdered.into();
+        for i in 0..3 {
+            client.send_message(channel_id, vec![i; SLICE_SIZE * 2]);
+        }
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+
+        // One message completed in each update
+        for i in 0..3 {
+            assert_eq!(server.receive_message(channel_id).unwrap(), vec![i; SLICE_SIZE * 2]);
+            assert!(server.receive_message(channel_id).is_none());
+            server.update(Duration::from_millis(16));
+        }
+        assert!(server.deferred_reassemblies.is_empty());
+    }
+
+    #[test]
+    fn max_queued_events() {
+        let config = ConnectionConfig {
+            max_queued_events: 2,
+            unknown_channel_policy: UnknownChannelPolicy::Event,
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config);
+        for channel_id in 10..13 {
+            client.received_invalid_channel(channel_id, 1);
+        }
+
+        // The oldest event was dropped
+        assert_eq!(client.dropped_events(), 1);
+        assert_eq!(client.diagnostics().dropped_events, 1);
+        assert_eq!(
+            client.get_event(),
+            Some(ClientEvent::UnknownChannelMessages {
+                channel_id: 11,
+                num_messages: 1
+            })
+        );
+        assert_eq!(
+            client.get_event(),
+            Some(ClientEvent::UnknownChannelMessages {
+                channel_id: 12,
+                num_messages: 1
+            })
+        );
+        assert!(client.get_event().is_none());
+
+        client.reset_all_diagnostics();
+        assert_eq!(client.dropped_events(), 0);
+    }
+
+    #[test]
+    fn reconfigure_channel_in_place() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig::default());
+        let mut config = ConnectionConfig::default();
+        for channels_config in [&mut config.client_channels_config, &mut config.server_channels_config] {
+            channels_config[2].max_memory_usage_bytes = 1024 * 1024;
+        }
+
+        client.send_message(2, vec![0]);
+        let old_packets = client.get_packets_to_send();
+        client.send_message(1, vec![1]);
+        let sync_packets = client.get_packets_to_send();
+        client.reconfigure_channels(&config, ReconfigurePolicy::Discard).unwrap();
+        client.send_message(2, vec![2]);
+        let new_packets = client.get_packets_to_send();
+
+        // The server reconfigures after receiving a newer packet, the old one arrives late
+        for packet in sync_packets {
+            server.process_packet(&packet);
+        }
+        server
+            .reconfigure_channels_from_server(&config, ReconfigurePolicy::Discard)
+            .unwrap();
+        for packet in old_packets.iter().chain(new_packets.iter()) {
+            server.process_packet(packet);
+        }
+
+        // The message sent with the old configuration doesn't take the id of the new one
+        assert_eq!(server.receive_message(1).unwrap(), vec![1]);
+        assert_eq!(server.receive_message(2).unwrap(), vec![2]);
+        assert!(server.receive_message(2).is_none());
+        assert!(!server.is_disconnected());
+
+        server.update(Duration::from_secs(3));
+        assert!(server.retired_receive_channels.is_empty());
+    }
+
+    #[test]
+    fn deferred_reassemblies_memory_limit() {
+        let config = ConnectionConfig {
+            max_reassemblies_per_update: Some(1),
+            max_total_reassembly_bytes: SLICE_SIZE * 3,
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        let channel_id: u8 = DefaultChannel::Unreliable.into();
+        for i in 0..4 {
+            client.send_message(channel_id, vec![i; SLICE_SIZE * 2]);
+        }
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+
+        // Each deferred message is evicted with its reassembly when the next one starts
+        assert_eq!(server.reassembly_evictions(), 2);
+        assert_eq!(server.deferred_reassemblies.len(), 1);
+        assert_eq!(server.deferred_reassembly_bytes, SLICE_SIZE);
+
+        assert_eq!(server.receive_message(channel_id).unwrap(), vec![0; SLICE_SIZE * 2]);
+        server.update(Duration::from_millis(16));
+        assert_eq!(server.receive_message(channel_id).unwrap(), vec![3; SLICE_SIZE * 2]);
+        assert!(server.receive_message(channel_id).is_none());
+        assert!(server.deferred_reassemblies.is_empty());
+        assert_eq!(server.deferred_reassembly_bytes, 0);
+        assert!(!server.is_disconnected());
+    }
+
+    #[test]
+    fn unknown_channel_policy() {
+        for policy in [
+            UnknownChannelPolicy::Drop,
+            UnknownChannelPolicy::Event,
+            UnknownChannelPolicy::Disconnect,
+        ] {
+            let server_config = ConnectionConfig {
+                unknown_channel_policy: policy,
+                ..Default::default()
+            };
+            // Channel only configured in the client
+            let mut client_config = server_config.clone();
+            client_config
+                .client_channels_config
+                .push(ChannelConfig::new(3, 1024, SendType::Unreliable));
+            let mut client = RenetClient::new(client_config);
+            let mut server = RenetClient::new_from_server(server_config);
+
+            client.send_message(3, vec![1]);
+            client.send_message(3, vec![2]);
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+
+            match policy {
+                UnknownChannelPolicy::Drop => {
+                    assert!(!server.is_disconnected());
+                    assert_eq!(server.unknown_channel_messages(), 2);
+                    assert_eq!(server.get_event(), None);
+                }
+                UnknownChannelPolicy::Event => {
+                    assert!(!server.is_disconnected());
+                    assert_eq!(server.unknown_channel_messages(), 2);
+                    assert_eq!(
+                        server.get_event(),
+                        Some(ClientEvent::UnknownChannelMessages {
+                            channel_id: 3,
+                            num_messages: 2
+                        })
+                    );
+                }
+                UnknownChannelPolicy::Disconnect => {
+                    assert_eq!(server.disconnect_reason(), Some(DisconnectReason::ReceivedInvalidChannelId(3)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ack_delay() {
+        let config = ConnectionConfig {
+            ack_delay: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+
+        for _ in 0..3 {
+            client.send_message(DefaultChannel::Unreliable, vec![0; 10]);
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+            assert!(server.get_packets_to_send().is_empty());
+            server.update(Duration::from_millis(20));
+        }
+
+        // The acks of the 3 packets are sent together
+        let packets = server.get_packets_to_send();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(server.acks_sent_this_update(), 1);
+        assert!(server.get_packets_to_send().is_empty());
+
+        for packet in packets {
+            client.process_packet(&packet);
+        }
+        assert!(client.sent_packets.is_empty());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        client.unknown_channel_messages = 2;
+        client.reassembly_evictions = 3;
+
+        let diagnostics = client.diagnostics();
+        assert_eq!(diagnostics.unknown_channel_messages, 2);
+        assert_eq!(diagnostics.reassembly_evictions, 3);
+
+        client.reset_all_diagnostics();
+        assert_eq!(client.diagnostics(), DiagnosticsSnapshot::default());
+    }
+
+    #[test]
+    fn has_pending_work() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new(ConnectionConfig::default());
+        assert!(!client.has_pending_work());
+
+        client.send_message(DefaultChannel::ReliableOrdered, vec![1]);
+        assert!(client.has_pending_work());
+
+        for packet in client.get_packets_to_send() {
+            server.process_packet(&packet);
+        }
+        // Waiting for the ack
+        assert!(client.has_pending_work());
+        assert!(server.has_pending_work());
+
+        for packet in server.get_packets_to_send() {
+            client.process_packet(&packet);
+        }
+        // The ack ranges are resent until the client acks the ack packet
+        assert!(server.has_pending_work());
+
+        // The client message is acked, only the ack for the server packet is left
+        client.get_packets_to_send();
+        assert!(client.has_pending_work());
+        client.disconnect();
+        assert!(!client.has_pending_work());
+    }
+
+    #[test]
+    fn network_info_delta() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let earlier = client.network_info();
+
+        for _ in 0..4 {
+            client.send_message(DefaultChannel::Unreliable, vec![0; 100]);
+            client.get_packets_to_send();
+            client.update(Duration::from_millis(500));
+        }
+
+        let delta = client.network_info_since(&earlier);
+        assert_eq!(delta.elapsed, Duration::from_secs(2));
+        assert_eq!(delta.packets_sent, 4);
+        assert_eq!(delta.messages_sent, 4);
+        assert_eq!(delta.packets_sent_per_second, 2.0);
+        assert_eq!(delta.bytes_sent_per_second, delta.bytes_sent as f64 / 2.0);
+
+        let same = client.network_info();
+        assert_eq!(same.delta(&same).bytes_sent_per_second, 0.0);
+    }
+
+    #[test]
+    fn status_handle() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let status = client.status_handle();
+        let ui_status = status.clone();
+        assert_send_sync(&ui_status);
+        assert!(!ui_status.is_connected());
+        assert_eq!(ui_status.client_id(), None);
+
+        client.connection_established(ConnectionEstablished {
+            client_id: 7,
+            server_addr: "127.0.0.1:5000".parse().unwrap(),
+        });
+        client.rtt = 0.1;
+        client.update(Duration::from_millis(10));
+        assert!(ui_status.is_connected());
+        assert_eq!(ui_status.client_id(), Some(7));
+        assert_eq!(ui_status.rtt(), 0.1);
+        assert_eq!(ui_status.network_info(), client.network_info());
+
+        client.disconnect();
+        client.update(Duration::from_millis(10));
+        assert!(!ui_status.is_connected());
+        assert!(status.is_disconnected());
+    }
+
+    #[test]
+    fn uplink_downlink_kbps() {
+        #[derive(Debug, Clone)]
+        struct EstimatedCapacity;
+
+        impl CongestionController for EstimatedCapacity {
+            fn available_bytes(&self) -> u64 {
+                u64::MAX
+            }
+
+            fn estimated_capacity(&self) -> Option<f64> {
+                Some(125_000.0)
+            }
+
+            fn box_clone(&self) -> Box<dyn CongestionController> {
+                Box::new(self.clone())
+            }
+        }
+
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let mut server = RenetClient::new_from_server(ConnectionConfig {
+            congestion_controller: Some(Box::new(EstimatedCapacity)),
+            ..Default::default()
+        });
+        assert_eq!(client.uplink_capacity_kbps(), None);
+        assert_eq!(server.uplink_capacity_kbps(), Some(1000.0));
+
+        for _ in 0..10 {
+            client.update(Duration::from_millis(100));
+            server.update(Duration::from_millis(100));
+            client.send_message(DefaultChannel::Unreliable, vec![0; 100]);
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+        }
+
+        // Only sending from the client
+        assert!(client.uplink_kbps() > 0.0);
+        assert_eq!(client.downlink_kbps(), 0.0);
+        assert_eq!(server.uplink_kbps(), 0.0);
+        assert_eq!(server.downlink_kbps(), client.uplink_kbps());
+    }
+
+    #[test]
+    fn padding() {
+        let config = ConnectionConfig {
+            padding: PaddingPolicy::Fixed(200),
+            ..Default::default()
+        };
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+        client.send_message(DefaultChannel::ReliableOrdered, vec![1; 10]);
+        client.send_message(DefaultChannel::ReliableOrdered, vec![2; 500]);
+        for packet in client.get_packets_to_send() {
+            assert!(packet.len() >= 200);
+            server.process_packet(&packet);
+        }
+        assert_eq!(server.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![1; 10]);
+        assert_eq!(server.receive_message(DefaultChannel::ReliableOrdered).unwrap(), vec![2; 500]);
+        assert!(!server.is_disconnected());
+
+        let packets = server.get_packets_to_send();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].len(), 200);
+
+        let policy = PaddingPolicy::SizeClasses(vec![256, 64]).validated();
+        assert_eq!(policy.padded_len(10), 64);
+        assert_eq!(policy.padded_len(100), 256);
+        assert_eq!(policy.padded_len(300), 300);
+    }
+
+    #[test]
+    fn drain_policy() {
+        fn drain(drain_policy: DrainPolicy) -> Vec<(u8, Bytes)> {
+            let config = ConnectionConfig {
+                drain_policy,
+                ..Default::default()
+            };
+            let mut client = RenetClient::new(config.clone());
+            let mut server = RenetClient::new_from_server(config);
+            for i in 0..3u8 {
+                for channel_id in 0..3u8 {
+                    client.send_message(channel_id, vec![channel_id, i]);
+                }
+            }
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+
+            let mut received = vec![];
+            while let Some(message) = server.receive_next_message() {
+                received.push(message);
+            }
+            received
+        }
+
+        let order = |received: &[(u8, Bytes)]| -> Vec<u8> { received.iter().map(|(channel_id, _)| *channel_id).collect() };
+
+        let sequential = drain(DrainPolicy::Sequential);
+        assert_eq!(order(&sequential), vec![0, 0, 0, 1, 1, 1, 2, 2, 2]);
+
+        let round_robin = drain(DrainPolicy::RoundRobin);
+        assert_eq!(order(&round_robin), vec![0, 1, 2, 0, 1, 2, 0, 1, 2]);
+
+        let weighted = drain(DrainPolicy::WeightedRoundRobin(vec![(2, 2)]));
+        assert_eq!(order(&weighted), vec![0, 1, 2, 2, 0, 1, 2, 0, 1]);
+        assert_eq!(weighted[3].1, vec![2, 1]);
+
+        // Identical inputs are drained in the identical order
+        for _ in 0..10 {
+            assert_eq!(drain(DrainPolicy::WeightedRoundRobin(vec![(2, 2)])), weighted);
+        }
+    }
+
+    #[test]
+    fn send_block() {
+        let mut config = ConnectionConfig::default();
+        config.client_channels_config[2].max_memory_usage_bytes = 100_000;
+        let mut client = RenetClient::new(config.clone());
+        let mut server = RenetClient::new_from_server(config);
+
+        let data: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+        let handle = client.send_block(DefaultChannel::ReliableOrdered, data.clone());
+        let cancelled = client.send_block(DefaultChannel::ReliableOrdered, vec![0; 1000]);
+        cancelled.cancel();
+        assert_eq!(handle.total_bytes(), 200_000);
+        assert_eq!(handle.progress(), 0.0);
+        assert_eq!(handle.eta(), None);
+
+        // Only one chunk fits in the channel memory
+        client.update(Duration::from_millis(10));
+        assert_eq!(handle.queued_bytes(), BLOCK_CHUNK_SIZE);
+        assert!(handle.is_backpressured());
+
+        let mut received = vec![];
+        for _ in 0..100 {
+            for packet in client.get_packets_to_send() {
+                server.process_packet(&packet);
+            }
+            for packet in server.get_packets_to_send() {
+                client.process_packet(&packet);
+            }
+            while let Some(message) = server.receive_message(DefaultChannel::ReliableOrdered) {
+                received.extend_from_slice(&message);
+            }
+            client.update(Duration::from_millis(10));
+            server.update(Duration::from_millis(10));
+            if handle.is_complete() {
+                break;
+            }
+        }
+
+        assert!(handle.is_complete());
+        assert_eq!(handle.progress(), 1.0);
+        assert_eq!(handle.eta(), Some(Duration::ZERO));
+        assert!(handle.transfer_rate() > 0.0);
+        assert_eq!(received, data);
+        assert!(cancelled.is_cancelled());
+        assert_eq!(cancelled.queued_bytes(), 0);
+        assert_eq!(client.active_block_sends(), 0);
+    }
+
+    #[test]
+    fn update_at_clock_went_backwards() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        client.update_at(Duration::from_secs(100)).unwrap();
+        assert_eq!(client.current_time, Duration::ZERO);
+
+        client.update_at(Duration::from_secs(101)).unwrap();
+        assert_eq!(client.current_time, Duration::from_secs(1));
+
+        // The time is clamped, the connection doesn't move back
+        let error = client.update_at(Duration::from_secs(99)).unwrap_err();
+        assert_eq!(
+            error,
+            ClockWentBackwards {
+                previous: Duration::from_secs(101),
+                now: Duration::from_secs(99)
+            }
+        );
+        assert_eq!(client.current_time, Duration::from_secs(1));
+        assert_eq!(client.clock_regressions(), 1);
+        assert!(!client.is_disconnected());
+
+        // Time is measured from the latest time once the clock catches up
+        client.update_at(Duration::from_secs(100)).unwrap_err();
+        client.update_at(Duration::from_secs(102)).unwrap();
+        assert_eq!(client.current_time, Duration::from_secs(2));
+        assert_eq!(client.clock_regressions(), 2);
+
+        // A clock that didn't tick doesn't advance the timers and isn't a regression
+        for _ in 0..10 {
+            client.update_at(Duration::from_secs(102)).unwrap();
+        }
+        assert_eq!(client.current_time, Duration::from_secs(2));
+        assert_eq!(client.clock_regressions(), 2);
+    }
 }
@@ -1,6 +1,7 @@
-use crate::error::{ClientNotFound, DisconnectReason};
+use crate::block::BlockHandle;
+use crate::error::{ClientNotFound, ClockWentBackwards, DisconnectReason, ReconfigureError, SendError};
 use crate::packet::Payload;
-use crate::remote_connection::{ConnectionConfig, NetworkInfo, RenetClient};
+use crate::remote_connection::{ConnectionConfig, DiagnosticsSnapshot, DisconnectReport, NetworkInfo, ReconfigurePolicy, RenetClient};
 use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 
@@ -20,14 +21,26 @@ pub struct RenetServer {
     connections: HashMap<u64, RenetClient>,
     connection_config: ConnectionConfig,
     events: VecDeque<ServerEvent>,
+    last_update_at: Option<Duration>,
+    clock_regressions: u64,
 }
 
 impl RenetServer {
+    /// Panics if [ConnectionConfig::server_version] is longer than 255 bytes.
     pub fn new(connection_config: ConnectionConfig) -> Self {
+        if let Some(server_version) = &connection_config.server_version {
+            assert!(
+                server_version.len() <= u8::MAX as usize,
+                "The server version cannot be longer than 255 bytes"
+            );
+        }
+
         Self {
             connections: HashMap::new(),
             connection_config,
             events: VecDeque::new(),
+            last_update_at: None,
+            clock_regressions: 0,
         }
     }
 
@@ -88,6 +101,112 @@ impl RenetServer {
         }
     }
 
+    /// Returns the number of ack packets sent to the client since the last update, or 0 if the client is not found,
+    /// see [RenetClient::acks_sent_this_update].
+    pub fn acks_sent_this_update(&self, client_id: u64) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.acks_sent_this_update(),
+            None => 0,
+        }
+    }
+
+    /// Returns the number of ack packets received from the client since the last update, or 0 if the client is not found,
+    /// see [RenetClient::acks_received_this_update].
+    pub fn acks_received_this_update(&self, client_id: u64) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.acks_received_this_update(),
+            None => 0,
+        }
+    }
+
+    /// Returns the number of messages received from the client in channels that are not configured,
+    /// or 0 if the client is not found, see [RenetClient::unknown_channel_messages].
+    pub fn unknown_channel_messages(&self, client_id: u64) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.unknown_channel_messages(),
+            None => 0,
+        }
+    }
+
+    /// Returns all the diagnostic counters of the client, or None if the client is not found,
+    /// see [RenetClient::diagnostics].
+    pub fn diagnostics(&self, client_id: u64) -> Option<DiagnosticsSnapshot> {
+        self.connections.get(&client_id).map(|connection| connection.diagnostics())
+    }
+
+    /// Resets all the diagnostic counters of the client to zero, it does nothing if the client is not found,
+    /// see [RenetClient::reset_all_diagnostics].
+    pub fn reset_all_diagnostics(&mut self, client_id: u64) {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            connection.reset_all_diagnostics();
+        }
+    }
+
+    /// Returns the number of incomplete sliced messages from the client dropped because the total memory
+    /// for reassembly was exceeded, or 0 if the client is not found.
+    pub fn reassembly_evictions(&self, client_id: u64) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.reassembly_evictions(),
+            None => 0,
+        }
+    }
+
+    /// Returns the number of packets to the client dropped because too many were deferred by the packet rate limit,
+    /// or 0 if the client is not found, see [RenetClient::dropped_deferred_packets].
+    pub fn dropped_deferred_packets(&self, client_id: u64) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.dropped_deferred_packets(),
+            None => 0,
+        }
+    }
+
+    /// Returns the number of messages to the client dropped because the client doesn't have their channel,
+    /// or 0 if the client is not found, see [RenetClient::unknown_remote_channel_messages].
+    pub fn unknown_remote_channel_messages(&self, client_id: u64) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.unknown_remote_channel_messages(),
+            None => 0,
+        }
+    }
+
+    /// Tags the connection with the given client with an application session id, see [RenetClient::set_session_id].
+    /// It does nothing if the client does not exits.
+    pub fn set_session_id(&mut self, client_id: u64, session_id: u64) {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            connection.set_session_id(session_id);
+        }
+    }
+
+    /// Returns the application session id of the given client, or None if not set or the client is not found.
+    pub fn session_id(&self, client_id: u64) -> Option<u64> {
+        self.connections.get(&client_id)?.session_id()
+    }
+
+    /// Returns the number of bytes allowed to be sent to the client in each update, or 0 if the client is not found,
+    /// see [RenetClient::congestion_window].
+    pub fn congestion_window(&self, client_id: u64) -> usize {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.congestion_window(),
+            None => 0,
+        }
+    }
+
+    /// Returns the packets sent per second for the client or 0.0 if the client is not found
+    pub fn packets_sent_per_sec(&self, client_id: u64) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.packets_sent_per_sec(),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the messages queued to be sent per second for the client or 0.0 if the client is not found
+    pub fn messages_sent_per_sec(&self, client_id: u64) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.messages_sent_per_sec(),
+            None => 0.0,
+        }
+    }
+
     /// Returns the packet loss for the client or 0.0 if the client is not found
     pub fn packet_loss(&self, client_id: u64) -> f64 {
         match self.connections.get(&client_id) {
@@ -104,6 +223,48 @@ impl RenetServer {
         }
     }
 
+    /// Returns the weight of each new sample in the bandwidth average of the given client, or None if the client is not found,
+    /// see [RenetClient::bandwidth_smoothing_factor].
+    pub fn bandwidth_smoothing_factor(&self, client_id: u64) -> Option<f64> {
+        self.connections
+            .get(&client_id)
+            .map(|connection| connection.bandwidth_smoothing_factor())
+    }
+
+    /// Changes the weight of each new sample in the bandwidth average of the given client,
+    /// see [RenetClient::set_bandwidth_smoothing_factor]. It does nothing if the client does not exits.
+    pub fn set_bandwidth_smoothing_factor(&mut self, client_id: u64, factor: f64) {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            connection.set_bandwidth_smoothing_factor(factor);
+        }
+    }
+
+    /// Returns the throughput from the server to the client in kilobits per second, or 0.0 if the client is not found,
+    /// see [RenetClient::uplink_kbps].
+    pub fn uplink_kbps(&self, client_id: u64) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.uplink_kbps(),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the throughput from the client to the server in kilobits per second, or 0.0 if the client is not found,
+    /// see [RenetClient::downlink_kbps].
+    pub fn downlink_kbps(&self, client_id: u64) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.downlink_kbps(),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the capacity from the server to the client in kilobits per second, as estimated by the congestion controller,
+    /// or None if the client is not found or the controller doesn't estimate it, see [RenetClient::uplink_capacity_kbps].
+    pub fn uplink_capacity_kbps(&self, client_id: u64) -> Option<f64> {
+        self.connections
+            .get(&client_id)
+            .and_then(|connection| connection.uplink_capacity_kbps())
+    }
+
     /// Returns the bytes received per seconds for the client or 0.0 if the client is not found
     pub fn bytes_received_per_sec(&self, client_id: u64) -> f64 {
         match self.connections.get(&client_id) {
@@ -120,6 +281,31 @@ impl RenetServer {
         }
     }
 
+    /// Returns a summary of the messages that have not been delivered yet to the client,
+    /// see [RenetClient::disconnect_report].
+    /// Disconnected clients are removed in the next update, so call it before that.
+    pub fn disconnect_report(&self, client_id: u64) -> Result<DisconnectReport, ClientNotFound> {
+        match self.connections.get(&client_id) {
+            Some(connection) => Ok(connection.disconnect_report()),
+            None => Err(ClientNotFound),
+        }
+    }
+
+    /// Changes the channels of the connection with the given client without reconnecting,
+    /// see [RenetClient::reconfigure_channels].
+    /// Only affects this client, new clients still use the channels the server was created with.
+    pub fn reconfigure_channels(
+        &mut self,
+        client_id: u64,
+        config: &ConnectionConfig,
+        policy: ReconfigurePolicy,
+    ) -> Result<(), ReconfigureError> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.reconfigure_channels_from_server(config, policy),
+            None => Err(ReconfigureError::ClientNotFound),
+        }
+    }
+
     /// Removes a connection from the server, emits an disconnect server event.
     /// It does nothing if the client does not exits.
     /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
@@ -151,6 +337,8 @@ impl RenetServer {
     }
 
     /// Send a message to all clients over a channel.
+    /// The message buffer is shared between all the clients without copying,
+    /// only the packet serialization and encryption are done for each client.
     pub fn broadcast_message<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) {
         let channel_id = channel_id.into();
         let message = message.into();
@@ -181,6 +369,93 @@ impl RenetServer {
         }
     }
 
+    /// Returns the maximum size in bytes of a message that can be sent over a channel for the given client.
+    /// Returns None if the client or the channel is not found, see [RenetClient::max_message_size].
+    pub fn max_message_size<I: Into<u8>>(&self, client_id: u64, channel_id: I) -> Option<usize> {
+        self.connections.get(&client_id)?.max_message_size(channel_id)
+    }
+
+    /// Stops sending messages to the given client, see [RenetClient::pause_send].
+    /// It does nothing if the client does not exits.
+    pub fn pause_send(&mut self, client_id: u64) {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            connection.pause_send();
+        }
+    }
+
+    /// Resumes sending messages to the given client, see [RenetClient::resume_send].
+    /// It does nothing if the client does not exits.
+    pub fn resume_send(&mut self, client_id: u64) {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            connection.resume_send();
+        }
+    }
+
+    /// Returns true if sending messages to the given client is paused.
+    /// Returns false if the client is not found.
+    pub fn is_send_paused(&self, client_id: u64) -> bool {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.is_send_paused(),
+            None => false,
+        }
+    }
+
+    /// Sets a hook called for every message queued to be sent to the given client,
+    /// see [RenetClient::on_message_queued].
+    pub fn on_message_queued<F: FnMut(u8, u64, &[u8]) + Send + Sync + 'static>(
+        &mut self,
+        client_id: u64,
+        hook: F,
+    ) -> Result<(), ClientNotFound> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => {
+                connection.on_message_queued(hook);
+                Ok(())
+            }
+            None => Err(ClientNotFound),
+        }
+    }
+
+    /// Sets a hook called for every reliable message acknowledged by the given client,
+    /// see [RenetClient::on_message_acked].
+    pub fn on_message_acked<F: FnMut(u8, u64, Duration) + Send + Sync + 'static>(
+        &mut self,
+        client_id: u64,
+        hook: F,
+    ) -> Result<(), ClientNotFound> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => {
+                connection.on_message_acked(hook);
+                Ok(())
+            }
+            None => Err(ClientNotFound),
+        }
+    }
+
+    /// Returns the number of sliced messages being sent in a reliable channel for the given client.
+    /// Returns 0 if the client is not found, see [RenetClient::active_block_transfers].
+    pub fn active_block_transfers<I: Into<u8>>(&self, client_id: u64, channel_id: I) -> usize {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.active_block_transfers(channel_id),
+            None => 0,
+        }
+    }
+
+    /// Returns an estimate of the time until a message queued in a reliable channel is delivered to the given client.
+    /// Returns None if the client is not found, see [RenetClient::estimated_delivery_time].
+    pub fn estimated_delivery_time<I: Into<u8>>(&self, client_id: u64, channel_id: I, message_id: u64) -> Option<Duration> {
+        self.connections.get(&client_id)?.estimated_delivery_time(channel_id, message_id)
+    }
+
+    /// Returns the number of stale messages discarded in an unreliable sequenced channel for the given client.
+    /// Returns 0 if the client is not found, see [RenetClient::stale_messages].
+    pub fn stale_messages<I: Into<u8>>(&self, client_id: u64, channel_id: I) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.stale_messages(channel_id),
+            None => 0,
+        }
+    }
+
     /// Checks if can send a message with the given size in bytes over a channel for the given client.
     /// Returns false if the client is not found.
     pub fn can_send_message<I: Into<u8>>(&self, client_id: u64, channel_id: I, size_bytes: usize) -> bool {
@@ -198,6 +473,37 @@ impl RenetServer {
         }
     }
 
+    /// Sends a large payload to a client over a reliable ordered channel, see [RenetClient::send_block].
+    pub fn send_block<I: Into<u8>, B: Into<Bytes>>(
+        &mut self,
+        client_id: u64,
+        channel_id: I,
+        data: B,
+    ) -> Result<BlockHandle, ClientNotFound> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => Ok(connection.send_block(channel_id, data)),
+            None => Err(ClientNotFound),
+        }
+    }
+
+    /// Send a message to a client over a channel, failing if the client doesn't have the channel,
+    /// see [RenetClient::try_send_message].
+    pub fn try_send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, client_id: u64, channel_id: I, message: B) -> Result<(), SendError> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.try_send_message(channel_id, message),
+            None => Err(SendError::ClientNotFound),
+        }
+    }
+
+    /// Returns true if the reliable ordered channel of the client is waiting for a missing message
+    /// to deliver the newer ones, or false if the client is not found, see [RenetClient::channel_is_blocked].
+    pub fn channel_is_blocked<I: Into<u8>>(&self, client_id: u64, channel_id: I) -> bool {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.channel_is_blocked(channel_id),
+            None => false,
+        }
+    }
+
     /// Receive a message from a client over a channel.
     pub fn receive_message<I: Into<u8>>(&mut self, client_id: u64, channel_id: I) -> Option<Bytes> {
         if let Some(connection) = self.connections.get_mut(&client_id) {
@@ -206,6 +512,24 @@ impl RenetServer {
         None
     }
 
+    /// Receive the next message from a client over any channel, with the id of its channel,
+    /// see [RenetClient::receive_next_message].
+    pub fn receive_next_message(&mut self, client_id: u64) -> Option<(u8, Bytes)> {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            return connection.receive_next_message();
+        }
+        None
+    }
+
+    /// Receive messages from a client over a channel into the given buffers, see [RenetClient::receive_into].
+    /// Returns 0 if the client is not found.
+    pub fn receive_into<I: Into<u8>>(&mut self, client_id: u64, channel_id: I, bufs: &mut [&mut Vec<u8>]) -> usize {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.receive_into(channel_id, bufs),
+            None => 0,
+        }
+    }
+
     /// Return ids for all connected clients (iterator)
     pub fn clients_id_iter(&self) -> impl Iterator<Item = u64> + '_ {
         self.connections.iter().filter(|(_, c)| !c.is_disconnected()).map(|(id, _)| *id)
@@ -244,7 +568,35 @@ impl RenetServer {
     pub fn update(&mut self, duration: Duration) {
         for connection in self.connections.values_mut() {
             connection.update(duration);
+            // Client events are not exposed for connections in the server
+            connection.events.clear();
+        }
+    }
+
+    /// Advances the server to the time of an absolute clock, updating it with the time elapsed since the previous call.
+    /// A time earlier than a previous one updates the server without elapsed time, is counted in
+    /// [RenetServer::clock_regressions] and returns an error, see [RenetClient::update_at].
+    /// Giving the same time again updates the server without elapsed time and is not a regression.
+    pub fn update_at(&mut self, now: Duration) -> Result<(), ClockWentBackwards> {
+        let previous = self.last_update_at.unwrap_or(now);
+        if now < previous {
+            log::warn!(
+                "Clock went backwards by {:?}, updating the server without elapsed time",
+                previous - now
+            );
+            self.clock_regressions += 1;
+            self.update(Duration::ZERO);
+            return Err(ClockWentBackwards { previous, now });
         }
+
+        self.last_update_at = Some(now);
+        self.update(now - previous);
+        Ok(())
+    }
+
+    /// Returns the number of times [RenetServer::update_at] was given a time earlier than a previous one.
+    pub fn clock_regressions(&self) -> u64 {
+        self.clock_regressions
     }
 
     /// Returns a list of packets to be sent to the client.
@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::remote_connection::NetworkInfo;
+
+/// Read-only view of the status of a [RenetClient][crate::RenetClient], see [RenetClient::status_handle][crate::RenetClient::status_handle].
+/// Cheap to clone and can be sent to other threads, for example to render the connection status in a UI thread
+/// while the network thread owns the client. The status is refreshed in every update of the client.
+///
+/// Each field is read without locks, so fields read separately may come from consecutive updates.
+#[derive(Debug, Clone, Default)]
+pub struct StatusHandle {
+    status: Arc<SharedStatus>,
+}
+
+#[derive(Debug, Default)]
+struct SharedStatus {
+    connected: AtomicBool,
+    disconnected: AtomicBool,
+    has_client_id: AtomicBool,
+    client_id: AtomicU64,
+    // f64 values are stored as their bits
+    rtt: AtomicU64,
+    packet_loss: AtomicU64,
+    packet_loss_available: AtomicBool,
+    bytes_sent_per_second: AtomicU64,
+    bytes_received_per_second: AtomicU64,
+    packets_sent_per_second: AtomicU64,
+    messages_sent_per_second: AtomicU64,
+    has_session_id: AtomicBool,
+    session_id: AtomicU64,
+    current_time_nanos: AtomicU64,
+    total_bytes_sent: AtomicU64,
+    total_bytes_received: AtomicU64,
+    total_packets_sent: AtomicU64,
+    total_messages_sent: AtomicU64,
+}
+
+impl StatusHandle {
+    pub(crate) fn store(&self, connected: bool, disconnected: bool, client_id: Option<u64>, network_info: &NetworkInfo) {
+        let status = &self.status;
+        status.connected.store(connected, Ordering::Relaxed);
+        status.disconnected.store(disconnected, Ordering::Relaxed);
+        store_option(&status.has_client_id, &status.client_id, client_id);
+        status.rtt.store(network_info.rtt.to_bits(), Ordering::Relaxed);
+        status.packet_loss.store(network_info.packet_loss.to_bits(), Ordering::Relaxed);
+        status
+            .packet_loss_available
+            .store(network_info.packet_loss_available, Ordering::Relaxed);
+        status
+            .bytes_sent_per_second
+            .store(network_info.bytes_sent_per_second.to_bits(), Ordering::Relaxed);
+        status
+            .bytes_received_per_second
+            .store(network_info.bytes_received_per_second.to_bits(), Ordering::Relaxed);
+        status
+            .packets_sent_per_second
+            .store(network_info.packets_sent_per_second.to_bits(), Ordering::Relaxed);
+        status
+            .messages_sent_per_second
+            .store(network_info.messages_sent_per_second.to_bits(), Ordering::Relaxed);
+        store_option(&status.has_session_id, &status.session_id, network_info.session_id);
+        status
+            .current_time_nanos
+            .store(network_info.current_time.as_nanos() as u64, Ordering::Relaxed);
+        status.total_bytes_sent.store(network_info.total_bytes_sent, Ordering::Relaxed);
+        status
+            .total_bytes_received
+            .store(network_info.total_bytes_received, Ordering::Relaxed);
+        status.total_packets_sent.store(network_info.total_packets_sent, Ordering::Relaxed);
+        status
+            .total_messages_sent
+            .store(network_info.total_messages_sent, Ordering::Relaxed);
+    }
+
+    /// Returns true if the connection was established and is not terminated.
+    pub fn is_connected(&self) -> bool {
+        self.status.connected.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if the connection was terminated.
+    pub fn is_disconnected(&self) -> bool {
+        self.status.disconnected.load(Ordering::Relaxed)
+    }
+
+    /// Returns the identifier of the client in the server, or None if the connection was not established yet.
+    pub fn client_id(&self) -> Option<u64> {
+        load_option(&self.status.has_client_id, &self.status.client_id)
+    }
+
+    /// Returns the round-trip time of the last update.
+    pub fn rtt(&self) -> f64 {
+        f64::from_bits(self.status.rtt.load(Ordering::Relaxed))
+    }
+
+    /// Returns the packet loss of the last update.
+    pub fn packet_loss(&self) -> f64 {
+        f64::from_bits(self.status.packet_loss.load(Ordering::Relaxed))
+    }
+
+    /// Returns the network info of the last update, see [RenetClient::network_info][crate::RenetClient::network_info].
+    pub fn network_info(&self) -> NetworkInfo {
+        let status = &self.status;
+        let load_f64 = |value: &AtomicU64| f64::from_bits(value.load(Ordering::Relaxed));
+        NetworkInfo {
+            rtt: load_f64(&status.rtt),
+            packet_loss: load_f64(&status.packet_loss),
+            packet_loss_available: status.packet_loss_available.load(Ordering::Relaxed),
+            bytes_sent_per_second: load_f64(&status.bytes_sent_per_second),
+            bytes_received_per_second: load_f64(&status.bytes_received_per_second),
+            packets_sent_per_second: load_f64(&status.packets_sent_per_second),
+            messages_sent_per_second: load_f64(&status.messages_sent_per_second),
+            session_id: load_option(&status.has_session_id, &status.session_id),
+            current_time: Duration::from_nanos(status.current_time_nanos.load(Ordering::Relaxed)),
+            total_bytes_sent: status.total_bytes_sent.load(Ordering::Relaxed),
+            total_bytes_received: status.total_bytes_received.load(Ordering::Relaxed),
+            total_packets_sent: status.total_packets_sent.load(Ordering::Relaxed),
+            total_messages_sent: status.total_messages_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// The value is stored before the flag is released and kept when the flag is cleared,
+// so a reader that acquires the flag always sees a value that was stored with it set.
+fn store_option(has_value: &AtomicBool, value: &AtomicU64, option: Option<u64>) {
+    if let Some(option) = option {
+        value.store(option, Ordering::Relaxed);
+    }
+    has_value.store(option.is_some(), Ordering::Release);
+}
+
+fn load_option(has_value: &AtomicBool, value: &AtomicU64) -> Option<u64> {
+    match has_value.load(Ordering::Acquire) {
+        true => Some(value.load(Ordering::Relaxed)),
+        false => None,
+    }
+}
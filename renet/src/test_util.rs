@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+#[cfg(feature = "transport")]
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::packet::Payload;
+use crate::remote_connection::{ConnectionConfig, RenetClient};
+use crate::server::RenetServer;
+#[cfg(feature = "transport")]
+use crate::transport::{generate_random_bytes, ClientAuthentication, ConnectToken, ServerAuthentication, ServerConfig, NETCODE_KEY_BYTES};
+
+/// Direction of a packet exchanged by the [Harness].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+struct InFlightPacket {
+    deliver_at: u64,
+    direction: Direction,
+    payload: Payload,
+}
+
+type DropFilter = Box<dyn FnMut(Direction, u64, &[u8]) -> bool>;
+
+/// Drives a connected client and server in lockstep, exchanging packets in memory without a transport layer.
+/// Latency is measured in ticks and packet loss is decided by a filter, so every run is deterministic.
+///
+/// # Usage
+/// ```
+/// # use renet::{ConnectionConfig, DefaultChannel, test_util::Harness};
+/// let mut harness = Harness::new(ConnectionConfig::default());
+/// harness.client.send_message(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+///
+/// // Sent in the first step and received in the next one
+/// harness.step();
+/// harness.step();
+/// let message = harness.server.receive_message(harness.client_id(), DefaultChannel::ReliableOrdered);
+/// assert_eq!(message.unwrap(), vec![1, 2, 3]);
+/// ```
+pub struct Harness {
+    pub client: RenetClient,
+    pub server: RenetServer,
+    client_id: u64,
+    tick: u64,
+    tick_duration: Duration,
+    latency_ticks: u64,
+    in_flight: VecDeque<InFlightPacket>,
+    drop_filter: DropFilter,
+}
+
+impl Harness {
+    /// Creates a client connected to a server, each tick advances 16ms with 1 tick of latency and no packet loss.
+    pub fn new(config: ConnectionConfig) -> Self {
+        let client_id = 0;
+        let mut server = RenetServer::new(config.clone());
+        server.add_connection(client_id);
+
+        Self {
+            client: RenetClient::new(config),
+            server,
+            client_id,
+            tick: 0,
+            tick_duration: Duration::from_millis(16),
+            latency_ticks: 1,
+            in_flight: VecDeque::new(),
+            drop_filter: Box::new(|_, _, _| false),
+        }
+    }
+
+    /// Id of the client in the server.
+    pub fn client_id(&self) -> u64 {
+        self.client_id
+    }
+
+    /// Number of steps taken.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Sets the duration that the client and server are advanced in each step.
+    pub fn set_tick_duration(&mut self, tick_duration: Duration) {
+        self.tick_duration = tick_duration;
+    }
+
+    /// Sets the number of steps until a sent packet is received, must be at least 1.
+    /// Only affects packets sent after this call.
+    pub fn set_latency_ticks(&mut self, latency_ticks: u64) {
+        assert!(latency_ticks > 0, "packets sent in a step can only be received in the next ones");
+        self.latency_ticks = latency_ticks;
+    }
+
+    /// Sets the filter that decides which packets are lost.
+    /// It's called for every sent packet with its direction, the current tick and the payload,
+    /// the packet is dropped if it returns true.
+    pub fn set_drop_filter<F: FnMut(Direction, u64, &[u8]) -> bool + 'static>(&mut self, drop_filter: F) {
+        self.drop_filter = Box::new(drop_filter);
+    }
+
+    /// Advances the client and server by one tick:
+    /// delivers the packets whose latency has elapsed, then sends the new packets of both sides.
+    pub fn step(&mut self) {
+        self.client.update(self.tick_duration);
+        self.server.update(self.tick_duration);
+
+        while self.in_flight.front().is_some_and(|packet| packet.deliver_at <= self.tick) {
+            let packet = self.in_flight.pop_front().unwrap();
+            match packet.direction {
+                Direction::ClientToServer => {
+                    let _ = self.server.process_packet_from(&packet.payload, self.client_id);
+                }
+                Direction::ServerToClient => self.client.process_packet(&packet.payload),
+            }
+        }
+
+        let client_packets = self.client.get_packets_to_send();
+        let server_packets = self.server.get_packets_to_send(self.client_id).unwrap_or_default();
+        let packets = client_packets
+            .into_iter()
+            .map(|payload| (Direction::ClientToServer, payload))
+            .chain(server_packets.into_iter().map(|payload| (Direction::ServerToClient, payload)));
+
+        for (direction, payload) in packets {
+            if (self.drop_filter)(direction, self.tick, &payload) {
+                continue;
+            }
+
+            // Latency only changes for new packets, so the packets remain sorted by delivery
+            let deliver_at = self.tick + self.latency_ticks;
+            let index = self.in_flight.partition_point(|packet| packet.deliver_at <= deliver_at);
+            self.in_flight.insert(
+                index,
+                InFlightPacket {
+                    deliver_at,
+                    direction,
+                    payload,
+                },
+            );
+        }
+
+        self.tick += 1;
+    }
+}
+
+/// Matching server config and connect tokens to test the secure path, created by [test_secure_setup].
+#[cfg(feature = "transport")]
+pub struct SecureTestSetup {
+    protocol_id: u64,
+    server_addr: SocketAddr,
+    private_key: [u8; NETCODE_KEY_BYTES],
+}
+
+/// Creates a random private key for a server in the given address, so tests can use
+/// [ServerAuthentication::Secure] as easily as [ServerAuthentication::Unsecure].
+///
+/// # Usage
+/// ```
+/// # use renet::test_util::test_secure_setup;
+/// # use std::time::Duration;
+/// let setup = test_secure_setup(7, "127.0.0.1:5000".parse().unwrap());
+/// let server_config = setup.server_config(Duration::ZERO);
+/// let authentication = setup.client_authentication(Duration::ZERO, 1);
+/// ```
+#[cfg(feature = "transport")]
+pub fn test_secure_setup(protocol_id: u64, server_addr: SocketAddr) -> SecureTestSetup {
+    SecureTestSetup {
+        protocol_id,
+        server_addr,
+        private_key: generate_random_bytes(),
+    }
+}
+
+#[cfg(feature = "transport")]
+impl SecureTestSetup {
+    /// Maximum number of clients allowed by the server config.
+    pub const MAX_CLIENTS: usize = 64;
+
+    /// Private key used by the server and to generate the connect tokens.
+    pub fn private_key(&self) -> &[u8; NETCODE_KEY_BYTES] {
+        &self.private_key
+    }
+
+    /// Returns a server config with secure authentication, using the private key of the setup.
+    pub fn server_config(&self, current_time: Duration) -> ServerConfig {
+        ServerConfig::new(
+            current_time,
+            Self::MAX_CLIENTS,
+            self.protocol_id,
+            vec![self.server_addr],
+            ServerAuthentication::Secure {
+                private_key: self.private_key,
+            },
+        )
+    }
+
+    /// Generates a valid connect token for the client, it expires after 300 seconds
+    /// and the connection times out after 15 seconds without packets.
+    pub fn connect_token(&self, current_time: Duration, client_id: u64) -> ConnectToken {
+        ConnectToken::generate(
+            current_time,
+            self.protocol_id,
+            300,
+            client_id,
+            15,
+            vec![self.server_addr],
+            None,
+            &self.private_key,
+        )
+        .expect("connect token with a single server address is always valid")
+    }
+
+    /// Returns the secure client authentication with a new connect token, see [SecureTestSetup::connect_token].
+    pub fn client_authentication(&self, current_time: Duration, client_id: u64) -> ClientAuthentication {
+        ClientAuthentication::Secure {
+            connect_token: self.connect_token(current_time, client_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultChannel;
+
+    #[test]
+    fn latency() {
+        let mut harness = Harness::new(ConnectionConfig::default());
+        harness.set_latency_ticks(3);
+        harness.client.send_message(DefaultChannel::Unreliable, vec![1, 2, 3]);
+
+        for _ in 0..3 {
+            harness.step();
+            assert!(harness
+                .server
+                .receive_message(harness.client_id(), DefaultChannel::Unreliable)
+                .is_none());
+        }
+
+        harness.step();
+        assert!(harness
+            .server
+            .receive_message(harness.client_id(), DefaultChannel::Unreliable)
+            .is_some());
+    }
+
+    #[test]
+    fn reliable_resend_after_loss() {
+        let mut harness = Harness::new(ConnectionConfig::default());
+        // Drop the first packet sent by the client
+        harness.set_drop_filter(|direction, tick, _| direction == Direction::ClientToServer && tick == 0);
+        harness.client.send_message(DefaultChannel::ReliableOrdered, vec![1, 2, 3]);
+
+        // Resent after 300ms, 19 ticks of 16ms, received in the next tick
+        let mut delivered_at = None;
+        for _ in 0..30 {
+            harness.step();
+            if harness
+                .server
+                .receive_message(harness.client_id(), DefaultChannel::ReliableOrdered)
+                .is_some()
+            {
+                delivered_at = Some(harness.tick());
+                break;
+            }
+        }
+        assert_eq!(delivered_at, Some(21));
+    }
+
+    #[test]
+    #[cfg(feature = "transport")]
+    fn secure_setup() {
+        use renetcode::{NetcodeClient, NetcodeServer, ServerResult};
+
+        let server_addr = "127.0.0.1:5000".parse().unwrap();
+        let client_addr = "127.0.0.1:6000".parse().unwrap();
+        let setup = test_secure_setup(7, server_addr);
+        assert_ne!(setup.private_key(), &[0; NETCODE_KEY_BYTES]);
+        assert_ne!(setup.private_key(), test_secure_setup(7, server_addr).private_key());
+
+        let mut server = NetcodeServer::new(setup.server_config(Duration::ZERO));
+        let mut client = NetcodeClient::new(Duration::ZERO, setup.client_authentication(Duration::ZERO, 1)).unwrap();
+
+        // Connection request and challenge
+        let (packet, _) = client.update(Duration::ZERO).unwrap();
+        let mut packet = packet.to_vec();
+        let ServerResult::PacketToSend { payload, .. } = server.process_packet(client_addr, &mut packet) else {
+            panic!("expected challenge packet");
+        };
+        let mut challenge = payload.to_vec();
+        assert!(client.process_packet(&mut challenge).is_none());
+
+        // Challenge response, sent after the send rate of the client
+        let (packet, _) = client.update(Duration::from_secs(1)).unwrap();
+        let mut packet = packet.to_vec();
+        match server.process_packet(client_addr, &mut packet) {
+            ServerResult::ClientConnected { client_id, .. } => assert_eq!(client_id, 1),
+            _ => panic!("expected client to connect"),
+        }
+    }
+}
@@ -1,21 +1,55 @@
 use std::{
-    io,
+    fmt, io,
     net::{SocketAddr, UdpSocket},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use renetcode::{ClientAuthentication, DisconnectReason, NetcodeClient, NetcodeError, NETCODE_MAX_PACKET_BYTES};
+use renetcode::{generate_random_bytes, ClientAuthentication, DisconnectReason, NetcodeClient, NetcodeError, NETCODE_MAX_PACKET_BYTES};
 
-use crate::remote_connection::RenetClient;
+use crate::remote_connection::{ConnectionEstablished, DiagnosticsSnapshot, RenetClient};
 
 use super::NetcodeTransportError;
 
+type UnexpectedSourceFn = dyn FnMut(SocketAddr, &[u8]) + Send + Sync;
+
+struct UnexpectedSourceHook {
+    hook: Box<UnexpectedSourceFn>,
+    min_interval: Duration,
+    last_reported: Option<Duration>,
+}
+
+impl fmt::Debug for UnexpectedSourceHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UnexpectedSourceHook")
+    }
+}
+
+/// The state transitions of a [NetcodeClientTransport::update].
+#[derive(Debug, Default)]
+pub struct UpdateOutcome {
+    /// True if the connection was established in this update.
+    pub connection_established: bool,
+    /// True if the netcode connection was lost in this update, timed out or disconnected by the server.
+    pub connection_lost: bool,
+    /// The error of the update, only set for transports created with [NetcodeClientTransport::new_observing],
+    /// the other transports return it instead.
+    pub error: Option<NetcodeTransportError>,
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::system::Resource))]
 pub struct NetcodeClientTransport {
     socket: UdpSocket,
     netcode_client: NetcodeClient,
     buffer: [u8; NETCODE_MAX_PACKET_BYTES],
+    connection_established: bool,
+    would_block: bool,
+    would_block_sends: u64,
+    disconnect_confirm_attempts: u32,
+    disconnect_confirm_interval: Duration,
+    on_unexpected_source: Option<UnexpectedSourceHook>,
+    unexpected_source_packets: u64,
+    observing: bool,
 }
 
 impl NetcodeClientTransport {
@@ -27,13 +61,49 @@ impl NetcodeClientTransport {
             buffer: [0u8; NETCODE_MAX_PACKET_BYTES],
             socket,
             netcode_client,
+            connection_established: false,
+            would_block: false,
+            would_block_sends: 0,
+            disconnect_confirm_attempts: 3,
+            disconnect_confirm_interval: Duration::from_millis(100),
+            on_unexpected_source: None,
+            unexpected_source_packets: 0,
+            observing: false,
         })
     }
 
+    /// Creates a transport whose [update][NetcodeClientTransport::update] never disconnects, for applications
+    /// that manage the connection lifecycle themselves. The update doesn't mark the [RenetClient] as disconnected
+    /// when the transport fails, nor sends a disconnect packet when the [RenetClient] is disconnected,
+    /// and the errors are reported in the [UpdateOutcome] instead of returned.
+    /// Call [NetcodeClientTransport::disconnect] to terminate the connection.
+    pub fn new_observing(current_time: Duration, authentication: ClientAuthentication, socket: UdpSocket) -> Result<Self, NetcodeError> {
+        let mut transport = Self::new(current_time, authentication, socket)?;
+        transport.observing = true;
+        Ok(transport)
+    }
+
+    /// Returns true if the transport was created with [NetcodeClientTransport::new_observing].
+    pub fn is_observing(&self) -> bool {
+        self.observing
+    }
+
     pub fn addr(&self) -> io::Result<SocketAddr> {
         self.socket.local_addr()
     }
 
+    /// Returns the socket of the transport, to set options that are not wrapped, like the DSCP/ToS for QoS.
+    /// The transport expects the socket to stay non-blocking and bound to the same address,
+    /// setting it back to blocking makes [update][NetcodeClientTransport::update] block until a packet is received.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns the socket of the transport mutably, see [NetcodeClientTransport::socket].
+    pub fn socket_mut(&mut self) -> &mut UdpSocket {
+        &mut self.socket
+    }
+
     pub fn client_id(&self) -> u64 {
         self.netcode_client.client_id()
     }
@@ -50,12 +120,238 @@ impl NetcodeClientTransport {
         self.netcode_client.is_disconnected()
     }
 
+    /// Returns true if sending a packet failed because the socket buffer was full in the last [NetcodeClientTransport::send_packets].
+    /// Use it to skip calling [NetcodeClientTransport::send_packets] in the next tick, avoiding repeated failures.
+    /// Std sockets cannot be polled for writability, so it reports the outcome of the last send attempt,
+    /// not the current state of the socket. It's kept across updates and cleared when sending packets again.
+    pub fn last_send_would_block(&self) -> bool {
+        self.would_block
+    }
+
+    /// Sets the number of zeroed bytes added to keep-alive packets, see [NetcodeClient::set_keep_alive_padding].
+    pub fn set_keep_alive_padding(&mut self, padding: usize) {
+        self.netcode_client.set_keep_alive_padding(padding);
+    }
+
+    /// Sets the maximum number of payloads buffered before the connection is established,
+    /// see [NetcodeClient::set_max_pending_payloads].
+    pub fn set_max_pending_payloads(&mut self, max_pending_payloads: usize) {
+        self.netcode_client.set_max_pending_payloads(max_pending_payloads);
+    }
+
+    /// Sets the number of connection requests sent at once when starting to connect to a server address,
+    /// see [NetcodeClient::set_initial_request_burst].
+    pub fn set_initial_request_burst(&mut self, initial_request_burst: u8) {
+        self.netcode_client.set_initial_request_burst(initial_request_burst);
+    }
+
+    /// Sets how many disconnect packets are sent by [NetcodeClientTransport::disconnect_and_confirm]
+    /// before giving up on the confirmation of the server. Panics if 0. Default: 3
+    pub fn set_disconnect_confirm_attempts(&mut self, attempts: u32) {
+        assert!(attempts > 0, "The disconnect confirm attempts cannot be zero");
+        self.disconnect_confirm_attempts = attempts;
+    }
+
+    /// Returns how many disconnect packets are sent before giving up on the confirmation of the server.
+    pub fn disconnect_confirm_attempts(&self) -> u32 {
+        self.disconnect_confirm_attempts
+    }
+
+    /// Sets how long [NetcodeClientTransport::disconnect_and_confirm] waits for the confirmation
+    /// of the server before retrying the disconnect packet. Default: 100 milliseconds
+    pub fn set_disconnect_confirm_interval(&mut self, interval: Duration) {
+        self.disconnect_confirm_interval = interval;
+    }
+
+    /// Returns how long to wait for the confirmation of the server before retrying the disconnect packet.
+    pub fn disconnect_confirm_interval(&self) -> Duration {
+        self.disconnect_confirm_interval
+    }
+
+    /// Sets the number of consecutive packets from the server that can fail authentication before disconnecting,
+    /// see [NetcodeClient::set_protocol_mismatch_threshold].
+    pub fn set_protocol_mismatch_threshold(&mut self, threshold: Option<u32>) {
+        self.netcode_client.set_protocol_mismatch_threshold(threshold);
+    }
+
+    /// Sets the time without receiving packets from a server address before trying the next one while connecting,
+    /// see [NetcodeClient::set_connect_timeout]. Once connected, the timeout of the connect token is used.
+    pub fn set_connect_timeout(&mut self, connect_timeout: Duration) -> Result<(), NetcodeError> {
+        self.netcode_client.set_connect_timeout(connect_timeout)
+    }
+
+    /// Returns the time without receiving packets before the connection times out in the current state,
+    /// see [NetcodeClient::session_timeout].
+    pub fn session_timeout(&self) -> Duration {
+        self.netcode_client.session_timeout()
+    }
+
+    /// Returns all the diagnostic counters of the connection, including the ones of the transport layer,
+    /// see [RenetClient::diagnostics].
+    pub fn diagnostics(&self, client: &RenetClient) -> DiagnosticsSnapshot {
+        DiagnosticsSnapshot {
+            replay_drops: self.netcode_client.replay_drops(),
+            would_block_sends: self.would_block_sends,
+            discarded_packets: self.unexpected_source_packets,
+            ..client.diagnostics()
+        }
+    }
+
+    /// Resets all the diagnostic counters of the connection and the transport layer to zero,
+    /// see [RenetClient::reset_all_diagnostics].
+    pub fn reset_all_diagnostics(&mut self, client: &mut RenetClient) {
+        self.netcode_client.reset_replay_drops();
+        self.would_block_sends = 0;
+        self.unexpected_source_packets = 0;
+        client.reset_all_diagnostics();
+    }
+
+    /// Returns true if the next [update][NetcodeClientTransport::update] has work to do, so hosts with many
+    /// clients can skip the update of idle ones. The duration is the time elapsed since the last update.
+    ///
+    /// Reports packets waiting in the socket, a keep-alive or a timeout due within the duration,
+    /// or pending work in the connection, see [RenetClient::has_pending_work].
+    /// Always true while connecting, or if the socket readability cannot be checked.
+    pub fn has_pending_work(&self, duration: Duration, client: &RenetClient) -> bool {
+        if !self.netcode_client.is_connected() || client.has_pending_work() {
+            return true;
+        }
+
+        if self
+            .netcode_client
+            .time_until_next_keep_alive()
+            .is_some_and(|time_until| time_until <= duration)
+        {
+            return true;
+        }
+
+        let time_since_last_received_packet = self.netcode_client.time_since_last_received_packet().saturating_add(duration);
+        if time_since_last_received_packet >= self.netcode_client.session_timeout() {
+            return true;
+        }
+
+        // Some platforms error when peeking with a buffer smaller than the packet, so any error other than WouldBlock counts as readable
+        match self.socket.peek_from(&mut [0u8; 1]) {
+            Ok(_) => true,
+            Err(e) => e.kind() != io::ErrorKind::WouldBlock,
+        }
+    }
+
+    /// Returns the diagnostic counters, including the ones of the transport layer, and the network info
+    /// in the Prometheus text exposition format, labeled with the client id and the session id when set.
+    #[cfg(feature = "prometheus")]
+    pub fn metrics_text(&self, client: &RenetClient) -> String {
+        let mut labels = vec![("client_id", self.client_id())];
+        if let Some(session_id) = client.session_id() {
+            labels.push(("session_id", session_id));
+        }
+        crate::metrics::metrics_text(&labels, &self.diagnostics(client), &client.network_info())
+    }
+
+    /// Sets a hook called with the address and bytes of packets received from an address other than the server,
+    /// for example to log or alert on spoofing or scanning attempts. The packets are still discarded.
+    /// After a report, the packets received within `min_interval` are only counted, to avoid floods under an attack,
+    /// see [NetcodeClientTransport::unexpected_source_packets].
+    pub fn on_unexpected_source<F: FnMut(SocketAddr, &[u8]) + Send + Sync + 'static>(&mut self, min_interval: Duration, hook: F) {
+        self.on_unexpected_source = Some(UnexpectedSourceHook {
+            hook: Box::new(hook),
+            min_interval,
+            last_reported: None,
+        });
+    }
+
+    /// Returns the number of packets discarded because they were received from an address other than the server,
+    /// including the ones not reported by [NetcodeClientTransport::on_unexpected_source].
+    /// Reset by [NetcodeClientTransport::reset_all_diagnostics].
+    pub fn unexpected_source_packets(&self) -> u64 {
+        self.unexpected_source_packets
+    }
+
     /// Returns the duration since the client last received a packet.
     /// Usefull to detect timeouts.
     pub fn time_since_last_received_packet(&self) -> Duration {
         self.netcode_client.time_since_last_received_packet()
     }
 
+    /// Returns the time left without receiving packets before the connection times out,
+    /// or None if disconnected or the timeout is disabled, see [NetcodeClient::time_until_timeout].
+    pub fn time_until_timeout(&self) -> Option<Duration> {
+        self.netcode_client.time_until_timeout()
+    }
+
+    /// Returns the time until a keep-alive packet is sent to the server, or None if not connected,
+    /// see [NetcodeClient::time_until_next_keep_alive].
+    pub fn time_until_next_keep_alive(&self) -> Option<Duration> {
+        self.netcode_client.time_until_next_keep_alive()
+    }
+
+    /// Probes every server address of the connect token, returning the round-trip time of each one,
+    /// or None if no reply was received within the timeout. Blocks until all addresses replied or the timeout.
+    /// Useful to pick the best address before connecting, no session is established.
+    ///
+    /// Only servers with [ServerConfig::respond_to_probes][crate::transport::ServerConfig::respond_to_probes] reply.
+    /// Should be called before the first update, since other packets received while probing are discarded.
+    pub fn probe_addresses(&mut self, timeout: Duration) -> Vec<(SocketAddr, Option<Duration>)> {
+        let mut results: Vec<(SocketAddr, Option<Duration>)> = self
+            .netcode_client
+            .server_addresses()
+            .into_iter()
+            .map(|addr| (addr, None))
+            .collect();
+        // Random tokens, so stray replies from previous probes are not mistaken for new ones
+        let first_token = u64::from_le_bytes(generate_random_bytes());
+        let start = Instant::now();
+        for (index, (addr, _)) in results.iter().enumerate() {
+            match self.netcode_client.generate_probe_packet(first_token.wrapping_add(index as u64)) {
+                Ok(packet) => {
+                    if let Err(e) = self.socket.send_to(packet, addr) {
+                        log::error!("Failed to send probe to {addr}: {e}");
+                    }
+                }
+                Err(e) => log::error!("Failed to generate probe packet: {e}"),
+            }
+        }
+
+        while start.elapsed() < timeout && results.iter().any(|(_, rtt)| rtt.is_none()) {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((len, addr)) => {
+                    let Some(token) = self.netcode_client.process_probe_reply(&mut self.buffer[..len]) else {
+                        log::debug!("Discarded packet from {addr} while probing");
+                        continue;
+                    };
+
+                    let index = token.wrapping_sub(first_token) as usize;
+                    if let Some((probed_addr, rtt @ None)) = results.get_mut(index) {
+                        if *probed_addr == addr {
+                            *rtt = Some(start.elapsed());
+                        }
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(1)),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::error!("Failed to receive probe replies: {e}");
+                    break;
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Sends a keep-alive packet to the server immediately, out of the regular schedule.
+    /// Useful right after the application returns from the background, to refresh a NAT mapping that may have expired.
+    /// Returns false if the client is not connected or a forced keep-alive was sent less than
+    /// [NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL][renetcode::NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL] ago.
+    pub fn send_keep_alive_now(&mut self) -> Result<bool, NetcodeTransportError> {
+        let Some((addr, packet)) = self.netcode_client.generate_keep_alive_now() else {
+            return Ok(false);
+        };
+
+        self.socket.send_to(packet, addr)?;
+        Ok(true)
+    }
+
     /// Disconnect the client from the transport layer.
     /// This sends the disconnect packet instantly, use this when closing/exiting games,
     /// should use [RenetClient::disconnect][crate::RenetClient::disconnect] otherwise.
@@ -86,30 +382,118 @@ impl NetcodeClientTransport {
             return Err(NetcodeError::Disconnected(reason).into());
         }
 
+        self.would_block = false;
         let packets = connection.get_packets_to_send();
         for packet in packets {
             let (addr, payload) = self.netcode_client.generate_payload_packet(&packet)?;
-            self.socket.send_to(payload, addr)?;
+            if let Err(e) = self.socket.send_to(payload, addr) {
+                return Err(self.send_failed(e));
+            }
         }
 
         Ok(())
     }
 
+    fn send_failed(&mut self, error: io::Error) -> NetcodeTransportError {
+        if error.kind() == io::ErrorKind::WouldBlock {
+            self.would_block = true;
+            self.would_block_sends += 1;
+        }
+        error.into()
+    }
+
+    /// Disconnect the client from the transport layer, waiting for the server to confirm it.
+    /// The disconnect packet is retried after each [disconnect confirm interval][NetcodeClientTransport::set_disconnect_confirm_interval],
+    /// up to the [disconnect confirm attempts][NetcodeClientTransport::set_disconnect_confirm_attempts],
+    /// so it blocks for at most their product. Useful on lossy links, so the server releases the client slot promptly.
+    ///
+    /// Returns true if the server confirmed the disconnect, always false if the connection was already
+    /// terminated for another reason than [RenetClient::disconnect][crate::RenetClient::disconnect].
+    pub fn disconnect_and_confirm(&mut self) -> bool {
+        if self.netcode_client.is_disconnected() && self.netcode_client.disconnect_reason() != Some(DisconnectReason::DisconnectedByClient)
+        {
+            return false;
+        }
+
+        for _ in 0..self.disconnect_confirm_attempts {
+            match self.netcode_client.disconnect() {
+                Ok((addr, packet)) => {
+                    if let Err(e) = self.socket.send_to(packet, addr) {
+                        log::error!("Failed to send disconnect packet: {e}");
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to generate disconnect packet: {e}");
+                    return false;
+                }
+            }
+
+            let start = Instant::now();
+            while start.elapsed() < self.disconnect_confirm_interval {
+                match self.socket.recv_from(&mut self.buffer) {
+                    Ok((len, addr)) => {
+                        if addr == self.netcode_client.server_addr() {
+                            self.netcode_client.process_packet(&mut self.buffer[..len]);
+                        }
+                        if self.netcode_client.disconnect_confirmed() {
+                            return true;
+                        }
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(1)),
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        log::error!("Failed to receive disconnect confirmation: {e}");
+                        return false;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
     /// Advances the transport by the duration, and receive packets from the network.
-    pub fn update(&mut self, duration: Duration, client: &mut RenetClient) -> Result<(), NetcodeTransportError> {
+    /// Returns the state transitions of the update, see [NetcodeClientTransport::new_observing] for a transport
+    /// that reports errors in them instead of returning them.
+    pub fn update(&mut self, duration: Duration, client: &mut RenetClient) -> Result<UpdateOutcome, NetcodeTransportError> {
+        let was_established = self.connection_established;
+        let was_disconnected = self.netcode_client.is_disconnected();
+        let result = self.update_connection(duration, client);
+        let mut outcome = UpdateOutcome {
+            connection_established: !was_established && self.connection_established,
+            connection_lost: !was_disconnected && self.netcode_client.is_disconnected(),
+            error: None,
+        };
+
+        match result {
+            Ok(()) => Ok(outcome),
+            Err(error) if self.observing => {
+                outcome.error = Some(error);
+                Ok(outcome)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn update_connection(&mut self, duration: Duration, client: &mut RenetClient) -> Result<(), NetcodeTransportError> {
         if let Some(reason) = self.netcode_client.disconnect_reason() {
             // Mark the client as disconnected if an error occured in the transport layer
-            if !client.is_disconnected() {
+            if !self.observing && !client.is_disconnected() {
                 client.disconnect_due_to_transport();
             }
 
             return Err(NetcodeError::Disconnected(reason).into());
         }
 
+        // When observing, the connection is kept and the error is reported after the update
+        let mut client_error = None;
         if let Some(error) = client.disconnect_reason() {
-            let (addr, disconnect_packet) = self.netcode_client.disconnect()?;
-            self.socket.send_to(disconnect_packet, addr)?;
-            return Err(error.into());
+            if !self.observing {
+                let (addr, disconnect_packet) = self.netcode_client.disconnect()?;
+                self.socket.send_to(disconnect_packet, addr)?;
+                return Err(error.into());
+            }
+            client_error = Some(error);
         }
 
         loop {
@@ -117,6 +501,17 @@ impl NetcodeClientTransport {
                 Ok((len, addr)) => {
                     if addr != self.netcode_client.server_addr() {
                         log::debug!("Discarded packet from unknown server {:?}", addr);
+                        self.unexpected_source_packets += 1;
+                        let current_time = self.netcode_client.current_time();
+                        if let Some(unexpected_source) = self.on_unexpected_source.as_mut() {
+                            let report = unexpected_source
+                                .last_reported
+                                .is_none_or(|last_reported| current_time - last_reported >= unexpected_source.min_interval);
+                            if report {
+                                unexpected_source.last_reported = Some(current_time);
+                                (unexpected_source.hook)(addr, &self.buffer[..len]);
+                            }
+                        }
                         continue;
                     }
 
@@ -130,12 +525,177 @@ impl NetcodeClientTransport {
             if let Some(payload) = self.netcode_client.process_packet(packet) {
                 client.process_packet(payload);
             }
+
+            if !self.connection_established && self.netcode_client.is_connected() {
+                self.connection_established = true;
+                log::debug!(
+                    "Connection established with server {} (session {:?})",
+                    self.netcode_client.server_addr(),
+                    client.session_id()
+                );
+                client.connection_established(ConnectionEstablished {
+                    client_id: self.netcode_client.client_id(),
+                    server_addr: self.netcode_client.server_addr(),
+                });
+            }
+
+            // Deliver payloads that arrived while the handshake was being completed,
+            // before the payloads received after it in the same update
+            while let Some(payload) = self.netcode_client.take_pending_payload() {
+                client.process_packet(&payload);
+            }
         }
 
         if let Some((packet, addr)) = self.netcode_client.update(duration) {
             self.socket.send_to(packet, addr)?;
         }
 
-        Ok(())
+        while let Some((packet, addr)) = self.netcode_client.generate_burst_packet() {
+            self.socket.send_to(packet, addr)?;
+        }
+
+        match client_error {
+            Some(error) => Err(error.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use renetcode::{NetcodeServer, ServerAuthentication, ServerConfig, ServerResult};
+
+    use crate::{ChannelConfig, ConnectionConfig, RenetServer, SendType};
+
+    use super::*;
+
+    #[test]
+    fn send_would_block() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let authentication = ClientAuthentication::Unsecure {
+            protocol_id: 0,
+            client_id: 0,
+            server_addr: "127.0.0.1:5000".parse::<SocketAddr>().unwrap(),
+            user_data: None,
+        };
+        let mut transport = NetcodeClientTransport::new(Duration::ZERO, authentication, socket).unwrap();
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        assert!(!transport.last_send_would_block());
+
+        let error = transport.send_failed(io::ErrorKind::WouldBlock.into());
+        assert!(matches!(error, NetcodeTransportError::IO(_)));
+        assert!(transport.last_send_would_block());
+        // Other errors in the same tick don't clear it
+        transport.send_failed(io::ErrorKind::ConnectionRefused.into());
+        assert!(transport.last_send_would_block());
+
+        // Kept after the update, so the caller can skip sending in this tick
+        transport.update(Duration::from_millis(10), &mut client).unwrap();
+        assert!(transport.last_send_would_block());
+
+        transport.send_packets(&mut client).unwrap();
+        assert!(!transport.last_send_would_block());
+    }
+
+    #[test]
+    fn payload_received_before_connected() {
+        let config = ConnectionConfig {
+            server_channels_config: vec![ChannelConfig::new(0, 1024 * 1024, SendType::UnreliableSequenced)],
+            ..Default::default()
+        };
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server_socket.set_nonblocking(true).unwrap();
+        let server_addr = server_socket.local_addr().unwrap();
+        let mut netcode_server = NetcodeServer::new(ServerConfig::new(
+            Duration::ZERO,
+            1,
+            0,
+            vec![server_addr],
+            ServerAuthentication::Unsecure,
+        ));
+        let mut server = RenetServer::new(config.clone());
+        let authentication = ClientAuthentication::Unsecure {
+            protocol_id: 0,
+            client_id: 0,
+            server_addr,
+            user_data: None,
+        };
+        let mut transport = NetcodeClientTransport::new(Duration::ZERO, authentication, socket).unwrap();
+        let mut client = RenetClient::new(config);
+
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let mut keep_alive = None;
+        // Connection request and challenge
+        for _ in 0..100 {
+            if keep_alive.is_some() {
+                break;
+            }
+            transport.update(Duration::from_millis(100), &mut client).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+            while let Ok((len, addr)) = server_socket.recv_from(&mut buffer) {
+                match netcode_server.process_packet(addr, &mut buffer[..len]) {
+                    ServerResult::PacketToSend { addr, payload } => {
+                        server_socket.send_to(payload, addr).unwrap();
+                    }
+                    ServerResult::ClientConnected {
+                        client_id, addr, payload, ..
+                    } => {
+                        server.add_connection(client_id);
+                        keep_alive = Some((payload.to_vec(), addr));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // The server sends a message before the keep-alive that completes the handshake, and another after it
+        let (keep_alive, client_addr) = keep_alive.unwrap();
+        for message in [1u8, 2] {
+            server.send_message(0, 0, vec![message]);
+            for packet in server.get_packets_to_send(0).unwrap() {
+                let (_, payload) = netcode_server.generate_payload_packet(0, &packet).unwrap();
+                server_socket.send_to(payload, client_addr).unwrap();
+            }
+            if message == 1 {
+                server_socket.send_to(&keep_alive, client_addr).unwrap();
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+
+        transport.update(Duration::ZERO, &mut client).unwrap();
+        assert!(transport.is_connected());
+        // The buffered message is delivered first, so it isn't discarded as stale by the sequenced channel
+        assert_eq!(client.receive_message(0).unwrap(), vec![1]);
+        assert_eq!(client.receive_message(0).unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn transport_diagnostics() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let client_addr = socket.local_addr().unwrap();
+        let authentication = ClientAuthentication::Unsecure {
+            protocol_id: 0,
+            client_id: 0,
+            server_addr: "127.0.0.1:5000".parse::<SocketAddr>().unwrap(),
+            user_data: None,
+        };
+        let mut transport = NetcodeClientTransport::new(Duration::ZERO, authentication, socket).unwrap();
+        let mut client = RenetClient::new(ConnectionConfig::default());
+
+        let other_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        other_socket.send_to(&[0; 10], client_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        transport.update(Duration::from_millis(10), &mut client).unwrap();
+        transport.send_failed(io::ErrorKind::WouldBlock.into());
+
+        let diagnostics = transport.diagnostics(&client);
+        assert_eq!(diagnostics.discarded_packets, 1);
+        assert_eq!(diagnostics.would_block_sends, 1);
+
+        transport.reset_all_diagnostics(&mut client);
+        assert_eq!(transport.diagnostics(&client), DiagnosticsSnapshot::default());
     }
 }
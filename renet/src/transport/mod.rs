@@ -1,16 +1,24 @@
 use std::{error::Error, fmt};
 
 mod client;
+mod reconnect;
 mod server;
+mod threaded;
 
 pub use client::*;
+pub use reconnect::*;
 pub use server::*;
+pub use threaded::*;
 
 pub use renetcode::{
-    generate_random_bytes, ClientAuthentication, ConnectToken, DisconnectReason as NetcodeDisconnectReason, NetcodeError,
-    ServerAuthentication, ServerConfig, TokenGenerationError, NETCODE_KEY_BYTES, NETCODE_USER_DATA_BYTES,
+    generate_random_bytes, ClientAuthentication, ConnectToken, ConnectTokenReusePolicy, DisconnectReason as NetcodeDisconnectReason,
+    NetcodeError, ServerAuthentication, ServerConfig, TokenGenerationError, NETCODE_KEY_BYTES, NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL,
+    NETCODE_REPLAY_PROTECTION_WINDOW, NETCODE_USER_DATA_BYTES,
 };
 
+#[cfg(feature = "test-util")]
+pub use renetcode::ConnectTokenRandomness;
+
 #[derive(Debug)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Event))]
 pub enum NetcodeTransportError {
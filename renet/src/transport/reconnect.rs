@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use renetcode::generate_random_bytes;
+
+/// Schedules reconnect attempts after the connection is lost, doubling the interval after each failed attempt.
+/// Intervals are bounded by a minimum and a maximum, and shortened by a random jitter to spread the
+/// reconnects of many clients, for example when a server restarts and all of them lose the connection at once.
+/// The jitter is applied after the maximum, so clients that reached it still reconnect at different times.
+///
+/// The schedule doesn't reconnect by itself, the application creates a new transport when it's time to reconnect.
+///
+/// # Usage
+/// ```
+/// # use std::time::Duration;
+/// # use renet::transport::ReconnectBackoff;
+/// let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(30));
+/// // When the transport reports the connection was lost
+/// backoff.disconnected();
+///
+/// // Every tick
+/// backoff.update(Duration::from_millis(16));
+/// if backoff.should_reconnect() {
+///     // Create a new NetcodeClientTransport and RenetClient
+/// }
+/// // When the new connection is established
+/// backoff.connected();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    min_reconnect_interval: Duration,
+    max_reconnect_interval: Duration,
+    jitter: f64,
+    rng_state: u64,
+    current_time: Duration,
+    failed_attempts: u32,
+    next_reconnect_at: Option<Duration>,
+}
+
+impl ReconnectBackoff {
+    /// Creates a schedule that waits `min_reconnect_interval` before the first attempt, doubling it up to
+    /// `max_reconnect_interval` after each failed attempt, shortened by a jitter of up to 50% of the interval.
+    /// Panics if the minimum interval is greater than the maximum.
+    pub fn new(min_reconnect_interval: Duration, max_reconnect_interval: Duration) -> Self {
+        assert!(
+            min_reconnect_interval <= max_reconnect_interval,
+            "The min reconnect interval cannot be greater than the max reconnect interval"
+        );
+
+        Self {
+            min_reconnect_interval,
+            max_reconnect_interval,
+            jitter: 0.5,
+            rng_state: u64::from_le_bytes(generate_random_bytes()),
+            current_time: Duration::ZERO,
+            failed_attempts: 0,
+            next_reconnect_at: None,
+        }
+    }
+
+    /// Sets the maximum random delay removed from each interval, as a fraction of it.
+    /// Use 0.0 to disable the jitter.
+    /// Panics if it's not between 0.0 and 1.0.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        assert!((0.0..=1.0).contains(&jitter), "The reconnect jitter must be between 0.0 and 1.0");
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the seed of the jitter, so the schedule is deterministic in tests.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng_state = seed;
+        self
+    }
+
+    /// Advances the time of the schedule.
+    pub fn update(&mut self, duration: Duration) {
+        self.current_time += duration;
+    }
+
+    /// Returns the time of the schedule, advanced by [ReconnectBackoff::update].
+    pub fn current_time(&self) -> Duration {
+        self.current_time
+    }
+
+    /// Schedules the next reconnect attempt, should be called when the connection is lost or a reconnect attempt fails.
+    /// Does nothing if an attempt is already scheduled.
+    pub fn disconnected(&mut self) {
+        if self.next_reconnect_at.is_some() {
+            return;
+        }
+
+        let interval = self
+            .min_reconnect_interval
+            .saturating_mul(2u32.saturating_pow(self.failed_attempts))
+            .min(self.max_reconnect_interval);
+        let interval = interval.mul_f64(1.0 - self.jitter * self.next_random());
+
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        self.next_reconnect_at = Some(self.current_time + interval);
+    }
+
+    /// Resets the interval to the minimum, should be called when a connection is established.
+    pub fn connected(&mut self) {
+        self.failed_attempts = 0;
+        self.next_reconnect_at = None;
+    }
+
+    /// Returns true once the scheduled attempt is due, the attempt is no longer scheduled after that.
+    pub fn should_reconnect(&mut self) -> bool {
+        match self.next_reconnect_at {
+            Some(next_reconnect_at) if self.current_time >= next_reconnect_at => {
+                self.next_reconnect_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the time of the next reconnect attempt, or None if no attempt is scheduled.
+    pub fn next_reconnect_at(&self) -> Option<Duration> {
+        self.next_reconnect_at
+    }
+
+    /// Returns the number of attempts scheduled since the last established connection.
+    pub fn attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    // SplitMix64, returns a value in [0, 1)
+    fn next_random(&mut self) -> f64 {
+        self.rng_state = self.rng_state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_bounds() {
+        let min = Duration::from_secs(1);
+        let max = Duration::from_secs(10);
+        let mut backoff = ReconnectBackoff::new(min, max).with_jitter(0.0);
+        assert_eq!(backoff.next_reconnect_at(), None);
+
+        let mut expected = vec![];
+        for _ in 0..6 {
+            backoff.disconnected();
+            let next_reconnect_at = backoff.next_reconnect_at().unwrap();
+            expected.push(next_reconnect_at - backoff.current_time());
+
+            backoff.update(next_reconnect_at - backoff.current_time() - Duration::from_millis(1));
+            assert!(!backoff.should_reconnect());
+            backoff.update(Duration::from_millis(1));
+            assert!(backoff.should_reconnect());
+            assert!(!backoff.should_reconnect());
+        }
+
+        let secs: Vec<u64> = expected.iter().map(|interval| interval.as_secs()).collect();
+        assert_eq!(secs, vec![1, 2, 4, 8, 10, 10]);
+
+        backoff.connected();
+        backoff.disconnected();
+        assert_eq!(backoff.next_reconnect_at(), Some(backoff.current_time() + min));
+    }
+
+    #[test]
+    fn seeded_jitter() {
+        let min = Duration::from_secs(1);
+        let max = Duration::from_secs(2);
+        let mut a = ReconnectBackoff::new(min, max).with_seed(42);
+        let mut b = ReconnectBackoff::new(min, max).with_seed(42);
+        let mut c = ReconnectBackoff::new(min, max).with_seed(7);
+
+        a.disconnected();
+        b.disconnected();
+        c.disconnected();
+        assert_eq!(a.next_reconnect_at(), b.next_reconnect_at());
+        assert_ne!(a.next_reconnect_at(), c.next_reconnect_at());
+
+        let next_reconnect_at = a.next_reconnect_at().unwrap();
+        assert!(next_reconnect_at >= min.mul_f64(0.5) && next_reconnect_at <= min);
+    }
+
+    #[test]
+    fn jitter_at_max_interval() {
+        let min = Duration::from_secs(1);
+        let max = Duration::from_secs(4);
+        let intervals: Vec<Duration> = (0..8)
+            .map(|seed| {
+                let mut backoff = ReconnectBackoff::new(min, max).with_seed(seed);
+                // Reach the maximum interval
+                for _ in 0..5 {
+                    backoff.disconnected();
+                    backoff.update(backoff.next_reconnect_at().unwrap() - backoff.current_time());
+                    assert!(backoff.should_reconnect());
+                }
+                backoff.disconnected();
+                backoff.next_reconnect_at().unwrap() - backoff.current_time()
+            })
+            .collect();
+
+        assert!(intervals.iter().all(|interval| *interval >= max.mul_f64(0.5) && *interval <= max));
+        assert!(intervals.iter().any(|interval| *interval != intervals[0]));
+    }
+}
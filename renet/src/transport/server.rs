@@ -16,6 +16,7 @@ pub struct NetcodeServerTransport {
     socket: UdpSocket,
     netcode_server: NetcodeServer,
     buffer: [u8; NETCODE_MAX_PACKET_BYTES],
+    would_block: bool,
 }
 
 impl NetcodeServerTransport {
@@ -28,9 +29,30 @@ impl NetcodeServerTransport {
             socket,
             netcode_server,
             buffer: [0; NETCODE_MAX_PACKET_BYTES],
+            would_block: false,
         })
     }
 
+    /// Returns true if sending a packet failed because the socket buffer was full in the last [NetcodeServerTransport::send_packets].
+    /// Use it to skip calling [NetcodeServerTransport::send_packets] in the next tick, avoiding repeated failures.
+    /// Std sockets cannot be polled for writability, so it reports the outcome of the last send attempt,
+    /// not the current state of the socket. It's kept across updates and cleared when sending packets again.
+    pub fn last_send_would_block(&self) -> bool {
+        self.would_block
+    }
+
+    /// Returns the socket of the transport, to set options that are not wrapped, like the DSCP/ToS for QoS.
+    /// The transport expects the socket to stay non-blocking and bound to the same address,
+    /// setting it back to blocking makes [update][NetcodeServerTransport::update] block until a packet is received.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns the socket of the transport mutably, see [NetcodeServerTransport::socket].
+    pub fn socket_mut(&mut self) -> &mut UdpSocket {
+        &mut self.socket
+    }
+
     /// Returns the server public address
     pub fn addresses(&self) -> Vec<SocketAddr> {
         self.netcode_server.addresses()
@@ -51,6 +73,12 @@ impl NetcodeServerTransport {
         self.netcode_server.user_data(client_id)
     }
 
+    /// Returns the time without receiving packets before the client times out, if connected,
+    /// see [NetcodeServer::session_timeout][renetcode::NetcodeServer::session_timeout].
+    pub fn session_timeout(&self, client_id: u64) -> Option<Duration> {
+        self.netcode_server.session_timeout(client_id)
+    }
+
     /// Returns the client address if connected.
     pub fn client_addr(&self, client_id: u64) -> Option<SocketAddr> {
         self.netcode_server.client_addr(client_id)
@@ -72,6 +100,12 @@ impl NetcodeServerTransport {
         self.netcode_server.time_since_last_received_packet(client_id)
     }
 
+    /// Returns the time left without receiving packets before the client times out, if connected,
+    /// see [NetcodeServer::time_until_timeout][renetcode::NetcodeServer::time_until_timeout].
+    pub fn time_until_timeout(&self, client_id: u64) -> Option<Duration> {
+        self.netcode_server.time_until_timeout(client_id)
+    }
+
     /// Advances the transport by the duration, and receive packets from the network.
     pub fn update(&mut self, duration: Duration, server: &mut RenetServer) -> Result<(), NetcodeTransportError> {
         self.netcode_server.update(duration);
@@ -104,13 +138,16 @@ impl NetcodeServerTransport {
 
     /// Send packets to connected clients.
     pub fn send_packets(&mut self, server: &mut RenetServer) {
+        self.would_block = false;
         'clients: for client_id in server.clients_id() {
             let packets = server.get_packets_to_send(client_id).unwrap();
             for packet in packets {
                 match self.netcode_server.generate_payload_packet(client_id, &packet) {
                     Ok((addr, payload)) => {
                         if let Err(e) = self.socket.send_to(payload, addr) {
-                            log::error!("Failed to send packet to client {client_id} ({addr}): {e}");
+                            if self.send_failed(client_id, addr, e) {
+                                return;
+                            }
                             continue 'clients;
                         }
                     }
@@ -122,6 +159,18 @@ impl NetcodeServerTransport {
             }
         }
     }
+
+    /// Returns true if the socket would block, so no more packets should be sent in this tick.
+    fn send_failed(&mut self, client_id: u64, addr: SocketAddr, error: io::Error) -> bool {
+        if error.kind() == io::ErrorKind::WouldBlock {
+            // The socket is shared by all clients, they would fail too
+            log::debug!("Stopped sending packets, socket would block");
+            self.would_block = true;
+            return true;
+        }
+        log::error!("Failed to send packet to client {client_id} ({addr}): {error}");
+        false
+    }
 }
 
 fn handle_server_result(server_result: ServerResult, socket: &UdpSocket, reliable_server: &mut RenetServer) {
@@ -158,3 +207,43 @@ fn handle_server_result(server_result: ServerResult, socket: &UdpSocket, reliabl
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use renetcode::ServerAuthentication;
+
+    use crate::ConnectionConfig;
+
+    use super::*;
+
+    #[test]
+    fn send_would_block() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_config = ServerConfig::new(
+            Duration::ZERO,
+            1,
+            0,
+            vec![socket.local_addr().unwrap()],
+            ServerAuthentication::Unsecure,
+        );
+        let mut transport = NetcodeServerTransport::new(server_config, socket).unwrap();
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        let addr = "127.0.0.1:5000".parse().unwrap();
+        assert!(!transport.last_send_would_block());
+
+        // Other errors only skip the client
+        assert!(!transport.send_failed(0, addr, io::ErrorKind::ConnectionRefused.into()));
+        assert!(!transport.last_send_would_block());
+
+        // The socket is shared, so sending stops for all clients
+        assert!(transport.send_failed(0, addr, io::ErrorKind::WouldBlock.into()));
+        assert!(transport.last_send_would_block());
+
+        // Kept after the update, so the caller can skip sending in this tick
+        transport.update(Duration::from_millis(10), &mut server).unwrap();
+        assert!(transport.last_send_would_block());
+
+        transport.send_packets(&mut server);
+        assert!(!transport.last_send_would_block());
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+
+use crate::remote_connection::{ClientEvent, RenetClient};
+
+use super::{NetcodeClientTransport, NetcodeTransportError};
+
+#[derive(Debug)]
+enum Command {
+    SendMessage { channel_id: u8, message: Bytes },
+    Disconnect,
+}
+
+/// Runs a [RenetClient] and its [NetcodeClientTransport] on a background thread.
+/// Messages and events are exchanged with the thread using channels, so the socket is never touched by the caller.
+///
+/// The thread updates the client every tick until [ThreadedClient::shutdown] is called,
+/// the handle is dropped or the connection is terminated.
+///
+/// # Usage
+/// ```no_run
+/// # use std::{net::UdpSocket, time::{Duration, SystemTime}};
+/// # use renet::{ConnectionConfig, DefaultChannel, RenetClient};
+/// # use renet::transport::{ClientAuthentication, NetcodeClientTransport, ThreadedClient};
+/// # let server_addr = "127.0.0.1:5000".parse().unwrap();
+/// # let authentication = ClientAuthentication::Unsecure { protocol_id: 0, client_id: 0, server_addr, user_data: None };
+/// # let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let client = RenetClient::new(ConnectionConfig::default());
+/// let transport = NetcodeClientTransport::new(current_time, authentication, socket).unwrap();
+/// let threaded_client = ThreadedClient::spawn(client, transport, Duration::from_millis(16)).unwrap();
+///
+/// threaded_client.send_message(DefaultChannel::ReliableOrdered, "Hello");
+/// while let Some((channel_id, message)) = threaded_client.receive_message() {
+///     println!("Received message in channel {channel_id}: {message:?}");
+/// }
+///
+/// // Sends the disconnect packet and waits for the thread to finish
+/// threaded_client.shutdown().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ThreadedClient {
+    send_channel_ids: Vec<u8>,
+    commands: Sender<Command>,
+    messages: Receiver<(u8, Bytes)>,
+    events: Receiver<ClientEvent>,
+    thread: Option<JoinHandle<Result<(), NetcodeTransportError>>>,
+}
+
+impl ThreadedClient {
+    /// Moves the client and its transport to a new thread, updating them every tick.
+    pub fn spawn(client: RenetClient, transport: NetcodeClientTransport, tick: Duration) -> std::io::Result<Self> {
+        let (commands_sender, commands_receiver) = mpsc::channel();
+        let (messages_sender, messages_receiver) = mpsc::channel();
+        let (events_sender, events_receiver) = mpsc::channel();
+        let send_channel_ids = client.send_channels_config.iter().map(|config| config.channel_id).collect();
+
+        let thread = thread::Builder::new()
+            .name("renet-client".to_string())
+            .spawn(move || run(client, transport, tick, commands_receiver, messages_sender, events_sender))?;
+
+        Ok(Self {
+            send_channel_ids,
+            commands: commands_sender,
+            messages: messages_receiver,
+            events: events_receiver,
+            thread: Some(thread),
+        })
+    }
+
+    /// Queues a message to be sent by the client thread in its next update.
+    /// The message is discarded if the thread has already finished.
+    ///
+    /// Panics if the client doesn't have the channel, like [RenetClient::send_message],
+    /// but on the calling thread instead of the client thread.
+    pub fn send_message<I: Into<u8>, B: Into<Bytes>>(&self, channel_id: I, message: B) {
+        let channel_id = channel_id.into();
+        if !self.send_channel_ids.contains(&channel_id) {
+            panic!("Called 'send_message' with invalid channel {channel_id}");
+        }
+
+        let command = Command::SendMessage {
+            channel_id,
+            message: message.into(),
+        };
+        // Fails only if the thread finished, there's no connection to send it
+        let _ = self.commands.send(command);
+    }
+
+    /// Returns the next message received by the client thread, with the channel it was received in.
+    pub fn receive_message(&self) -> Option<(u8, Bytes)> {
+        self.messages.try_recv().ok()
+    }
+
+    /// Returns the next event emitted by the client, see [RenetClient::get_event].
+    pub fn get_event(&self) -> Option<ClientEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Returns if the client thread has finished, the connection was terminated.
+    /// Use [ThreadedClient::shutdown] to retrieve the reason.
+    pub fn is_disconnected(&self) -> bool {
+        match &self.thread {
+            Some(thread) => thread.is_finished(),
+            None => true,
+        }
+    }
+
+    /// Disconnects the client, sending the disconnect packet, and waits for the thread to finish.
+    /// Returns the error that terminated the connection if it was lost before the shutdown.
+    pub fn shutdown(mut self) -> Result<(), NetcodeTransportError> {
+        match self.stop() {
+            Some(Ok(result)) => result,
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => Ok(()),
+        }
+    }
+
+    fn stop(&mut self) -> Option<thread::Result<Result<(), NetcodeTransportError>>> {
+        let thread = self.thread.take()?;
+        // Fails only if the thread already finished
+        let _ = self.commands.send(Command::Disconnect);
+        Some(thread.join())
+    }
+}
+
+impl Drop for ThreadedClient {
+    fn drop(&mut self) {
+        // Errors and panics cannot be reported when dropping
+        let _ = self.stop();
+    }
+}
+
+fn run(
+    mut client: RenetClient,
+    mut transport: NetcodeClientTransport,
+    tick: Duration,
+    commands: Receiver<Command>,
+    messages: Sender<(u8, Bytes)>,
+    events: Sender<ClientEvent>,
+) -> Result<(), NetcodeTransportError> {
+    let channel_ids: Vec<u8> = client.receive_channels_config.iter().map(|config| config.channel_id).collect();
+    let mut last_updated = Instant::now();
+
+    loop {
+        loop {
+            match commands.try_recv() {
+                Ok(Command::SendMessage { channel_id, message }) => client.send_message(channel_id, message),
+                Ok(Command::Disconnect) | Err(TryRecvError::Disconnected) => {
+                    transport.disconnect();
+                    return Ok(());
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        let now = Instant::now();
+        let duration = now - last_updated;
+        last_updated = now;
+
+        client.update(duration);
+        transport.update(duration, &mut client)?;
+
+        // Sending only fails if the handle was dropped, the thread stops once the commands are processed
+        for &channel_id in channel_ids.iter() {
+            while let Some(message) = client.receive_message(channel_id) {
+                let _ = messages.send((channel_id, message));
+            }
+        }
+
+        while let Some(event) = client.get_event() {
+            let _ = events.send(event);
+        }
+
+        // Messages are kept in the channels until the connection is established
+        if transport.is_connected() {
+            transport.send_packets(&mut client)?;
+        }
+
+        thread::sleep(tick.saturating_sub(last_updated.elapsed()));
+    }
+}
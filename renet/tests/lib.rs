@@ -1,5 +1,18 @@
+use std::{
+    net::UdpSocket,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
 use bytes::Bytes;
-use renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+use renet::{
+    transport::{
+        ClientAuthentication, NetcodeClientTransport, NetcodeServerTransport, NetcodeTransportError, ServerAuthentication, ServerConfig,
+        ThreadedClient,
+    },
+    ChannelConfig, ClientEvent, ConnectionConfig, DefaultChannel, ReconfigureError, ReconfigurePolicy, RenetClient, RenetServer, SendType,
+    ServerEvent,
+};
 
 pub fn init_log() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -54,3 +67,397 @@ fn test_remote_connection_reliable_channel() {
 
     assert_eq!(count, 10);
 }
+
+#[test]
+fn test_broadcast_many_clients() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut clients: Vec<RenetClient> = (0..100).map(|_| RenetClient::new(ConnectionConfig::default())).collect();
+    for client_id in 0..clients.len() as u64 {
+        server.add_connection(client_id);
+    }
+
+    server.broadcast_message(DefaultChannel::ReliableOrdered, Bytes::from("test".repeat(1000)));
+
+    for (client_id, client) in clients.iter_mut().enumerate() {
+        for packet in server.get_packets_to_send(client_id as u64).unwrap() {
+            client.process_packet(&packet);
+        }
+
+        let received_message = client.receive_message(DefaultChannel::ReliableOrdered).unwrap();
+        assert_eq!(received_message, "test".repeat(1000));
+    }
+}
+
+#[test]
+fn test_server_update_at_clock_went_backwards() {
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    server.add_connection(0);
+
+    server.update_at(Duration::from_secs(100)).unwrap();
+    server.update_at(Duration::from_secs(101)).unwrap();
+    assert_eq!(server.network_info(0).unwrap().current_time, Duration::from_secs(1));
+
+    // The connections don't move back and the time is measured from the latest time
+    let error = server.update_at(Duration::from_secs(99)).unwrap_err();
+    assert_eq!(error.previous, Duration::from_secs(101));
+    assert_eq!(error.now, Duration::from_secs(99));
+    assert_eq!(server.network_info(0).unwrap().current_time, Duration::from_secs(1));
+    assert_eq!(server.clock_regressions(), 1);
+    assert!(server.is_connected(0));
+
+    // A clock that didn't tick isn't a regression
+    server.update_at(Duration::from_secs(101)).unwrap();
+    server.update_at(Duration::from_secs(101)).unwrap();
+    server.update_at(Duration::from_secs(102)).unwrap();
+    assert_eq!(server.network_info(0).unwrap().current_time, Duration::from_secs(2));
+    assert_eq!(server.clock_regressions(), 1);
+}
+
+#[test]
+fn test_connection_established_event() {
+    init_log();
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let server_config = ServerConfig::new(Duration::ZERO, 1, 0, vec![server_addr], ServerAuthentication::Unsecure);
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let mut client = RenetClient::new(ConnectionConfig::default());
+    let mut client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+
+    let delta = Duration::from_millis(10);
+    let mut events = vec![];
+    for _ in 0..200 {
+        client_transport.update(delta, &mut client).unwrap();
+        client_transport.send_packets(&mut client).unwrap();
+        server_transport.update(delta, &mut server).unwrap();
+        server_transport.send_packets(&mut server);
+        while let Some(event) = client.get_event() {
+            events.push(event);
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    assert!(client_transport.is_connected());
+    assert!(!client_transport.last_send_would_block());
+    assert!(!server_transport.last_send_would_block());
+    let established: Vec<_> = events
+        .into_iter()
+        .filter_map(|event| match event {
+            ClientEvent::ConnectionEstablished(connection) => Some(connection),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(established.len(), 1);
+    assert_eq!(established[0].client_id, 7);
+    assert_eq!(established[0].server_addr, server_addr);
+}
+
+#[test]
+fn test_probe_addresses() {
+    init_log();
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let mut server_config = ServerConfig::new(Duration::ZERO, 1, 0, vec![server_addr], ServerAuthentication::Unsecure);
+    server_config.respond_to_probes = true;
+    let server_thread = std::thread::spawn(move || {
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        let mut server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+        for _ in 0..500 {
+            server_transport.update(Duration::from_millis(1), &mut server).unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!server.has_connections());
+    });
+
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let mut client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+    let results = client_transport.probe_addresses(Duration::from_millis(400));
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0, server_addr);
+    assert!(results[0].1.is_some());
+    assert!(!client_transport.is_connected());
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_reconfigure_channels() {
+    init_log();
+    let menu_config = ConnectionConfig::default();
+    let mut match_config = ConnectionConfig::default();
+    let match_channel = ChannelConfig::new(3, 1024 * 1024, SendType::UnreliableSequenced);
+    match_config.client_channels_config.push(match_channel.clone());
+    match_config.server_channels_config.push(match_channel);
+    // Removes the unreliable channel
+    match_config.client_channels_config.remove(0);
+    match_config.server_channels_config.remove(0);
+
+    let mut server = RenetServer::new(menu_config);
+    let mut client = RenetClient::new(ConnectionConfig::default());
+    let client_id = 0u64;
+    server.add_connection(client_id);
+
+    // Messages still to be delivered in a removed channel
+    client.send_message(DefaultChannel::Unreliable, Bytes::from("menu"));
+    client.send_message(DefaultChannel::ReliableOrdered, Bytes::from("kept"));
+    assert_eq!(
+        client.reconfigure_channels(&match_config, ReconfigurePolicy::RequireDrained),
+        Err(ReconfigureError::ChannelsNotDrained { channel_ids: vec![0] })
+    );
+
+    // Sent before the client reconfigured its channels
+    let packets = client.get_packets_to_send();
+    client
+        .reconfigure_channels(&match_config, ReconfigurePolicy::RequireDrained)
+        .unwrap();
+    server
+        .reconfigure_channels(client_id, &match_config, ReconfigurePolicy::Discard)
+        .unwrap();
+    assert_eq!(
+        server.reconfigure_channels(1, &match_config, ReconfigurePolicy::Discard),
+        Err(ReconfigureError::ClientNotFound)
+    );
+
+    // Message in the removed channel is ignored, without disconnecting
+    for packet in packets {
+        server.process_packet_from(&packet, client_id).unwrap();
+    }
+    assert_eq!(server.disconnect_reason(client_id), None);
+    assert_eq!(server.receive_message(client_id, DefaultChannel::ReliableOrdered).unwrap(), "kept");
+
+    server.send_message(client_id, 3, Bytes::from("match"));
+    for packet in server.get_packets_to_send(client_id).unwrap() {
+        client.process_packet(&packet);
+    }
+    assert_eq!(client.receive_message(3).unwrap(), "match");
+    assert!(!client.is_disconnected());
+}
+
+#[test]
+fn test_session_id() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let client_id = 0u64;
+
+    // Each reconnection is a new connection, tagged with a new session id
+    for session_id in [10, 11] {
+        server.add_connection(client_id);
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        let status = client.status_handle();
+        assert_eq!(client.network_info().session_id, None);
+        assert_eq!(server.session_id(client_id), None);
+
+        client.set_session_id(session_id);
+        server.set_session_id(client_id, session_id);
+        for _ in 0..2 {
+            client.update(Duration::from_millis(10));
+            server.update(Duration::from_millis(10));
+            for packet in client.get_packets_to_send() {
+                server.process_packet_from(&packet, client_id).unwrap();
+            }
+            for packet in server.get_packets_to_send(client_id).unwrap() {
+                client.process_packet(&packet);
+            }
+        }
+
+        assert!(!client.is_disconnected());
+        assert_eq!(client.network_info().session_id, Some(session_id));
+        assert_eq!(status.network_info().session_id, Some(session_id));
+        assert_eq!(server.network_info(client_id).unwrap().session_id, Some(session_id));
+
+        server.remove_connection(client_id);
+    }
+}
+
+#[test]
+fn test_threaded_client() {
+    init_log();
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let server_config = ServerConfig::new(Duration::ZERO, 1, 0, vec![server_addr], ServerAuthentication::Unsecure);
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let client = RenetClient::new(ConnectionConfig::default());
+    let client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+    let client = ThreadedClient::spawn(client, client_transport, Duration::from_millis(5)).unwrap();
+    client.send_message(DefaultChannel::ReliableOrdered, Bytes::from("ping"));
+
+    let delta = Duration::from_millis(5);
+    let mut server_received = None;
+    let mut client_received = None;
+    let mut connected = false;
+    for _ in 0..400 {
+        server.update(delta);
+        server_transport.update(delta, &mut server).unwrap();
+        if server_received.is_none() {
+            server_received = server.receive_message(7, DefaultChannel::ReliableOrdered);
+            if server_received.is_some() {
+                server.send_message(7, DefaultChannel::ReliableOrdered, Bytes::from("pong"));
+            }
+        }
+        server_transport.send_packets(&mut server);
+
+        while let Some(event) = client.get_event() {
+            connected |= matches!(event, ClientEvent::ConnectionEstablished(_));
+        }
+        if let Some(message) = client.receive_message() {
+            client_received = Some(message);
+            break;
+        }
+        std::thread::sleep(delta);
+    }
+
+    assert!(connected);
+    assert_eq!(server_received.unwrap(), "ping");
+    assert_eq!(
+        client_received.unwrap(),
+        (DefaultChannel::ReliableOrdered.into(), Bytes::from("pong"))
+    );
+    assert!(!client.is_disconnected());
+
+    client.shutdown().unwrap();
+    let mut disconnected = false;
+    for _ in 0..10 {
+        server_transport.update(delta, &mut server).unwrap();
+        while let Some(event) = server.get_event() {
+            disconnected |= matches!(event, ServerEvent::ClientDisconnected { client_id: 7, .. });
+        }
+        std::thread::sleep(delta);
+    }
+    assert!(disconnected);
+}
+
+#[test]
+#[should_panic(expected = "invalid channel 9")]
+fn test_threaded_client_invalid_channel() {
+    let server_addr = "127.0.0.1:5000".parse().unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let client = RenetClient::new(ConnectionConfig::default());
+    let client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+    let client = ThreadedClient::spawn(client, client_transport, Duration::from_millis(5)).unwrap();
+    // Panics on the calling thread, not on the client thread
+    client.send_message(9, Bytes::from("ping"));
+}
+
+#[test]
+fn test_unexpected_source() {
+    init_log();
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let mut client = RenetClient::new(ConnectionConfig::default());
+    let mut client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let hook_reported = reported.clone();
+    client_transport.on_unexpected_source(Duration::from_secs(1), move |addr, packet| {
+        hook_reported.lock().unwrap().push((addr, packet.to_vec()));
+    });
+
+    let scanner_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let scanner_addr = scanner_socket.local_addr().unwrap();
+    let client_addr = client_transport.addr().unwrap();
+    for i in 0..3 {
+        scanner_socket.send_to(&[i; 32], client_addr).unwrap();
+    }
+    std::thread::sleep(Duration::from_millis(50));
+    client_transport.update(Duration::from_millis(10), &mut client).unwrap();
+
+    // Rate limited to one report per second
+    assert_eq!(client_transport.unexpected_source_packets(), 3);
+    assert_eq!(*reported.lock().unwrap(), vec![(scanner_addr, vec![0; 32])]);
+
+    client_transport.update(Duration::from_secs(1), &mut client).unwrap();
+    scanner_socket.send_to(&[3; 32], client_addr).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    client_transport.update(Duration::from_millis(10), &mut client).unwrap();
+    assert_eq!(client_transport.unexpected_source_packets(), 4);
+    assert_eq!(reported.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_observing_transport() {
+    init_log();
+    let server_addr = "127.0.0.1:5000".parse().unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut client = RenetClient::new(ConnectionConfig::default());
+    let mut client_transport = NetcodeClientTransport::new_observing(Duration::ZERO, authentication, client_socket).unwrap();
+    assert!(client_transport.is_observing());
+
+    // Disconnecting the client doesn't disconnect the transport
+    client.disconnect();
+    let outcome = client_transport.update(Duration::from_millis(10), &mut client).unwrap();
+    assert!(matches!(outcome.error, Some(NetcodeTransportError::Renet(_))));
+    assert!(!outcome.connection_lost);
+    assert!(client_transport.is_connecting());
+
+    // The transport timing out doesn't disconnect the client
+    let mut client = RenetClient::new(ConnectionConfig::default());
+    let timeout = client_transport.time_until_timeout().unwrap();
+    let outcome = client_transport.update(timeout + Duration::from_millis(1), &mut client).unwrap();
+    assert!(outcome.connection_lost);
+    assert!(client_transport.is_disconnected());
+    assert!(!client.is_disconnected());
+
+    let outcome = client_transport.update(Duration::from_millis(10), &mut client).unwrap();
+    assert!(!outcome.connection_lost);
+    assert!(matches!(outcome.error, Some(NetcodeTransportError::Netcode(_))));
+    assert!(!client.is_disconnected());
+}
+
+#[test]
+fn test_transport_socket() {
+    let server_addr = "127.0.0.1:5000".parse().unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 0,
+        client_id: 7,
+        server_addr,
+        user_data: None,
+    };
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+
+    client_transport.socket_mut().set_ttl(32).unwrap();
+    assert_eq!(client_transport.socket().ttl().unwrap(), 32);
+    assert_eq!(client_transport.socket().local_addr().unwrap(), client_transport.addr().unwrap());
+}
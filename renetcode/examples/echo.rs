@@ -120,13 +120,13 @@ fn handle_server_result(
 
 fn server(addr: SocketAddr, private_key: [u8; NETCODE_KEY_BYTES]) {
     let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    let config = ServerConfig {
+    let config = ServerConfig::new(
         current_time,
-        max_clients: 16,
-        protocol_id: PROTOCOL_ID,
-        public_addresses: vec![addr],
-        authentication: ServerAuthentication::Secure { private_key },
-    };
+        16,
+        PROTOCOL_ID,
+        vec![addr],
+        ServerAuthentication::Secure { private_key },
+    );
     let mut server: NetcodeServer = NetcodeServer::new(config);
     let udp_socket = UdpSocket::bind(addr).unwrap();
     udp_socket.set_nonblocking(true).unwrap();
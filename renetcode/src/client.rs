@@ -1,8 +1,9 @@
-use std::{fmt, net::SocketAddr, time::Duration};
+use std::{collections::VecDeque, fmt, net::SocketAddr, time::Duration};
 
 use crate::{
-    packet::Packet, replay_protection::ReplayProtection, token::ConnectToken, ClientID, NetcodeError, NETCODE_CHALLENGE_TOKEN_BYTES,
-    NETCODE_KEY_BYTES, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_SEND_RATE, NETCODE_USER_DATA_BYTES,
+    packet::Packet, replay_protection::ReplayProtection, session_timeout, token::ConnectToken, ClientID, NetcodeError,
+    NETCODE_CHALLENGE_TOKEN_BYTES, NETCODE_KEY_BYTES, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_MAX_PENDING_PAYLOADS,
+    NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL, NETCODE_SEND_RATE, NETCODE_TIMEOUT_SECONDS, NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
 };
 
 /// The reason why a client is in error state
@@ -13,6 +14,12 @@ pub enum DisconnectReason {
     ConnectionResponseTimedOut,
     ConnectionRequestTimedOut,
     ConnectionDenied,
+    /// The server denied the connection because the connect token is already used by another client,
+    /// only reported by servers with [ConnectTokenReusePolicy::Deny][crate::ConnectTokenReusePolicy::Deny].
+    ConnectTokenAlreadyUsed,
+    /// Consecutive packets from the server failed authentication, as expected when the server changed its protocol id,
+    /// see [NetcodeClient::set_protocol_mismatch_threshold].
+    ProtocolMismatch,
     DisconnectedByClient,
     DisconnectedByServer,
 }
@@ -66,6 +73,17 @@ pub struct NetcodeClient {
     client_index: u32,
     send_rate: Duration,
     replay_protection: ReplayProtection,
+    replay_drops: u64,
+    keep_alive_padding: usize,
+    pending_payloads: VecDeque<Vec<u8>>,
+    max_pending_payloads: usize,
+    initial_request_burst: u8,
+    burst_requests_remaining: u8,
+    last_forced_keep_alive: Option<Duration>,
+    disconnect_confirmed: bool,
+    protocol_mismatch_threshold: Option<u32>,
+    consecutive_authentication_failures: u32,
+    connect_timeout: Duration,
     out: [u8; NETCODE_MAX_PACKET_BYTES],
 }
 
@@ -79,6 +97,11 @@ impl fmt::Display for DisconnectReason {
             ConnectionResponseTimedOut => write!(f, "connection timed out during response step"),
             ConnectionRequestTimedOut => write!(f, "connection timed out during request step"),
             ConnectionDenied => write!(f, "server denied connection"),
+            ConnectTokenAlreadyUsed => write!(
+                f,
+                "server denied connection because the connect token is already used by another client"
+            ),
+            ProtocolMismatch => write!(f, "packets from the server failed authentication for the protocol of the session"),
             DisconnectedByClient => write!(f, "connection terminated by client"),
             DisconnectedByServer => write!(f, "connection terminated by server"),
         }
@@ -98,7 +121,7 @@ impl NetcodeClient {
                 protocol_id,
                 300,
                 client_id,
-                15,
+                NETCODE_TIMEOUT_SECONDS,
                 vec![server_addr],
                 user_data.as_ref(),
                 &[0; NETCODE_KEY_BYTES],
@@ -107,6 +130,7 @@ impl NetcodeClient {
         };
 
         let server_addr = connect_token.server_addresses[0].expect("cannot create or deserialize a ConnectToken without a server address");
+        let connect_timeout = session_timeout(connect_token.timeout_seconds);
 
         Ok(Self {
             sequence: 0,
@@ -124,7 +148,18 @@ impl NetcodeClient {
             send_rate: NETCODE_SEND_RATE,
             challenge_token_data: [0u8; NETCODE_CHALLENGE_TOKEN_BYTES],
             connect_token,
-            replay_protection: ReplayProtection::new(),
+            replay_protection: ReplayProtection::default(),
+            replay_drops: 0,
+            keep_alive_padding: 0,
+            pending_payloads: VecDeque::new(),
+            max_pending_payloads: NETCODE_MAX_PENDING_PAYLOADS,
+            initial_request_burst: 1,
+            burst_requests_remaining: 0,
+            last_forced_keep_alive: None,
+            disconnect_confirmed: false,
+            protocol_mismatch_threshold: None,
+            consecutive_authentication_failures: 0,
+            connect_timeout,
             out: [0u8; NETCODE_MAX_PACKET_BYTES],
         })
     }
@@ -152,12 +187,204 @@ impl NetcodeClient {
         self.client_id
     }
 
+    /// Returns the number of packets from the server dropped by the replay protection,
+    /// because their sequence was already received or too old.
+    pub fn replay_drops(&self) -> u64 {
+        self.replay_drops
+    }
+
+    /// Resets the number of packets dropped by the replay protection to zero.
+    pub fn reset_replay_drops(&mut self) {
+        self.replay_drops = 0;
+    }
+
+    /// Sets the number of zeroed bytes added to keep-alive packets, for networks that reap flows with only small packets.
+    /// Panics if greater than [NETCODE_MAX_PAYLOAD_BYTES].
+    pub fn set_keep_alive_padding(&mut self, padding: usize) {
+        assert!(
+            padding <= NETCODE_MAX_PAYLOAD_BYTES,
+            "The keep alive padding cannot be greater than {}",
+            NETCODE_MAX_PAYLOAD_BYTES
+        );
+        self.keep_alive_padding = padding;
+    }
+
+    /// Returns the number of zeroed bytes added to keep-alive packets.
+    pub fn keep_alive_padding(&self) -> usize {
+        self.keep_alive_padding
+    }
+
+    /// Sets the maximum number of payload packets buffered while finishing the handshake.
+    /// Payloads can arrive before the keep-alive that completes the connection, these are kept
+    /// and returned by [NetcodeClient::take_pending_payload] once connected.
+    /// Setting it to 0 drops payloads received before the client is connected.
+    pub fn set_max_pending_payloads(&mut self, max_pending_payloads: usize) {
+        self.max_pending_payloads = max_pending_payloads;
+        self.pending_payloads.truncate(max_pending_payloads);
+    }
+
+    /// Returns the maximum number of payload packets buffered while finishing the handshake.
+    pub fn max_pending_payloads(&self) -> usize {
+        self.max_pending_payloads
+    }
+
+    /// Sets the number of connection requests sent at once when starting to connect to a server address.
+    /// On lossy links, sending a few redundant requests avoids waiting a full resend interval
+    /// when the first one is lost. The extra requests are returned by [NetcodeClient::generate_burst_packet].
+    /// Panics if zero.
+    pub fn set_initial_request_burst(&mut self, initial_request_burst: u8) {
+        assert!(initial_request_burst > 0, "The initial request burst cannot be zero");
+        self.initial_request_burst = initial_request_burst;
+    }
+
+    /// Sets the number of consecutive packets from the server that can fail authentication before the client
+    /// disconnects with [DisconnectReason::ProtocolMismatch]. The protocol id is authenticated with every packet,
+    /// so a server that changes it mid-session only sends packets that fail authentication.
+    /// Any packet authenticated resets the count. Since packets with the server address can be spoofed,
+    /// a low threshold lets an attacker disconnect the client more easily.
+    /// Panics if Some(0). None: packets that fail authentication are dropped without disconnecting. Default: None
+    pub fn set_protocol_mismatch_threshold(&mut self, threshold: Option<u32>) {
+        assert!(threshold != Some(0), "The protocol mismatch threshold cannot be zero");
+        self.protocol_mismatch_threshold = threshold;
+    }
+
+    /// Sets the time without receiving packets from a server address before trying the next one while connecting,
+    /// for example a short timeout to fail fast in matchmaking. Once connected, the client times out with the
+    /// [keep-alive timeout][NetcodeClient::keep_alive_timeout] of the connect token, the same the server uses.
+    /// Returns [NetcodeError::InvalidTimeout] if the timeout is zero. Default: the timeout of the connect token
+    pub fn set_connect_timeout(&mut self, connect_timeout: Duration) -> Result<(), NetcodeError> {
+        if connect_timeout.is_zero() {
+            return Err(NetcodeError::InvalidTimeout);
+        }
+        self.connect_timeout = connect_timeout;
+        Ok(())
+    }
+
+    /// Returns the time without receiving packets before the connection times out while connecting,
+    /// [Duration::MAX] if disabled.
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    /// Returns the time without receiving packets before the connection times out once connected,
+    /// as set in the connect token, [Duration::MAX] if the connect token disables the timeout.
+    /// The server times out the client with the same timeout, as it reads it from the connect token,
+    /// so a longer keep-alive timeout to tolerate transient outages is set when generating the token.
+    pub fn keep_alive_timeout(&self) -> Duration {
+        session_timeout(self.connect_token.timeout_seconds)
+    }
+
+    /// Returns the number of connection requests sent at once when starting to connect to a server address.
+    pub fn initial_request_burst(&self) -> u8 {
+        self.initial_request_burst
+    }
+
+    /// Returns the redundant connection requests of the initial burst, after the first one was generated by [NetcodeClient::update].
+    /// Should be called until it returns None, see [NetcodeClient::set_initial_request_burst].
+    pub fn generate_burst_packet(&mut self) -> Option<(&mut [u8], SocketAddr)> {
+        if self.state != ClientState::SendingConnectionRequest || self.burst_requests_remaining == 0 {
+            return None;
+        }
+
+        self.burst_requests_remaining -= 1;
+        let packet = Packet::connection_request_from_token(&self.connect_token);
+        match packet.encode(&mut self.out, self.connect_token.protocol_id, None) {
+            Err(_) => None,
+            Ok(encoded) => Some((&mut self.out[..encoded], self.server_addr)),
+        }
+    }
+
+    /// Returns a payload that was received during the handshake, before the client was connected.
+    /// Only returns payloads after the connection is established, should be called after processing packets.
+    pub fn take_pending_payload(&mut self) -> Option<Vec<u8>> {
+        if self.state != ClientState::Connected {
+            return None;
+        }
+
+        self.pending_payloads.pop_front()
+    }
+
     /// Returns the duration since the client last received a packet.
     /// Usefull to detect timeouts.
     pub fn time_since_last_received_packet(&self) -> Duration {
         self.current_time - self.last_packet_received_time
     }
 
+    /// Returns the time until the client sends a keep-alive packet, or None if it's not connected.
+    /// Keep-alives are only sent when no other packet was sent in the last 250 milliseconds,
+    /// so sending a payload postpones it. It's sent in the first update after this time has elapsed,
+    /// returns [Duration::ZERO] if it's due in the next update.
+    pub fn time_until_next_keep_alive(&self) -> Option<Duration> {
+        if self.state != ClientState::Connected {
+            return None;
+        }
+
+        let time_until = match self.last_packet_send_time {
+            Some(last_packet_send_time) => (last_packet_send_time + self.send_rate).saturating_sub(self.current_time),
+            None => Duration::ZERO,
+        };
+        Some(time_until)
+    }
+
+    /// Generates a keep-alive packet out of the regular schedule, for example to refresh a NAT mapping
+    /// that may have expired while the application was in the background.
+    /// Returns None if the client is not connected, or if the last forced keep-alive was generated
+    /// less than [NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL] ago.
+    pub fn generate_keep_alive_now(&mut self) -> Option<(SocketAddr, &mut [u8])> {
+        if self.state != ClientState::Connected {
+            return None;
+        }
+        if let Some(last_forced_keep_alive) = self.last_forced_keep_alive {
+            if self.current_time - last_forced_keep_alive < NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL {
+                return None;
+            }
+        }
+
+        let packet = Packet::KeepAlive {
+            client_index: 0,
+            max_clients: 0,
+        };
+        let result = packet.encode_with_padding(
+            &mut self.out,
+            self.connect_token.protocol_id,
+            Some((self.sequence, &self.connect_token.client_to_server_key)),
+            self.keep_alive_padding,
+        );
+        match result {
+            Err(_) => None,
+            Ok(encoded) => {
+                self.sequence += 1;
+                self.last_forced_keep_alive = Some(self.current_time);
+                self.last_packet_send_time = Some(self.current_time);
+                Some((self.server_addr, &mut self.out[..encoded]))
+            }
+        }
+    }
+
+    /// Returns the time without receiving packets before the connection times out in the current state,
+    /// the [connect timeout][NetcodeClient::connect_timeout] while connecting and the
+    /// [keep-alive timeout][NetcodeClient::keep_alive_timeout] of the connect token otherwise.
+    /// Returns [Duration::MAX] if the timeout is disabled.
+    pub fn session_timeout(&self) -> Duration {
+        match self.is_connecting() {
+            true => self.connect_timeout,
+            false => self.keep_alive_timeout(),
+        }
+    }
+
+    /// Returns the time left without receiving packets before the connection times out,
+    /// computed from [NetcodeClient::time_since_last_received_packet] and [NetcodeClient::session_timeout].
+    /// The client disconnects in the first update after it reaches [Duration::ZERO].
+    /// While connecting, it's the time until the current server address times out.
+    /// Returns None if the client is disconnected or the timeout is disabled.
+    pub fn time_until_timeout(&self) -> Option<Duration> {
+        if self.is_disconnected() || self.session_timeout() == Duration::MAX {
+            return None;
+        }
+
+        Some(self.session_timeout().saturating_sub(self.time_since_last_received_packet()))
+    }
+
     /// Returns the reason that the client was disconnected for.
     pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
         if let ClientState::Disconnected(reason) = &self.state {
@@ -171,6 +398,38 @@ impl NetcodeClient {
         self.server_addr
     }
 
+    /// Returns the server addresses of the connect token, in the order they are tried when connecting.
+    pub fn server_addresses(&self) -> Vec<SocketAddr> {
+        self.connect_token.server_addresses.iter().filter_map(|addr| *addr).collect()
+    }
+
+    /// Returns an unauthenticated probe packet with the given token, to be sent to a server address
+    /// to measure its reachability without establishing a session.
+    /// Only servers with [ServerConfig::respond_to_probes][crate::ServerConfig::respond_to_probes] reply to it,
+    /// use [NetcodeClient::process_probe_reply] to read the token of the reply.
+    pub fn generate_probe_packet(&mut self, token: u64) -> Result<&mut [u8], NetcodeError> {
+        let packet = Packet::probe(self.connect_token.protocol_id, token);
+        let len = packet.encode(&mut self.out, self.connect_token.protocol_id, None)?;
+
+        Ok(&mut self.out[..len])
+    }
+
+    /// Returns the token of a probe reply, or None if the packet is not a probe reply for this protocol.
+    /// The reply is not authenticated, so it should only be used for diagnostics.
+    pub fn process_probe_reply(&self, buffer: &mut [u8]) -> Option<u64> {
+        match Packet::decode(buffer, self.connect_token.protocol_id, None, None) {
+            Ok((
+                _,
+                Packet::ProbeReply {
+                    version_info,
+                    protocol_id,
+                    token,
+                },
+            )) if version_info == *NETCODE_VERSION_INFO && protocol_id == self.connect_token.protocol_id => Some(token),
+            _ => None,
+        }
+    }
+
     /// Disconnect the client from the server.
     /// Returns a disconnect packet that should be sent to the server.
     pub fn disconnect(&mut self) -> Result<(SocketAddr, &mut [u8]), NetcodeError> {
@@ -181,10 +440,18 @@ impl NetcodeClient {
             self.connect_token.protocol_id,
             Some((self.sequence, &self.connect_token.client_to_server_key)),
         )?;
+        // Each retry has a new sequence, so it's not dropped by the replay protection of the server
+        self.sequence += 1;
 
         Ok((self.server_addr, &mut self.out[..len]))
     }
 
+    /// Returns true if the server confirmed the disconnect requested with [NetcodeClient::disconnect].
+    /// The disconnect packet can be lost, call [NetcodeClient::disconnect] again to retry it until confirmed.
+    pub fn disconnect_confirmed(&self) -> bool {
+        self.disconnect_confirmed
+    }
+
     /// Process any packet received from the server. This function might return a payload sent from the
     /// server. If nothing is returned, it was a packet used for the internal protocol or an
     /// invalid packet.
@@ -196,18 +463,42 @@ impl NetcodeClient {
             Some(&mut self.replay_protection),
         ) {
             Ok((_, packet)) => packet,
+            Err(NetcodeError::DuplicatedSequence) => {
+                self.replay_drops += 1;
+                log::debug!("Dropped packet from server with duplicated or too old sequence");
+                return None;
+            }
+            Err(NetcodeError::CryptoError) if !self.is_disconnected() => {
+                log::debug!("Dropped packet from server that failed authentication");
+                self.consecutive_authentication_failures += 1;
+                if let Some(threshold) = self.protocol_mismatch_threshold {
+                    if self.consecutive_authentication_failures >= threshold {
+                        log::error!("Disconnected after {threshold} packets from the server failed authentication");
+                        self.state = ClientState::Disconnected(DisconnectReason::ProtocolMismatch);
+                    }
+                }
+                return None;
+            }
             Err(e) => {
                 log::error!("Failed to decode packet: {}", e);
                 return None;
             }
         };
         log::trace!("Received packet from server: {:?}", packet.packet_type());
+        // Unencrypted packets can be sent by anyone, only authenticated ones show the protocol matches
+        if packet.packet_type().is_encrypted() {
+            self.consecutive_authentication_failures = 0;
+        }
 
         match (packet, &self.state) {
             (Packet::ConnectionDenied, ClientState::SendingConnectionRequest | ClientState::SendingConnectionResponse) => {
                 self.state = ClientState::Disconnected(DisconnectReason::ConnectionDenied);
                 self.last_packet_received_time = self.current_time;
             }
+            (Packet::ConnectTokenAlreadyUsed, ClientState::SendingConnectionRequest | ClientState::SendingConnectionResponse) => {
+                self.state = ClientState::Disconnected(DisconnectReason::ConnectTokenAlreadyUsed);
+                self.last_packet_received_time = self.current_time;
+            }
             (
                 Packet::Challenge {
                     token_data,
@@ -234,10 +525,21 @@ impl NetcodeClient {
                 self.last_packet_received_time = self.current_time;
                 return Some(p);
             }
+            (Packet::Payload(p), ClientState::SendingConnectionResponse) => {
+                if self.pending_payloads.len() < self.max_pending_payloads {
+                    self.pending_payloads.push_back(p.to_vec());
+                } else {
+                    log::debug!("Dropped payload received before the connection was established");
+                }
+            }
             (Packet::Disconnect, ClientState::Connected) => {
                 self.state = ClientState::Disconnected(DisconnectReason::DisconnectedByServer);
                 self.last_packet_received_time = self.current_time;
             }
+            (Packet::Disconnect, ClientState::Disconnected(DisconnectReason::DisconnectedByClient)) => {
+                self.disconnect_confirmed = true;
+                self.last_packet_received_time = self.current_time;
+            }
             _ => {}
         }
 
@@ -280,8 +582,7 @@ impl NetcodeClient {
 
     fn update_internal_state(&mut self, duration: Duration) -> Result<(), NetcodeError> {
         self.current_time += duration;
-        let connection_timed_out = self.connect_token.timeout_seconds > 0
-            && (self.last_packet_received_time + Duration::from_secs(self.connect_token.timeout_seconds as u64) < self.current_time);
+        let connection_timed_out = self.current_time - self.last_packet_received_time > self.session_timeout();
 
         match self.state {
             ClientState::SendingConnectionRequest | ClientState::SendingConnectionResponse => {
@@ -298,6 +599,7 @@ impl NetcodeClient {
                         DisconnectReason::ConnectionRequestTimedOut
                     };
                     self.state = ClientState::Disconnected(reason);
+                    self.pending_payloads.clear();
                     // Try to connect to the next server address
                     self.server_addr_index += 1;
                     if self.server_addr_index >= 32 {
@@ -338,6 +640,11 @@ impl NetcodeClient {
             }
         }
 
+        if self.state == ClientState::SendingConnectionRequest && self.last_packet_send_time.is_none() {
+            // First request to this server address
+            self.burst_requests_remaining = self.initial_request_burst - 1;
+        }
+
         if matches!(
             self.state,
             ClientState::Connected | ClientState::SendingConnectionRequest | ClientState::SendingConnectionResponse
@@ -357,10 +664,15 @@ impl NetcodeClient {
             _ => return None,
         };
 
-        let result = packet.encode(
+        let padding = match packet {
+            Packet::KeepAlive { .. } => self.keep_alive_padding,
+            _ => 0,
+        };
+        let result = packet.encode_with_padding(
             &mut self.out,
             self.connect_token.protocol_id,
             Some((self.sequence, &self.connect_token.client_to_server_key)),
+            padding,
         );
         match result {
             Err(_) => None,
@@ -444,4 +756,334 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn connection_denied_during_handshake() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let protocol_id = 2;
+        let timeout_seconds = 5;
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            protocol_id,
+            3,
+            4,
+            timeout_seconds,
+            server_addresses,
+            None,
+            private_key,
+        )
+        .unwrap();
+        let server_key = connect_token.server_to_client_key;
+        let authentication = ClientAuthentication::Secure { connect_token };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+
+        let (packet_buffer, _) = client.update(Duration::ZERO).unwrap();
+        let (_, packet) = Packet::decode(packet_buffer, protocol_id, None, None).unwrap();
+        assert!(matches!(packet, Packet::ConnectionRequest { .. }));
+
+        // Server denies the first connection request
+        let len = Packet::ConnectionDenied
+            .encode(&mut buffer, protocol_id, Some((0, &server_key)))
+            .unwrap();
+        client.process_packet(&mut buffer[..len]);
+
+        // Surfaced immediately, without waiting for the timeout
+        assert!(client.is_disconnected());
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ConnectionDenied));
+        assert!(client.update(Duration::from_millis(1)).is_none());
+
+        // The timeout does not overwrite the reason
+        assert!(client.update(Duration::from_secs(timeout_seconds as u64 + 1)).is_none());
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ConnectionDenied));
+    }
+
+    #[test]
+    fn payload_received_before_connected() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let protocol_id = 2;
+        let client_id = 4;
+        let connect_token =
+            ConnectToken::generate(Duration::ZERO, protocol_id, 3, client_id, 5, server_addresses, None, private_key).unwrap();
+        let server_key = connect_token.server_to_client_key;
+        let authentication = ClientAuthentication::Secure { connect_token };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+        client.update(Duration::ZERO).unwrap();
+
+        let challenge_key = generate_random_bytes();
+        let challenge_packet = Packet::generate_challenge(client_id, &[0u8; NETCODE_USER_DATA_BYTES], 0, &challenge_key).unwrap();
+        let len = challenge_packet.encode(&mut buffer, protocol_id, Some((0, &server_key))).unwrap();
+        client.process_packet(&mut buffer[..len]);
+        assert_eq!(ClientState::SendingConnectionResponse, client.state);
+
+        // The server starts sending data in the same tick it accepts the client,
+        // the payload arrives before the keep-alive that completes the handshake.
+        let payload = vec![7u8; 100];
+        let len = Packet::Payload(&payload)
+            .encode(&mut buffer, protocol_id, Some((2, &server_key)))
+            .unwrap();
+        assert!(client.process_packet(&mut buffer[..len]).is_none());
+        assert!(client.take_pending_payload().is_none());
+
+        let keep_alive_packet = Packet::KeepAlive {
+            max_clients: 4,
+            client_index: 0,
+        };
+        let len = keep_alive_packet.encode(&mut buffer, protocol_id, Some((1, &server_key))).unwrap();
+        client.process_packet(&mut buffer[..len]);
+        assert!(client.is_connected());
+
+        assert_eq!(client.take_pending_payload().unwrap(), payload);
+        assert!(client.take_pending_payload().is_none());
+    }
+
+    #[test]
+    fn initial_request_burst() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 3, 4, 5, server_addresses, None, private_key).unwrap();
+        let authentication = ClientAuthentication::Secure { connect_token };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+        client.set_initial_request_burst(3);
+        assert!(client.generate_burst_packet().is_none());
+
+        let request = client.update(Duration::ZERO).unwrap().0.to_vec();
+        assert_eq!(client.generate_burst_packet().unwrap().0, &request[..]);
+        assert_eq!(client.generate_burst_packet().unwrap().0, &request[..]);
+        assert!(client.generate_burst_packet().is_none());
+
+        // Only the first request is sent as a burst
+        assert!(client.update(NETCODE_SEND_RATE).is_some());
+        assert!(client.generate_burst_packet().is_none());
+    }
+
+    #[test]
+    fn time_until_next_keep_alive() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let protocol_id = 2;
+        let client_id = 4;
+        let connect_token =
+            ConnectToken::generate(Duration::ZERO, protocol_id, 3, client_id, 5, server_addresses, None, private_key).unwrap();
+        let server_key = connect_token.server_to_client_key;
+        let authentication = ClientAuthentication::Secure { connect_token };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+        assert_eq!(client.time_until_next_keep_alive(), None);
+
+        client.state = ClientState::SendingConnectionResponse;
+        let keep_alive_packet = Packet::KeepAlive {
+            max_clients: 4,
+            client_index: 0,
+        };
+        let len = keep_alive_packet.encode(&mut buffer, protocol_id, Some((0, &server_key))).unwrap();
+        client.process_packet(&mut buffer[..len]);
+        assert!(client.is_connected());
+
+        client.update(Duration::ZERO).unwrap();
+        assert_eq!(client.time_until_next_keep_alive(), Some(NETCODE_SEND_RATE));
+
+        client.update(Duration::from_millis(10));
+        assert_eq!(
+            client.time_until_next_keep_alive(),
+            Some(NETCODE_SEND_RATE - Duration::from_millis(10))
+        );
+
+        // Sending a payload postpones the keep-alive
+        client.generate_payload_packet(&[1, 2, 3]).unwrap();
+        assert_eq!(client.time_until_next_keep_alive(), Some(NETCODE_SEND_RATE));
+    }
+
+    #[test]
+    fn generate_keep_alive_now() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let protocol_id = 2;
+        let connect_token = ConnectToken::generate(Duration::ZERO, protocol_id, 3, 4, 5, server_addresses, None, private_key).unwrap();
+        let server_key = connect_token.server_to_client_key;
+        let authentication = ClientAuthentication::Secure { connect_token };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+        assert!(client.generate_keep_alive_now().is_none());
+
+        client.state = ClientState::SendingConnectionResponse;
+        let keep_alive_packet = Packet::KeepAlive {
+            max_clients: 4,
+            client_index: 0,
+        };
+        let len = keep_alive_packet.encode(&mut buffer, protocol_id, Some((0, &server_key))).unwrap();
+        client.process_packet(&mut buffer[..len]);
+        assert!(client.is_connected());
+
+        assert!(client.generate_keep_alive_now().is_some());
+        // Postpones the scheduled keep-alive
+        assert_eq!(client.time_until_next_keep_alive(), Some(NETCODE_SEND_RATE));
+        // Limited by the minimum interval
+        assert!(client.generate_keep_alive_now().is_none());
+        client.update(NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL);
+        assert!(client.generate_keep_alive_now().is_some());
+    }
+
+    #[test]
+    fn session_timeout() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 3, 4, 5, server_addresses.clone(), None, private_key).unwrap();
+        let client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert_eq!(client.session_timeout(), Duration::from_secs(5));
+
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 3, 4, -1, server_addresses, None, private_key).unwrap();
+        let client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert_eq!(client.session_timeout(), Duration::MAX);
+
+        let authentication = ClientAuthentication::Unsecure {
+            protocol_id: 2,
+            client_id: 4,
+            server_addr: "127.0.0.1:8080".parse().unwrap(),
+            user_data: None,
+        };
+        let client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+        assert_eq!(client.session_timeout(), Duration::from_secs(NETCODE_TIMEOUT_SECONDS as u64));
+    }
+
+    #[test]
+    fn protocol_mismatch() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let protocol_id = 2;
+        let connect_token = ConnectToken::generate(Duration::ZERO, protocol_id, 3, 4, 5, server_addresses, None, private_key).unwrap();
+        let server_key = connect_token.server_to_client_key;
+        let authentication = ClientAuthentication::Secure { connect_token };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication).unwrap();
+        client.set_protocol_mismatch_threshold(Some(3));
+
+        client.state = ClientState::SendingConnectionResponse;
+        let keep_alive_packet = Packet::KeepAlive {
+            max_clients: 4,
+            client_index: 0,
+        };
+        let len = keep_alive_packet.encode(&mut buffer, protocol_id, Some((0, &server_key))).unwrap();
+        client.process_packet(&mut buffer[..len]);
+        assert!(client.is_connected());
+
+        // The server changes its protocol mid-session
+        let mut sequence = 1;
+        for _ in 0..2 {
+            let len = Packet::Payload(&[1; 10])
+                .encode(&mut buffer, protocol_id + 1, Some((sequence, &server_key)))
+                .unwrap();
+            sequence += 1;
+            assert!(client.process_packet(&mut buffer[..len]).is_none());
+        }
+        assert!(client.is_connected());
+
+        // Authenticated packets reset the count
+        let len = Packet::Payload(&[1; 10])
+            .encode(&mut buffer, protocol_id, Some((sequence, &server_key)))
+            .unwrap();
+        sequence += 1;
+        assert!(client.process_packet(&mut buffer[..len]).is_some());
+
+        for i in 0..3 {
+            let len = Packet::Payload(&[1; 10])
+                .encode(&mut buffer, protocol_id + 1, Some((sequence, &server_key)))
+                .unwrap();
+            sequence += 1;
+            assert!(client.process_packet(&mut buffer[..len]).is_none());
+
+            // Unauthenticated packets don't reset the count
+            if i == 0 {
+                let probe_reply = Packet::ProbeReply {
+                    version_info: *NETCODE_VERSION_INFO,
+                    protocol_id,
+                    token: 0,
+                };
+                let len = probe_reply.encode(&mut buffer, protocol_id, None).unwrap();
+                assert!(client.process_packet(&mut buffer[..len]).is_none());
+            }
+        }
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ProtocolMismatch));
+    }
+
+    #[test]
+    fn time_until_timeout() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 30, 4, 5, server_addresses.clone(), None, private_key).unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert_eq!(client.time_until_timeout(), Some(Duration::from_secs(5)));
+
+        client.update(Duration::from_secs(2));
+        assert_eq!(client.time_until_timeout(), Some(Duration::from_secs(3)));
+
+        client.update(Duration::from_secs(3));
+        assert_eq!(client.time_until_timeout(), Some(Duration::ZERO));
+
+        client.update(Duration::from_millis(1));
+        assert!(client.is_disconnected());
+        assert_eq!(client.time_until_timeout(), None);
+
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 30, 4, -1, server_addresses, None, private_key).unwrap();
+        let client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert_eq!(client.time_until_timeout(), None);
+    }
+
+    #[test]
+    fn separate_timeouts() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let protocol_id = 2;
+        // The keep-alive timeout is set in the connect token, shared with the server
+        let connect_token =
+            ConnectToken::generate(Duration::ZERO, protocol_id, 30, 4, 10, server_addresses.clone(), None, private_key).unwrap();
+        let server_key = connect_token.server_to_client_key;
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert_eq!(client.connect_timeout(), Duration::from_secs(10));
+        assert_eq!(client.keep_alive_timeout(), Duration::from_secs(10));
+
+        client.set_connect_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(client.session_timeout(), Duration::from_secs(1));
+
+        client.state = ClientState::SendingConnectionResponse;
+        let keep_alive_packet = Packet::KeepAlive {
+            max_clients: 4,
+            client_index: 0,
+        };
+        let len = keep_alive_packet.encode(&mut buffer, protocol_id, Some((0, &server_key))).unwrap();
+        client.process_packet(&mut buffer[..len]);
+        assert!(client.is_connected());
+        assert_eq!(client.session_timeout(), Duration::from_secs(10));
+
+        // Longer than the connect timeout
+        client.update(Duration::from_secs(8));
+        assert!(client.is_connected());
+        assert_eq!(client.time_until_timeout(), Some(Duration::from_secs(2)));
+        client.update(Duration::from_secs(3));
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ConnectionTimedOut));
+
+        // The connect timeout fails fast
+        let connect_token = ConnectToken::generate(Duration::ZERO, protocol_id, 30, 4, 10, server_addresses, None, private_key).unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        client.set_connect_timeout(Duration::from_secs(1)).unwrap();
+        client.update(Duration::from_millis(1500));
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ConnectionRequestTimedOut));
+    }
+
+    #[test]
+    fn zero_connect_timeout() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 30, 4, 5, server_addresses, None, private_key).unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert!(matches!(
+            client.set_connect_timeout(Duration::ZERO),
+            Err(NetcodeError::InvalidTimeout)
+        ));
+        assert_eq!(client.connect_timeout(), Duration::from_secs(5));
+    }
 }
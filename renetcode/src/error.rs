@@ -38,6 +38,8 @@ pub enum NetcodeError {
     IoError(io::Error),
     /// An error occured while generating the connect token.
     TokenGenerationError(TokenGenerationError),
+    /// The timeout is zero.
+    InvalidTimeout,
 }
 
 impl fmt::Display for NetcodeError {
@@ -61,6 +63,7 @@ impl fmt::Display for NetcodeError {
             ClientNotConnected => write!(fmt, "client is disconnected or connecting"),
             IoError(ref err) => write!(fmt, "{}", err),
             TokenGenerationError(ref err) => write!(fmt, "{}", err),
+            InvalidTimeout => write!(fmt, "timeout must be positive"),
         }
     }
 }
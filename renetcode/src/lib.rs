@@ -28,7 +28,9 @@ mod token;
 pub use client::{ClientAuthentication, DisconnectReason, NetcodeClient};
 pub use crypto::generate_random_bytes;
 pub use error::NetcodeError;
-pub use server::{NetcodeServer, ServerAuthentication, ServerConfig, ServerResult};
+pub use server::{ConnectTokenReusePolicy, NetcodeServer, ServerAuthentication, ServerConfig, ServerResult};
+#[cfg(feature = "test-util")]
+pub use token::ConnectTokenRandomness;
 pub use token::{ConnectToken, TokenGenerationError};
 
 use std::time::Duration;
@@ -61,4 +63,31 @@ const NETCODE_ADDITIONAL_DATA_SIZE: usize = 13 + 8 + 8;
 
 const NETCODE_TIMEOUT_SECONDS: i32 = 15;
 
+/// The default number of packet sequences tracked by the replay protection, see
+/// [ServerConfig::replay_protection_window].
+pub const NETCODE_REPLAY_PROTECTION_WINDOW: usize = 256;
+
+/// The maximum number of packet sequences tracked by the replay protection, each connection allocates
+/// 8 bytes per tracked sequence, see [ServerConfig::replay_protection_window].
+pub const NETCODE_MAX_REPLAY_PROTECTION_WINDOW: usize = 4096;
+
+/// The default number of payload packets buffered while the client finishes the handshake, see
+/// [NetcodeClient::set_max_pending_payloads].
+pub const NETCODE_MAX_PENDING_PAYLOADS: usize = 16;
+
+/// Minimum time between keep-alive packets sent out of schedule, see [NetcodeClient::generate_keep_alive_now].
+pub const NETCODE_MIN_FORCED_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
 const NETCODE_SEND_RATE: Duration = Duration::from_millis(250);
+
+// Time the server keeps the keys of a disconnected client to confirm retried disconnect packets
+const NETCODE_DISCONNECT_CONFIRM_WINDOW: Duration = Duration::from_secs(10);
+
+// Timeouts of zero or less disable the timeout of the connection
+fn session_timeout(timeout_seconds: i32) -> Duration {
+    if timeout_seconds > 0 {
+        Duration::from_secs(timeout_seconds as u64)
+    } else {
+        Duration::MAX
+    }
+}
@@ -19,6 +19,9 @@ pub enum PacketType {
     KeepAlive = 4,
     Payload = 5,
     Disconnect = 6,
+    Probe = 7,
+    ProbeReply = 8,
+    ConnectTokenAlreadyUsed = 9,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -46,6 +49,19 @@ pub enum Packet<'a> {
     },
     Payload(&'a [u8]),
     Disconnect,
+    // Unauthenticated packets to measure reachability, the reply has the same size as the request.
+    Probe {
+        version_info: [u8; 13],
+        protocol_id: u64,
+        token: u64,
+    },
+    ProbeReply {
+        version_info: [u8; 13],
+        protocol_id: u64,
+        token: u64,
+    },
+    // Denies a connection request with a connect token already used from another address.
+    ConnectTokenAlreadyUsed,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -66,6 +82,9 @@ impl PacketType {
             4 => KeepAlive,
             5 => Payload,
             6 => Disconnect,
+            7 => Probe,
+            8 => ProbeReply,
+            9 => ConnectTokenAlreadyUsed,
             _ => return Err(NetcodeError::InvalidPacketType),
         };
         Ok(packet_type)
@@ -76,6 +95,12 @@ impl PacketType {
 
         matches!(self, KeepAlive | Payload | Disconnect)
     }
+
+    pub(crate) fn is_encrypted(&self) -> bool {
+        use PacketType::*;
+
+        !matches!(self, ConnectionRequest | Probe | ProbeReply)
+    }
 }
 
 impl<'a> Packet<'a> {
@@ -88,6 +113,9 @@ impl<'a> Packet<'a> {
             Packet::KeepAlive { .. } => PacketType::KeepAlive,
             Packet::Payload { .. } => PacketType::Payload,
             Packet::Disconnect => PacketType::Disconnect,
+            Packet::Probe { .. } => PacketType::Probe,
+            Packet::ProbeReply { .. } => PacketType::ProbeReply,
+            Packet::ConnectTokenAlreadyUsed => PacketType::ConnectTokenAlreadyUsed,
         }
     }
 
@@ -95,6 +123,15 @@ impl<'a> Packet<'a> {
         self.packet_type() as u8
     }
 
+    /// Returns the type of an encoded packet, without decoding it.
+    pub fn peek_type(buffer: &[u8]) -> Result<PacketType, NetcodeError> {
+        let Some(&prefix_byte) = buffer.first() else {
+            return Err(NetcodeError::PacketTooSmall);
+        };
+        let (packet_type, _) = decode_prefix(prefix_byte);
+        PacketType::from_u8(packet_type)
+    }
+
     pub fn connection_request_from_token(connect_token: &ConnectToken) -> Self {
         Packet::ConnectionRequest {
             xnonce: connect_token.xnonce,
@@ -105,6 +142,14 @@ impl<'a> Packet<'a> {
         }
     }
 
+    pub fn probe(protocol_id: u64, token: u64) -> Self {
+        Packet::Probe {
+            version_info: *NETCODE_VERSION_INFO,
+            protocol_id,
+            token,
+        }
+    }
+
     pub fn generate_challenge(
         client_id: u64,
         user_data: &[u8; NETCODE_USER_DATA_BYTES],
@@ -155,7 +200,21 @@ impl<'a> Packet<'a> {
             Packet::Payload(p) => {
                 writer.write_all(p)?;
             }
-            Packet::ConnectionDenied | Packet::Disconnect => {}
+            Packet::Probe {
+                version_info,
+                protocol_id,
+                token,
+            }
+            | Packet::ProbeReply {
+                version_info,
+                protocol_id,
+                token,
+            } => {
+                writer.write_all(version_info)?;
+                writer.write_all(&protocol_id.to_le_bytes())?;
+                writer.write_all(&token.to_le_bytes())?;
+            }
+            Packet::ConnectionDenied | Packet::Disconnect | Packet::ConnectTokenAlreadyUsed => {}
         }
 
         Ok(())
@@ -208,14 +267,46 @@ impl<'a> Packet<'a> {
 
                 Ok(Packet::KeepAlive { client_index, max_clients })
             }
+            PacketType::Probe | PacketType::ProbeReply => {
+                let version_info = read_bytes(src)?;
+                let protocol_id = read_u64(src)?;
+                let token = read_u64(src)?;
+
+                if matches!(packet_type, PacketType::Probe) {
+                    Ok(Packet::Probe {
+                        version_info,
+                        protocol_id,
+                        token,
+                    })
+                } else {
+                    Ok(Packet::ProbeReply {
+                        version_info,
+                        protocol_id,
+                        token,
+                    })
+                }
+            }
             PacketType::ConnectionDenied => Ok(Packet::ConnectionDenied),
             PacketType::Disconnect => Ok(Packet::Disconnect),
+            PacketType::ConnectTokenAlreadyUsed => Ok(Packet::ConnectTokenAlreadyUsed),
             PacketType::Payload => unreachable!(),
         }
     }
 
     pub fn encode(&self, buffer: &mut [u8], protocol_id: u64, crypto_info: Option<(u64, &[u8; 32])>) -> Result<usize, NetcodeError> {
-        if matches!(self, Packet::ConnectionRequest { .. }) {
+        self.encode_with_padding(buffer, protocol_id, crypto_info, 0)
+    }
+
+    /// Encodes the packet followed by `padding` zeroed bytes, they are encrypted with the packet
+    /// and ignored when decoding. Connection requests and probes are not encrypted nor padded.
+    pub fn encode_with_padding(
+        &self,
+        buffer: &mut [u8],
+        protocol_id: u64,
+        crypto_info: Option<(u64, &[u8; 32])>,
+        padding: usize,
+    ) -> Result<usize, NetcodeError> {
+        if !self.packet_type().is_encrypted() {
             let mut writer = io::Cursor::new(buffer);
             let prefix_byte = encode_prefix(self.id(), 0);
             writer.write_all(&prefix_byte.to_le_bytes())?;
@@ -234,6 +325,9 @@ impl<'a> Packet<'a> {
 
                 let start = writer.position() as usize;
                 self.write(&mut writer)?;
+                for _ in 0..padding {
+                    writer.write_all(&[0])?;
+                }
 
                 let additional_data = get_additional_data(prefix_byte, protocol_id);
                 (start, writer.position() as usize, additional_data)
@@ -266,8 +360,8 @@ impl<'a> Packet<'a> {
         let (packet_type, sequence_len) = decode_prefix(prefix_byte);
         let packet_type = PacketType::from_u8(packet_type)?;
 
-        if matches!(packet_type, PacketType::ConnectionRequest) {
-            Ok((0, Packet::read(PacketType::ConnectionRequest, &buffer[1..])?))
+        if !packet_type.is_encrypted() {
+            Ok((0, Packet::read(packet_type, &buffer[1..])?))
         } else if let Some(private_key) = private_key {
             let (sequence, aad, read_pos) = {
                 let src = &mut io::Cursor::new(&mut buffer);
@@ -361,7 +455,8 @@ fn sequence_bytes_required(sequence: u64) -> usize {
         mask >>= 8;
     }
 
-    0
+    // Always write at least one byte, otherwise empty packets with sequence 0 are smaller than the minimum packet size
+    1
 }
 
 fn write_sequence(out: &mut impl io::Write, seq: u64) -> Result<usize, io::Error> {
@@ -470,6 +565,19 @@ mod tests {
         assert_eq!(packet, d_packet);
     }
 
+    #[test]
+    fn encrypt_decrypt_token_already_used_packet() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let key = b"an example very very secret key."; // 32-bytes
+        let packet = Packet::ConnectTokenAlreadyUsed;
+        let protocol_id = 12;
+        let sequence = 2;
+        let len = packet.encode(&mut buffer, protocol_id, Some((sequence, key))).unwrap();
+        let (d_sequence, d_packet) = Packet::decode(&mut buffer[..len], protocol_id, Some(key), None).unwrap();
+        assert_eq!(sequence, d_sequence);
+        assert_eq!(packet, d_packet);
+    }
+
     #[test]
     fn encrypt_decrypt_payload_packet() {
         let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
@@ -508,4 +616,44 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn encrypt_decrypt_padded_keep_alive_packet() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let key = b"an example very very secret key."; // 32-bytes
+        let packet = Packet::KeepAlive {
+            client_index: 2,
+            max_clients: 4,
+        };
+        let protocol_id = 12;
+        let sequence = 2;
+        let unpadded_len = packet.encode(&mut buffer, protocol_id, Some((sequence, key))).unwrap();
+        let len = packet
+            .encode_with_padding(&mut buffer, protocol_id, Some((sequence, key)), 100)
+            .unwrap();
+        assert_eq!(len, unpadded_len + 100);
+
+        let (d_sequence, d_packet) = Packet::decode(&mut buffer[..len], protocol_id, Some(key), None).unwrap();
+        assert_eq!(sequence, d_sequence);
+        assert_eq!(packet, d_packet);
+    }
+
+    #[test]
+    fn probe_serialization() {
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let packet = Packet::probe(12, 99);
+        let len = packet.encode(&mut buffer, 12, None).unwrap();
+        let (_, d_packet) = Packet::decode(&mut buffer[..len], 12, None, None).unwrap();
+        assert_eq!(packet, d_packet);
+
+        let reply = Packet::ProbeReply {
+            version_info: *NETCODE_VERSION_INFO,
+            protocol_id: 12,
+            token: 99,
+        };
+        let reply_len = reply.encode(&mut buffer, 12, None).unwrap();
+        assert_eq!(len, reply_len);
+        let (_, d_reply) = Packet::decode(&mut buffer[..reply_len], 12, None, None).unwrap();
+        assert_eq!(reply, d_reply);
+    }
 }
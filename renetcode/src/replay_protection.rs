@@ -1,32 +1,35 @@
-const NETCODE_REPLAY_BUFFER_SIZE: usize = 256;
+use crate::NETCODE_REPLAY_PROTECTION_WINDOW;
+
 const EMPTY: u64 = u64::MAX;
 
 #[derive(Debug, Clone)]
 pub struct ReplayProtection {
     most_recent_sequence: u64,
-    received_packet: [u64; NETCODE_REPLAY_BUFFER_SIZE],
+    received_packet: Box<[u64]>,
 }
 
 impl Default for ReplayProtection {
     fn default() -> Self {
-        Self::new()
+        Self::new(NETCODE_REPLAY_PROTECTION_WINDOW)
     }
 }
 
 impl ReplayProtection {
-    pub fn new() -> Self {
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "The replay protection window cannot be zero");
         Self {
             most_recent_sequence: 0,
-            received_packet: [EMPTY; NETCODE_REPLAY_BUFFER_SIZE],
+            received_packet: vec![EMPTY; window].into_boxed_slice(),
         }
     }
 
     pub fn already_received(&self, sequence: u64) -> bool {
-        if sequence + NETCODE_REPLAY_BUFFER_SIZE as u64 <= self.most_recent_sequence {
+        let window = self.received_packet.len();
+        if sequence + window as u64 <= self.most_recent_sequence {
             return true;
         }
 
-        let index = sequence as usize % NETCODE_REPLAY_BUFFER_SIZE;
+        let index = sequence as usize % window;
         if self.received_packet[index] == EMPTY {
             return false;
         }
@@ -43,7 +46,7 @@ impl ReplayProtection {
             self.most_recent_sequence = sequence;
         }
 
-        let index = sequence as usize % NETCODE_REPLAY_BUFFER_SIZE;
+        let index = sequence as usize % self.received_packet.len();
         self.received_packet[index] = sequence;
     }
 }
@@ -54,11 +57,11 @@ mod tests {
 
     #[test]
     fn replay_protection() {
-        let mut replay_protection = ReplayProtection::new();
+        let mut replay_protection = ReplayProtection::default();
         assert_eq!(replay_protection.most_recent_sequence, 0);
 
         // New packets aren't already received
-        let max_sequence = (NETCODE_REPLAY_BUFFER_SIZE * 4) as u64;
+        let max_sequence = (NETCODE_REPLAY_PROTECTION_WINDOW * 4) as u64;
         for i in 0..max_sequence {
             assert!(!replay_protection.already_received(i));
             replay_protection.advance_sequence(i);
@@ -73,11 +76,26 @@ mod tests {
         }
 
         // Jumping to a higher sequence should be considered not already received
-        assert!(!replay_protection.already_received(max_sequence + NETCODE_REPLAY_BUFFER_SIZE as u64));
+        assert!(!replay_protection.already_received(max_sequence + NETCODE_REPLAY_PROTECTION_WINDOW as u64));
 
         // Old packets should be considered received
         for i in 0..max_sequence {
             assert!(replay_protection.already_received(i));
         }
     }
+
+    #[test]
+    fn replay_protection_window() {
+        let mut narrow = ReplayProtection::new(8);
+        let mut wide = ReplayProtection::new(64);
+        // Packet 16 is delayed and arrives after all the others
+        for i in (0..32).filter(|i| *i != 16) {
+            narrow.advance_sequence(i);
+            wide.advance_sequence(i);
+        }
+
+        // The late packet is outside the narrow window, but still inside the wide one
+        assert!(narrow.already_received(16));
+        assert!(!wide.already_received(16));
+    }
 }
@@ -2,11 +2,13 @@ use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
 use crate::{
     crypto::generate_random_bytes,
-    packet::{ChallengeToken, Packet},
+    packet::{ChallengeToken, Packet, PacketType},
     replay_protection::ReplayProtection,
+    session_timeout,
     token::PrivateConnectToken,
-    ClientID, NetcodeError, NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_KEY_BYTES, NETCODE_MAC_BYTES,
-    NETCODE_MAX_CLIENTS, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_MAX_PENDING_CLIENTS, NETCODE_SEND_RATE,
+    ClientID, NetcodeError, NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_DISCONNECT_CONFIRM_WINDOW,
+    NETCODE_KEY_BYTES, NETCODE_MAC_BYTES, NETCODE_MAX_CLIENTS, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES,
+    NETCODE_MAX_PENDING_CLIENTS, NETCODE_MAX_REPLAY_PROTECTION_WINDOW, NETCODE_REPLAY_PROTECTION_WINDOW, NETCODE_SEND_RATE,
     NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
 };
 
@@ -48,6 +50,8 @@ struct ConnectTokenEntry {
 pub struct NetcodeServer {
     clients: Box<[Option<Connection>]>,
     pending_clients: HashMap<SocketAddr, Connection>,
+    // Clients that requested to disconnect, kept to confirm their retried disconnect packets
+    disconnected_clients: HashMap<SocketAddr, Connection>,
     connect_token_entries: Box<[Option<ConnectTokenEntry>; NETCODE_MAX_CLIENTS * 2]>,
     protocol_id: u64,
     connect_key: [u8; NETCODE_KEY_BYTES],
@@ -58,6 +62,11 @@ pub struct NetcodeServer {
     current_time: Duration,
     global_sequence: u64,
     secure: bool,
+    replay_protection_window: usize,
+    keep_alive_padding: usize,
+    respond_to_probes: bool,
+    connect_token_reuse_policy: ConnectTokenReusePolicy,
+    replay_drops: u64,
     out: [u8; NETCODE_MAX_PACKET_BYTES],
 }
 
@@ -85,6 +94,19 @@ pub enum ServerResult<'a, 's> {
     },
 }
 
+/// How the server handles a connect token used from an address other than the one that first used it,
+/// for example when the same token is given to two clients.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectTokenReusePolicy {
+    /// The connection request is ignored, the other client times out in the request step.
+    #[default]
+    Ignore,
+    /// The connection request is denied, the other client is disconnected with
+    /// [DisconnectReason::ConnectTokenAlreadyUsed][crate::DisconnectReason::ConnectTokenAlreadyUsed].
+    /// A request for a client id already connected from another address is also treated as a reused token.
+    Deny,
+}
+
 /// Configuration to establish a secure or unsecure connection with the server.
 pub enum ServerAuthentication {
     /// Establishes a safe connection using a private key for encryption. The private key cannot be
@@ -110,6 +132,48 @@ pub struct ServerConfig {
     pub public_addresses: Vec<SocketAddr>,
     /// Authentication configuration for the server
     pub authentication: ServerAuthentication,
+    /// Number of packet sequences tracked by each connection to protect against replayed packets.
+    /// Packets with a sequence older than this window are dropped, as they can't be checked.
+    /// Widening it accepts legitimate late packets on paths with heavy reordering, at the cost of more
+    /// memory per connection and a larger range of old packets an attacker can attempt to replay.
+    /// Cannot be zero or greater than [NETCODE_MAX_REPLAY_PROTECTION_WINDOW].
+    /// Only the server uses this window, the client tracks the packets of the server with [NETCODE_REPLAY_PROTECTION_WINDOW].
+    /// Use [NETCODE_REPLAY_PROTECTION_WINDOW] for the default value.
+    pub replay_protection_window: usize,
+    /// Number of zeroed bytes added to keep-alive packets, for networks that reap flows with only small packets.
+    /// The padding is encrypted with the packet, so it's indistinguishable from a payload.
+    /// Cannot be greater than [NETCODE_MAX_PAYLOAD_BYTES], use 0 to not pad keep-alive packets.
+    pub keep_alive_padding: usize,
+    /// Whether the server replies to unauthenticated probes sent before connecting,
+    /// used by clients to measure the reachability and round-trip time of each server address.
+    /// The reply has the same size as the probe, so it cannot be used for amplification.
+    pub respond_to_probes: bool,
+    /// How connection requests with a connect token already used from another address are handled.
+    pub connect_token_reuse_policy: ConnectTokenReusePolicy,
+}
+
+impl ServerConfig {
+    /// Creates a server configuration with the default values for the replay protection window,
+    /// the keep-alive padding, the probe replies and the connect token reuse policy.
+    pub fn new(
+        current_time: Duration,
+        max_clients: usize,
+        protocol_id: u64,
+        public_addresses: Vec<SocketAddr>,
+        authentication: ServerAuthentication,
+    ) -> Self {
+        Self {
+            current_time,
+            max_clients,
+            protocol_id,
+            public_addresses,
+            authentication,
+            replay_protection_window: NETCODE_REPLAY_PROTECTION_WINDOW,
+            keep_alive_padding: 0,
+            respond_to_probes: false,
+            connect_token_reuse_policy: ConnectTokenReusePolicy::default(),
+        }
+    }
 }
 
 impl NetcodeServer {
@@ -119,6 +183,18 @@ impl NetcodeServer {
             //       only using for token entries
             panic!("The max clients allowed is {}", NETCODE_MAX_CLIENTS);
         }
+        if config.replay_protection_window == 0 {
+            panic!("The replay protection window cannot be zero");
+        }
+        if config.replay_protection_window > NETCODE_MAX_REPLAY_PROTECTION_WINDOW {
+            panic!(
+                "The replay protection window cannot be greater than {}",
+                NETCODE_MAX_REPLAY_PROTECTION_WINDOW
+            );
+        }
+        if config.keep_alive_padding > NETCODE_MAX_PAYLOAD_BYTES {
+            panic!("The keep alive padding cannot be greater than {}", NETCODE_MAX_PAYLOAD_BYTES);
+        }
         let challenge_key = generate_random_bytes();
         let clients = vec![None; config.max_clients].into_boxed_slice();
 
@@ -136,6 +212,7 @@ impl NetcodeServer {
             clients,
             connect_token_entries: Box::new([None; NETCODE_MAX_CLIENTS * 2]),
             pending_clients: HashMap::new(),
+            disconnected_clients: HashMap::new(),
             protocol_id: config.protocol_id,
             connect_key,
             max_clients: config.max_clients,
@@ -145,19 +222,24 @@ impl NetcodeServer {
             public_addresses: config.public_addresses,
             current_time: config.current_time,
             secure,
+            replay_protection_window: config.replay_protection_window,
+            keep_alive_padding: config.keep_alive_padding,
+            respond_to_probes: config.respond_to_probes,
+            connect_token_reuse_policy: config.connect_token_reuse_policy,
+            replay_drops: 0,
             out: [0u8; NETCODE_MAX_PACKET_BYTES],
         }
     }
 
     #[doc(hidden)]
     pub fn __test() -> Self {
-        let config = ServerConfig {
-            current_time: Duration::ZERO,
-            max_clients: 32,
-            protocol_id: 0,
-            public_addresses: vec!["127.0.0.1:0".parse().unwrap()],
-            authentication: ServerAuthentication::Unsecure,
-        };
+        let config = ServerConfig::new(
+            Duration::ZERO,
+            32,
+            0,
+            vec!["127.0.0.1:0".parse().unwrap()],
+            ServerAuthentication::Unsecure,
+        );
         Self::new(config)
     }
 
@@ -169,6 +251,19 @@ impl NetcodeServer {
         self.current_time
     }
 
+    /// Returns the number of packets dropped by the replay protection,
+    /// because their sequence was already received or too old.
+    /// A steady increase might indicate that the replay protection window is too narrow
+    /// for the reordering in the network, see [ServerConfig::replay_protection_window].
+    pub fn replay_drops(&self) -> u64 {
+        self.replay_drops
+    }
+
+    /// Resets the number of packets dropped by the replay protection to zero.
+    pub fn reset_replay_drops(&mut self) {
+        self.replay_drops = 0;
+    }
+
     fn find_or_add_connect_token_entry(&mut self, new_entry: ConnectTokenEntry) -> bool {
         let mut min = Duration::MAX;
         let mut oldest_entry = 0;
@@ -223,6 +318,30 @@ impl NetcodeServer {
         None
     }
 
+    /// Returns the time without receiving packets before the connected client times out, as set in its connect token.
+    /// Returns [Duration::MAX] if the connect token disables the timeout.
+    pub fn session_timeout(&self, client_id: ClientID) -> Option<Duration> {
+        if let Some(client) = find_client_by_id(&self.clients, client_id) {
+            return Some(session_timeout(client.timeout_seconds));
+        }
+
+        None
+    }
+
+    /// Returns the time left without receiving packets before the connected client times out.
+    /// Returns None if the client is not connected or its connect token disables the timeout.
+    pub fn time_until_timeout(&self, client_id: ClientID) -> Option<Duration> {
+        if let Some(client) = find_client_by_id(&self.clients, client_id) {
+            if client.timeout_seconds <= 0 {
+                return None;
+            }
+            let time_since_last_received_packet = self.current_time - client.last_packet_received_time;
+            return Some(session_timeout(client.timeout_seconds).saturating_sub(time_since_last_received_packet));
+        }
+
+        None
+    }
+
     /// Returns the client address if connected.
     pub fn client_addr(&self, client_id: ClientID) -> Option<SocketAddr> {
         if let Some(client) = find_client_by_id(&self.clients, client_id) {
@@ -232,6 +351,20 @@ impl NetcodeServer {
         None
     }
 
+    fn deny_reused_connect_token<'a>(
+        &mut self,
+        addr: SocketAddr,
+        server_to_client_key: &[u8; NETCODE_KEY_BYTES],
+    ) -> Result<ServerResult<'a, '_>, NetcodeError> {
+        let packet = Packet::ConnectTokenAlreadyUsed;
+        let len = packet.encode(&mut self.out, self.protocol_id, Some((self.global_sequence, server_to_client_key)))?;
+        self.global_sequence += 1;
+        Ok(ServerResult::PacketToSend {
+            addr,
+            payload: &mut self.out[..len],
+        })
+    }
+
     fn handle_connection_request<'a>(
         &mut self,
         addr: SocketAddr,
@@ -276,6 +409,9 @@ impl NetcodeServer {
                 connect_token.client_id,
                 addr
             );
+            if id_already_connected && !addr_already_connected && self.connect_token_reuse_policy == ConnectTokenReusePolicy::Deny {
+                return self.deny_reused_connect_token(addr, &connect_token.server_to_client_key);
+            }
             return Ok(ServerResult::None);
         }
 
@@ -297,6 +433,9 @@ impl NetcodeServer {
 
         if !self.find_or_add_connect_token_entry(connect_token_entry) {
             log::warn!("Connection request denied: unable to add connect token entry");
+            if self.connect_token_reuse_policy == ConnectTokenReusePolicy::Deny {
+                return self.deny_reused_connect_token(addr, &connect_token.server_to_client_key);
+            }
             return Ok(ServerResult::None);
         }
 
@@ -345,7 +484,7 @@ impl NetcodeServer {
             timeout_seconds: connect_token.timeout_seconds,
             expire_timestamp,
             user_data: connect_token.user_data,
-            replay_protection: ReplayProtection::new(),
+            replay_protection: ReplayProtection::new(self.replay_protection_window),
         });
         pending.last_packet_received_time = self.current_time;
         pending.last_packet_send_time = self.current_time;
@@ -356,6 +495,39 @@ impl NetcodeServer {
         })
     }
 
+    fn handle_probe<'a>(
+        &mut self,
+        addr: SocketAddr,
+        version_info: [u8; 13],
+        protocol_id: u64,
+        token: u64,
+    ) -> Result<ServerResult<'a, '_>, NetcodeError> {
+        if !self.respond_to_probes {
+            log::trace!("Ignored probe from {}, probes are disabled", addr);
+            return Ok(ServerResult::None);
+        }
+
+        if version_info != *NETCODE_VERSION_INFO {
+            return Err(NetcodeError::InvalidVersion);
+        }
+
+        if protocol_id != self.protocol_id {
+            return Err(NetcodeError::InvalidProtocolID);
+        }
+
+        let packet = Packet::ProbeReply {
+            version_info,
+            protocol_id,
+            token,
+        };
+        let len = packet.encode(&mut self.out, self.protocol_id, None)?;
+
+        Ok(ServerResult::PacketToSend {
+            addr,
+            payload: &mut self.out[..len],
+        })
+    }
+
     /// Returns an encoded packet payload to be sent to the client
     pub fn generate_payload_packet<'s>(
         &'s mut self,
@@ -382,6 +554,10 @@ impl NetcodeServer {
     /// [ServerResult].
     pub fn process_packet<'a, 's>(&'s mut self, addr: SocketAddr, buffer: &'a mut [u8]) -> ServerResult<'a, 's> {
         match self.process_packet_internal(addr, buffer) {
+            Err(NetcodeError::DuplicatedSequence) => {
+                log::debug!("Dropped packet from {} with duplicated or too old sequence", addr);
+                ServerResult::None
+            }
             Err(e) => {
                 log::error!("Failed to process packet: {}", e);
                 ServerResult::None
@@ -402,7 +578,13 @@ impl NetcodeServer {
                 self.protocol_id,
                 Some(&client.receive_key),
                 Some(&mut client.replay_protection),
-            )?;
+            )
+            .map_err(|e| {
+                if matches!(e, NetcodeError::DuplicatedSequence) {
+                    self.replay_drops += 1;
+                }
+                e
+            })?;
             log::trace!(
                 "Received packet from connected client ({}): {:?}",
                 client.client_id,
@@ -415,12 +597,18 @@ impl NetcodeServer {
                     Packet::Disconnect => {
                         client.state = ConnectionState::Disconnected;
                         let client_id = client.client_id;
-                        self.clients[slot] = None;
+                        let mut client = self.clients[slot].take().unwrap();
                         log::trace!("Client {} requested to disconnect", client_id);
+
+                        // Confirm the disconnect, so the client knows it doesn't need to retry it
+                        let len = Packet::Disconnect.encode(&mut self.out, self.protocol_id, Some((client.sequence, &client.send_key)))?;
+                        client.sequence += 1;
+                        client.last_packet_received_time = self.current_time;
+                        self.disconnected_clients.insert(addr, client);
                         return Ok(ServerResult::ClientDisconnected {
                             client_id,
                             addr,
-                            payload: None,
+                            payload: Some(&mut self.out[..len]),
                         });
                     }
                     Packet::Payload(payload) => {
@@ -453,7 +641,13 @@ impl NetcodeServer {
                 self.protocol_id,
                 Some(&pending.receive_key),
                 Some(&mut pending.replay_protection),
-            )?;
+            )
+            .map_err(|e| {
+                if matches!(e, NetcodeError::DuplicatedSequence) {
+                    self.replay_drops += 1;
+                }
+                e
+            })?;
             pending.last_packet_received_time = self.current_time;
             log::trace!("Received packet from pending client ({}): {:?}", addr, packet.packet_type());
             match packet {
@@ -500,7 +694,12 @@ impl NetcodeServer {
                                 max_clients: self.max_clients as u32,
                                 client_index: client_index as u32,
                             };
-                            let len = packet.encode(&mut self.out, self.protocol_id, Some((pending.sequence, &pending.send_key)))?;
+                            let len = packet.encode_with_padding(
+                                &mut self.out,
+                                self.protocol_id,
+                                Some((pending.sequence, &pending.send_key)),
+                                self.keep_alive_padding,
+                            )?;
                             pending.sequence += 1;
 
                             let client_id: ClientID = pending.client_id;
@@ -520,6 +719,27 @@ impl NetcodeServer {
             }
         }
 
+        // Handle retried disconnect from a client already disconnected
+        if matches!(Packet::peek_type(buffer), Ok(PacketType::Disconnect)) {
+            if let Some(client) = self.disconnected_clients.get_mut(&addr) {
+                let (_, packet) = Packet::decode(
+                    buffer,
+                    self.protocol_id,
+                    Some(&client.receive_key),
+                    Some(&mut client.replay_protection),
+                )?;
+                if packet == Packet::Disconnect {
+                    let len = Packet::Disconnect.encode(&mut self.out, self.protocol_id, Some((client.sequence, &client.send_key)))?;
+                    client.sequence += 1;
+                    return Ok(ServerResult::PacketToSend {
+                        addr,
+                        payload: &mut self.out[..len],
+                    });
+                }
+            }
+            return Ok(ServerResult::None);
+        }
+
         // Handle new client
         let (_, packet) = Packet::decode(buffer, self.protocol_id, None, None)?;
         match packet {
@@ -530,7 +750,13 @@ impl NetcodeServer {
                 xnonce,
                 version_info,
             } => self.handle_connection_request(addr, version_info, protocol_id, expire_timestamp, xnonce, data),
-            _ => unreachable!("Decoding packet without key can only return ConnectionRequest packets"),
+            Packet::Probe {
+                version_info,
+                protocol_id,
+                token,
+            } => self.handle_probe(addr, version_info, protocol_id, token),
+            Packet::ProbeReply { .. } => Ok(ServerResult::None),
+            _ => unreachable!("Decoding packet without key can only return unencrypted packets"),
         }
     }
 
@@ -574,6 +800,10 @@ impl NetcodeServer {
         }
 
         self.pending_clients.retain(|_, c| c.state != ConnectionState::Disconnected);
+
+        let current_time = self.current_time;
+        self.disconnected_clients
+            .retain(|_, c| current_time - c.last_packet_received_time < NETCODE_DISCONNECT_CONFIRM_WINDOW);
     }
 
     /// Updates the client, returns a ServerResult.
@@ -636,7 +866,12 @@ impl NetcodeServer {
                     max_clients: self.max_clients as u32,
                 };
 
-                let len = match packet.encode(&mut self.out, self.protocol_id, Some((client.sequence, &client.send_key))) {
+                let len = match packet.encode_with_padding(
+                    &mut self.out,
+                    self.protocol_id,
+                    Some((client.sequence, &client.send_key)),
+                    self.keep_alive_padding,
+                ) {
                     Err(e) => {
                         log::error!("Failed to encode keep alive packet: {}", e);
                         return ServerResult::None;
@@ -714,7 +949,7 @@ fn find_client_mut_by_addr(clients: &mut [Option<Connection>], addr: SocketAddr)
 
 #[cfg(test)]
 mod tests {
-    use crate::{client::NetcodeClient, token::ConnectToken, ClientAuthentication};
+    use crate::{client::NetcodeClient, token::ConnectToken, ClientAuthentication, DisconnectReason};
 
     use super::*;
 
@@ -722,16 +957,30 @@ mod tests {
     const TEST_PROTOCOL_ID: u64 = 7;
 
     fn new_server() -> NetcodeServer {
-        let config = ServerConfig {
-            current_time: Duration::ZERO,
-            max_clients: 16,
-            protocol_id: TEST_PROTOCOL_ID,
-            public_addresses: vec!["127.0.0.1:5000".parse().unwrap()],
-            authentication: ServerAuthentication::Secure { private_key: *TEST_KEY },
-        };
+        let config = ServerConfig::new(
+            Duration::ZERO,
+            16,
+            TEST_PROTOCOL_ID,
+            vec!["127.0.0.1:5000".parse().unwrap()],
+            ServerAuthentication::Secure { private_key: *TEST_KEY },
+        );
         NetcodeServer::new(config)
     }
 
+    #[test]
+    #[should_panic]
+    fn replay_protection_window_above_max() {
+        let mut config = ServerConfig::new(
+            Duration::ZERO,
+            16,
+            TEST_PROTOCOL_ID,
+            vec!["127.0.0.1:5000".parse().unwrap()],
+            ServerAuthentication::Unsecure,
+        );
+        config.replay_protection_window = NETCODE_MAX_REPLAY_PROTECTION_WINDOW + 1;
+        NetcodeServer::new(config);
+    }
+
     #[test]
     fn server_connection() {
         let mut server = new_server();
@@ -848,4 +1097,139 @@ mod tests {
         // Don't allow same token with different address
         assert!(!server.find_or_add_connect_token_entry(connect_token));
     }
+
+    #[test]
+    fn server_probe() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+        let len = Packet::probe(TEST_PROTOCOL_ID, 42)
+            .encode(&mut buffer, TEST_PROTOCOL_ID, None)
+            .unwrap();
+
+        // Probes are ignored unless enabled
+        assert_eq!(server.process_packet(client_addr, &mut buffer[..len]), ServerResult::None);
+
+        server.respond_to_probes = true;
+        match server.process_packet(client_addr, &mut buffer[..len]) {
+            ServerResult::PacketToSend { addr, payload } => {
+                assert_eq!(addr, client_addr);
+                assert_eq!(payload.len(), len);
+                let (_, reply) = Packet::decode(payload, TEST_PROTOCOL_ID, None, None).unwrap();
+                assert!(matches!(reply, Packet::ProbeReply { token: 42, .. }));
+            }
+            _ => unreachable!(),
+        }
+
+        // Probes with other protocols are not answered
+        let len = Packet::probe(TEST_PROTOCOL_ID + 1, 42)
+            .encode(&mut buffer, TEST_PROTOCOL_ID, None)
+            .unwrap();
+        assert_eq!(server.process_packet(client_addr, &mut buffer[..len]), ServerResult::None);
+    }
+
+    #[test]
+    fn connect_token_reuse_policy() {
+        let mut server = new_server();
+        server.connect_token_reuse_policy = ConnectTokenReusePolicy::Deny;
+        let connect_token = ConnectToken::generate(Duration::ZERO, TEST_PROTOCOL_ID, 30, 4, 5, server.addresses(), None, TEST_KEY).unwrap();
+
+        let first_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut first_client = NetcodeClient::new(
+            Duration::ZERO,
+            ClientAuthentication::Secure {
+                connect_token: connect_token.clone(),
+            },
+        )
+        .unwrap();
+        let (packet, _) = first_client.update(Duration::ZERO).unwrap();
+        assert!(matches!(
+            server.process_packet(first_addr, packet),
+            ServerResult::PacketToSend { .. }
+        ));
+
+        let second_addr: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        let mut second_client = NetcodeClient::new(
+            Duration::ZERO,
+            ClientAuthentication::Secure {
+                connect_token: connect_token.clone(),
+            },
+        )
+        .unwrap();
+        let (packet, _) = second_client.update(Duration::ZERO).unwrap();
+        match server.process_packet(second_addr, packet) {
+            ServerResult::PacketToSend { addr, payload } => {
+                assert_eq!(addr, second_addr);
+                assert!(second_client.process_packet(payload).is_none());
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(second_client.disconnect_reason(), Some(DisconnectReason::ConnectTokenAlreadyUsed));
+        assert!(!first_client.is_disconnected());
+
+        // Ignored with the default policy
+        server.connect_token_reuse_policy = ConnectTokenReusePolicy::Ignore;
+        let mut third_client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        let (packet, _) = third_client.update(Duration::ZERO).unwrap();
+        assert_eq!(server.process_packet(second_addr, packet), ServerResult::None);
+    }
+
+    #[test]
+    fn disconnect_confirmation() {
+        let mut server = new_server();
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            TEST_PROTOCOL_ID,
+            30,
+            client_id,
+            5,
+            server.addresses(),
+            None,
+            TEST_KEY,
+        )
+        .unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+
+        let (packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, packet) {
+            ServerResult::PacketToSend { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        let (packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, packet) {
+            ServerResult::ClientConnected { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        assert!(client.is_connected());
+
+        // First disconnect is lost
+        client.disconnect().unwrap();
+        assert!(!client.disconnect_confirmed());
+
+        let (_, packet) = client.disconnect().unwrap();
+        match server.process_packet(client_addr, packet) {
+            ServerResult::ClientDisconnected { payload, .. } => {
+                // Confirmation is lost
+                assert!(payload.is_some());
+            }
+            _ => unreachable!(),
+        }
+        assert!(!server.is_client_connected(client_id));
+
+        // Retries are confirmed while the server remembers the client
+        let (_, packet) = client.disconnect().unwrap();
+        match server.process_packet(client_addr, packet) {
+            ServerResult::PacketToSend { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        assert!(client.disconnect_confirmed());
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::DisconnectedByClient));
+
+        server.update(NETCODE_DISCONNECT_CONFIRM_WINDOW);
+        let (_, packet) = client.disconnect().unwrap();
+        assert_eq!(server.process_packet(client_addr, packet), ServerResult::None);
+    }
 }
@@ -10,8 +10,8 @@ use crate::{
     crypto::{dencrypted_in_place_xnonce, encrypt_in_place_xnonce, generate_random_bytes},
     serialize::*,
     NetcodeError, NETCODE_ADDITIONAL_DATA_SIZE, NETCODE_ADDRESS_IPV4, NETCODE_ADDRESS_IPV6, NETCODE_ADDRESS_NONE,
-    NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_KEY_BYTES, NETCODE_TIMEOUT_SECONDS,
-    NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
+    NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_KEY_BYTES, NETCODE_USER_DATA_BYTES,
+    NETCODE_VERSION_INFO,
 };
 use chacha20poly1305::aead::Error as CryptoError;
 
@@ -82,9 +82,23 @@ impl fmt::Display for TokenGenerationError {
     }
 }
 
+/// Values that are randomly generated for a connect token.
+/// Fixing them makes the generated tokens reproducible, for golden-byte tests or to compare
+/// against known vectors of other netcode implementations. Never use fixed values in production.
+#[cfg(feature = "test-util")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectTokenRandomness {
+    /// Nonce used to encrypt the private data of the token.
+    pub xnonce: [u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
+    pub client_to_server_key: [u8; NETCODE_KEY_BYTES],
+    pub server_to_client_key: [u8; NETCODE_KEY_BYTES],
+}
+
 impl ConnectToken {
     /// Generate a token to be sent to an client. The user data is available to the server after an
     /// successfull conection. The private key and the protocol id must be the same used in server.
+    /// The timeout is the keep-alive timeout of the connection, used by both the client and the server,
+    /// the client can connect with a shorter one with [NetcodeClient::set_connect_timeout][crate::NetcodeClient::set_connect_timeout].
     #[allow(clippy::too_many_arguments)]
     pub fn generate(
         current_time: Duration,
@@ -95,12 +109,67 @@ impl ConnectToken {
         server_addresses: Vec<SocketAddr>,
         user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
         private_key: &[u8; NETCODE_KEY_BYTES],
+    ) -> Result<Self, TokenGenerationError> {
+        Self::generate_internal(
+            current_time,
+            protocol_id,
+            expire_seconds,
+            client_id,
+            timeout_seconds,
+            server_addresses,
+            user_data,
+            private_key,
+            generate_random_bytes(),
+            (generate_random_bytes(), generate_random_bytes()),
+        )
+    }
+
+    /// Generate a token like [ConnectToken::generate], but with the given nonce and keys instead of random ones.
+    /// If no user data is passed, it's zeroed instead of random.
+    #[cfg(feature = "test-util")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_with_randomness(
+        current_time: Duration,
+        protocol_id: u64,
+        expire_seconds: u64,
+        client_id: u64,
+        timeout_seconds: i32,
+        server_addresses: Vec<SocketAddr>,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+        private_key: &[u8; NETCODE_KEY_BYTES],
+        randomness: ConnectTokenRandomness,
+    ) -> Result<Self, TokenGenerationError> {
+        Self::generate_internal(
+            current_time,
+            protocol_id,
+            expire_seconds,
+            client_id,
+            timeout_seconds,
+            server_addresses,
+            Some(user_data.unwrap_or(&[0; NETCODE_USER_DATA_BYTES])),
+            private_key,
+            randomness.xnonce,
+            (randomness.client_to_server_key, randomness.server_to_client_key),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn generate_internal(
+        current_time: Duration,
+        protocol_id: u64,
+        expire_seconds: u64,
+        client_id: u64,
+        timeout_seconds: i32,
+        server_addresses: Vec<SocketAddr>,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+        private_key: &[u8; NETCODE_KEY_BYTES],
+        xnonce: [u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
+        keys: ([u8; NETCODE_KEY_BYTES], [u8; NETCODE_KEY_BYTES]),
     ) -> Result<Self, TokenGenerationError> {
         let expire_timestamp = current_time.as_secs() + expire_seconds;
 
-        let private_connect_token = PrivateConnectToken::generate(client_id, timeout_seconds, server_addresses, user_data)?;
+        let private_connect_token = PrivateConnectToken::generate(client_id, timeout_seconds, server_addresses, user_data, keys)?;
         let mut private_data = [0u8; NETCODE_CONNECT_TOKEN_PRIVATE_BYTES];
-        let xnonce = generate_random_bytes();
         private_connect_token.encode(&mut private_data, protocol_id, expire_timestamp, &xnonce, private_key)?;
 
         Ok(Self {
@@ -114,7 +183,7 @@ impl ConnectToken {
             server_addresses: private_connect_token.server_addresses,
             client_to_server_key: private_connect_token.client_to_server_key,
             server_to_client_key: private_connect_token.server_to_client_key,
-            timeout_seconds: NETCODE_TIMEOUT_SECONDS,
+            timeout_seconds,
         })
     }
 
@@ -174,6 +243,7 @@ impl PrivateConnectToken {
         timeout_seconds: i32,
         server_addresses: Vec<SocketAddr>,
         user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+        (client_to_server_key, server_to_client_key): ([u8; NETCODE_KEY_BYTES], [u8; NETCODE_KEY_BYTES]),
     ) -> Result<Self, TokenGenerationError> {
         if server_addresses.len() > 32 {
             return Err(TokenGenerationError::MaxHostCount);
@@ -187,9 +257,6 @@ impl PrivateConnectToken {
             server_addresses_arr[i] = Some(addr);
         }
 
-        let client_to_server_key = generate_random_bytes();
-        let server_to_client_key = generate_random_bytes();
-
         let user_data = match user_data {
             Some(data) => *data,
             None => generate_random_bytes(),
@@ -350,7 +417,14 @@ mod tests {
     #[test]
     fn private_connect_token_serialization() {
         let hosts: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap(), "127.0.0.2:3000".parse().unwrap()];
-        let token = PrivateConnectToken::generate(1, 5, hosts, Some(&generate_random_bytes())).unwrap();
+        let token = PrivateConnectToken::generate(
+            1,
+            5,
+            hosts,
+            Some(&generate_random_bytes()),
+            (generate_random_bytes(), generate_random_bytes()),
+        )
+        .unwrap();
         let mut buffer: Vec<u8> = vec![];
 
         token.write(&mut buffer).unwrap();
@@ -362,7 +436,14 @@ mod tests {
     #[test]
     fn private_connect_token_encode_decode() {
         let hosts: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap(), "127.0.0.2:3000".parse().unwrap()];
-        let token = PrivateConnectToken::generate(1, 5, hosts, Some(&generate_random_bytes())).unwrap();
+        let token = PrivateConnectToken::generate(
+            1,
+            5,
+            hosts,
+            Some(&generate_random_bytes()),
+            (generate_random_bytes(), generate_random_bytes()),
+        )
+        .unwrap();
         let key = b"an example very very secret key."; // 32-bytes
         let protocol_id = 12;
         let expire_timestamp = 0;
@@ -416,4 +497,43 @@ mod tests {
         assert_eq!(token.client_to_server_key, private.client_to_server_key);
         assert_eq!(token.server_to_client_key, private.server_to_client_key);
     }
+
+    #[test]
+    #[cfg(feature = "test-util")]
+    fn connect_token_with_randomness() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let private_key = b"an example very very secret key."; // 32-bytes
+        let randomness = ConnectTokenRandomness {
+            xnonce: [1; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
+            client_to_server_key: [2; NETCODE_KEY_BYTES],
+            server_to_client_key: [3; NETCODE_KEY_BYTES],
+        };
+        let generate = || {
+            let token = ConnectToken::generate_with_randomness(
+                Duration::ZERO,
+                2,
+                3,
+                4,
+                5,
+                server_addresses.clone(),
+                None,
+                private_key,
+                randomness.clone(),
+            )
+            .unwrap();
+            let mut buffer: Vec<u8> = vec![];
+            token.write(&mut buffer).unwrap();
+            buffer
+        };
+
+        let buffer = generate();
+        assert_eq!(buffer, generate());
+
+        let token = ConnectToken::read(&mut buffer.as_slice()).unwrap();
+        assert_eq!(token.xnonce, randomness.xnonce);
+        let private = PrivateConnectToken::decode(&token.private_data, 2, token.expire_timestamp, &token.xnonce, private_key).unwrap();
+        assert_eq!(private.client_to_server_key, randomness.client_to_server_key);
+        assert_eq!(private.server_to_client_key, randomness.server_to_client_key);
+        assert_eq!(private.user_data, [0; NETCODE_USER_DATA_BYTES]);
+    }
 }